@@ -0,0 +1,216 @@
+//! Measures cost of one frame of landscape subsidence.
+//!
+//! Run with `cargo bench --bench landscape`.
+use std::time::{Duration, Instant};
+
+use tank_war::clock::Clock;
+use tank_war::landscape::{Landscape, Material, Terrain};
+use tank_war::texture::Textures;
+use tank_war::G;
+
+const WIDTH: u16 = 1918;
+const HEIGHT: u16 = 1078;
+/// The same as in `tank_war::landscape`.
+const TIME_SCALE: f32 = 3.0;
+
+/// Creates landscape with large cavern under the ground.
+fn landscape_with_cavern(width: u16, height: u16) -> Landscape {
+    let mut landscape = Landscape::new(width, height).unwrap();
+    landscape.set_seed(42);
+    landscape.generate(Terrain::Hills);
+    let (w, h) = (i32::from(width), i32::from(height));
+    let (left, top) = (w / 5, h * 2 / 3);
+    let (cavern_width, cavern_height) = (w / 2, h / 4);
+    for y in top..top + cavern_height {
+        let pixels = landscape
+            .get_pixels_line_mut((left, y), cavern_width as u16)
+            .unwrap();
+        pixels.iter_mut().for_each(|c| *c = 0);
+    }
+    landscape.set_rect_changed(left, top, cavern_width, cavern_height);
+    landscape
+}
+
+/// Runs subsidence until its end and returns count of frames,
+/// time spent to update of landscape and its RGBA buffer and
+/// count of pixels which have to be uploaded into texture.
+fn subsidence<F>(update_rgba: F) -> (u32, Duration, Duration, usize)
+where
+    F: Fn(&mut Landscape, &Textures) -> usize,
+{
+    let textures = Textures::default();
+    let mut landscape = landscape_with_cavern(WIDTH, HEIGHT);
+    landscape.rgba_buffer(&textures);
+
+    let mut clock = Clock::new();
+    let mut frames = 0;
+    let mut update_time = Duration::default();
+    let mut rgba_time = Duration::default();
+    let mut uploaded = 0;
+    landscape.subsidence(clock.now());
+    loop {
+        clock.tick();
+        let started = Instant::now();
+        let finished = landscape.update(clock.now());
+        let updated = Instant::now();
+        uploaded += update_rgba(&mut landscape, &textures);
+        rgba_time += updated.elapsed();
+        update_time += updated - started;
+        frames += 1;
+        if finished {
+            break;
+        }
+    }
+    (frames, update_time, rgba_time, uploaded)
+}
+
+fn report(name: &str, update_rgba: impl Fn(&mut Landscape, &Textures) -> usize) {
+    println!("{}:", name);
+    let (frames, update_time, rgba_time, uploaded) = subsidence(update_rgba);
+    println!("  frames: {}", frames);
+    println!(
+        "  uploaded pixels per frame: {}",
+        uploaded / frames as usize
+    );
+    println!("  subsidence per frame: {:?}", update_time / frames);
+    println!("  RGBA buffer per frame: {:?}", rgba_time / frames);
+}
+
+/// Previous algorithm of subsidence. It moves every falling pixel
+/// down by one row per step and scans all rows for every step.
+struct RowSubsidence {
+    width: usize,
+    height: usize,
+    skip: usize,
+    take: usize,
+}
+
+impl RowSubsidence {
+    /// Makes given count of steps and returns `true` if nothing
+    /// has been moved.
+    fn update(&mut self, buffer: &mut [u8], steps: u32) -> bool {
+        let stride = self.width;
+        for _ in 0..steps {
+            let start = self.skip;
+            let end = (start + self.take).min(stride);
+            let mut cur_row_index = stride * self.height;
+            let mut left_changed_pos = stride;
+            let mut right_changed_pos = 0;
+
+            for _ in 1..self.height {
+                cur_row_index -= stride;
+                let (top_rows, current_row) = buffer.split_at_mut(cur_row_index);
+                let (_, top_row) = top_rows.split_at_mut(cur_row_index - stride);
+                for x in start..end {
+                    let top_pixel = top_row[x];
+                    if top_pixel == Material::Empty as u8 || top_pixel == Material::Bedrock as u8 {
+                        continue;
+                    }
+                    let target = if current_row[x] == Material::Empty as u8 {
+                        x
+                    } else if top_pixel == Material::Sand as u8 {
+                        let is_free = |x: usize| top_row[x] == 0 && current_row[x] == 0;
+                        if x > 0 && is_free(x - 1) {
+                            x - 1
+                        } else if x + 1 < stride && is_free(x + 1) {
+                            x + 1
+                        } else {
+                            continue;
+                        }
+                    } else {
+                        continue;
+                    };
+                    current_row[target] = top_pixel;
+                    top_row[x] = 0;
+                    left_changed_pos = left_changed_pos.min(target.min(x));
+                    right_changed_pos = right_changed_pos.max(target.max(x));
+                }
+            }
+
+            if left_changed_pos > right_changed_pos {
+                return true;
+            }
+            self.skip = left_changed_pos.saturating_sub(1);
+            self.take = (right_changed_pos + 2).min(stride) - self.skip;
+        }
+        false
+    }
+}
+
+/// Compares previous and current algorithms of subsidence
+/// on identical landscapes.
+fn compare_algorithms(width: u16, height: u16) {
+    println!("Subsidence algorithms on {}x{} landscape", width, height);
+    let mut landscape = landscape_with_cavern(width, height);
+    let mut buffer = landscape.buffer().to_vec();
+
+    let mut old = RowSubsidence {
+        width: width as usize,
+        height: height as usize,
+        skip: 0,
+        take: width as usize,
+    };
+    let mut clock = Clock::new();
+    let mut last_pos = 0;
+    let mut frames = 0;
+    let mut max_time = Duration::default();
+    let started = Instant::now();
+    loop {
+        clock.tick();
+        let time = clock.now();
+        let pos = (G * time * time * TIME_SCALE).round() as u32;
+        let frame_started = Instant::now();
+        let finished = old.update(&mut buffer, pos - last_pos);
+        max_time = max_time.max(frame_started.elapsed());
+        last_pos = pos;
+        frames += 1;
+        if finished {
+            break;
+        }
+    }
+    let total = started.elapsed();
+    println!("Row by row:");
+    println!("  frames: {}", frames);
+    println!("  per frame: {:?}, max: {:?}", total / frames, max_time);
+
+    let mut clock = Clock::new();
+    let mut frames = 0;
+    let mut max_time = Duration::default();
+    let started = Instant::now();
+    landscape.subsidence(clock.now());
+    loop {
+        clock.tick();
+        let frame_started = Instant::now();
+        let finished = landscape.update(clock.now());
+        max_time = max_time.max(frame_started.elapsed());
+        frames += 1;
+        if finished {
+            break;
+        }
+    }
+    let total = started.elapsed();
+    println!("Column by column:");
+    println!("  frames: {}", frames);
+    println!("  per frame: {:?}, max: {:?}", total / frames, max_time);
+}
+
+fn main() {
+    println!("Subsidence of {}x{} landscape", WIDTH, HEIGHT);
+    report("Full redraw", |landscape, textures| {
+        if !landscape.changed() {
+            return 0;
+        }
+        landscape.set_changed();
+        landscape.rgba_buffer(textures).len() / 4
+    });
+    report("Redraw of dirty rectangles", |landscape, textures| {
+        let rects = landscape.update_rgba_buffer(textures);
+        rects
+            .iter()
+            .map(|rect| landscape.rgba_pixels(rect).len() / 4)
+            .sum()
+    });
+
+    compare_algorithms(WIDTH, HEIGHT);
+    compare_algorithms(WIDTH * 2, HEIGHT * 2);
+}
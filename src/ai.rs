@@ -0,0 +1,280 @@
+//! Computer-controlled players.
+use cgmath::MetricSpace;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::item::Item;
+use crate::player::Player;
+use crate::round::{GameState, Round};
+use crate::shield::ShieldKind;
+use crate::shop::{self, Offer, PRICE_LIST};
+use crate::types::Point2;
+use crate::weapon::Weapon;
+
+/// Max change of angle (in degrees) and power of gun during one update.
+const AIMING_SPEED: f32 = 1.0;
+/// Count of updates before a bot starts to move the gun.
+const THINKING_TICKS: u32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Returns max errors of angle and power of gun.
+    fn aiming_error(self) -> (f32, f32) {
+        match self {
+            Difficulty::Easy => (8.0, 10.0),
+            Difficulty::Normal => (3.0, 4.0),
+            Difficulty::Hard => (0.5, 1.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aim {
+    pub angle: f32,
+    pub power: f32,
+}
+
+/// Finds angle and power of gun of current tank to hit a closest enemy.
+///
+/// Aim is searched by simulation of shots on a coarse grid of angles and
+/// powers and a fine grid around the best of them.
+pub fn find_aim(round: &Round) -> Option<Aim> {
+    let tank = round.tanks.get(round.current_tank)?;
+    let own_pos = tank.center();
+    let radius = tank.weapon.explosion_radius();
+    let targets: Vec<Point2> = round
+        .live_tanks()
+        .filter(|t| t.player_number != tank.player_number)
+        .map(|t| t.center())
+        .collect();
+    if targets.is_empty() {
+        return None;
+    }
+
+    let miss_distance = |angle: f32, power: f32| -> f32 {
+        match round.simulate_shot(angle, power) {
+            Some(pos) => {
+                let distance = targets
+                    .iter()
+                    .map(|t| t.distance(pos))
+                    .fold(f32::MAX, f32::min);
+                if own_pos.distance(pos) < radius {
+                    // Don't hurt yourself
+                    distance + 10_000.
+                } else {
+                    distance
+                }
+            }
+            None => f32::MAX,
+        }
+    };
+
+    let mut best = (
+        f32::MAX,
+        Aim {
+            angle: 0.,
+            power: 0.,
+        },
+    );
+    let check = |best: &mut (f32, Aim), angle: f32, power: f32| {
+        if !(-90. ..=90.).contains(&angle) || !(0. ..=100.).contains(&power) {
+            return;
+        }
+        let distance = miss_distance(angle, power);
+        if distance < best.0 {
+            *best = (distance, Aim { angle, power });
+        }
+    };
+
+    for angle in (-85..=85).step_by(5) {
+        for power in (10..=100).step_by(5) {
+            check(&mut best, angle as f32, power as f32);
+        }
+    }
+    let coarse_aim = best.1;
+    for angle_delta in -4..=4 {
+        for power_delta in -4..=4 {
+            check(
+                &mut best,
+                coarse_aim.angle + angle_delta as f32,
+                coarse_aim.power + power_delta as f32,
+            );
+        }
+    }
+
+    if best.0 < f32::MAX {
+        Some(best.1)
+    } else {
+        None
+    }
+}
+
+/// Spends money of computer player on random goods from the price list.
+pub fn go_shopping<R: Rng>(player: &mut Player, rng: &mut R) {
+    loop {
+        let affordable: Vec<&Offer> = PRICE_LIST
+            .iter()
+            .filter(|o| o.price <= player.money)
+            .collect();
+        match affordable.choose(rng) {
+            Some(offer) => {
+                shop::buy(player, offer);
+            }
+            None => return,
+        }
+    }
+}
+
+/// Repairs damaged tank and puts a shield on it if player has such items.
+fn use_items(round: &mut Round, players: &mut [Player]) {
+    if round.health() <= 50 {
+        round.use_item(Item::RepairKit, players);
+    }
+    let needs_shield = match round.tanks.get(round.current_tank) {
+        Some(tank) => tank.shield.is_none(),
+        None => false,
+    };
+    if needs_shield {
+        let shields = [ShieldKind::Heavy, ShieldKind::Deflector, ShieldKind::Light];
+        for &kind in shields.iter() {
+            if round.use_item(Item::Shield(kind), players) {
+                break;
+            }
+        }
+    }
+}
+
+/// Aims and shoots instead of human player.
+#[derive(Debug, Default)]
+pub struct Bot {
+    target: Option<Aim>,
+    thinking_ticks: u32,
+}
+
+impl Bot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves gun of current tank toward chosen aim and shoots
+    /// when the aim is reached. Must be called before every update
+    /// of round while current tank is controlled by the bot.
+    pub fn update<R: Rng>(
+        &mut self,
+        round: &mut Round,
+        players: &mut [Player],
+        difficulty: Difficulty,
+        rng: &mut R,
+    ) {
+        match round.state {
+            GameState::Aiming => (),
+            _ => {
+                self.target = None;
+                self.thinking_ticks = 0;
+                return;
+            }
+        }
+
+        if self.thinking_ticks < THINKING_TICKS {
+            self.thinking_ticks += 1;
+            return;
+        }
+
+        let target = match self.target {
+            Some(target) => target,
+            None => {
+                use_items(round, players);
+                // The most powerful of available weapons
+                for &weapon in Weapon::ALL.iter().rev() {
+                    if weapon.damage() > 0. && round.select_weapon(weapon, players) {
+                        break;
+                    }
+                }
+                let mut target = find_aim(round).unwrap_or(Aim {
+                    angle: rng.gen_range(-60., 60.),
+                    power: rng.gen_range(30., 90.),
+                });
+                let (angle_error, power_error) = difficulty.aiming_error();
+                target.angle += rng.gen_range(-angle_error, angle_error);
+                target.power += rng.gen_range(-power_error, power_error);
+                target.angle = (target.angle.min(90.).max(-90.) * 10.).round() / 10.;
+                target.power = (target.power.min(100.).max(0.) * 10.).round() / 10.;
+                self.target = Some(target);
+                target
+            }
+        };
+
+        let angle_delta = target.angle - round.gun_angle();
+        let power_delta = target.power - round.gun_power();
+        if angle_delta == 0. && power_delta == 0. {
+            round.shoot(players);
+            self.target = None;
+            self.thinking_ticks = 0;
+        } else {
+            round.inc_gun_angle(angle_delta.min(AIMING_SPEED).max(-AIMING_SPEED));
+            round.inc_gun_power(power_delta.min(AIMING_SPEED).max(-AIMING_SPEED));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg32;
+
+    use super::*;
+    use crate::landscape::{Terrain, TerrainParams};
+    use crate::player::{Control, Player};
+
+    #[test]
+    fn test_find_aim() {
+        let mut players = vec![Player::default(), Player::default()];
+        let mut round =
+            Round::new(800, 600, 2, 3, Terrain::Hills, TerrainParams::default()).unwrap();
+        while let GameState::TanksThrowing = round.update(&mut players) {}
+
+        let aim = find_aim(&round).unwrap();
+        let hit_point = round.simulate_shot(aim.angle, aim.power).unwrap();
+        let enemy = round
+            .tanks
+            .iter()
+            .find(|t| t.player_number != round.player_number());
+        let distance = enemy.unwrap().center().distance(hit_point);
+        let radius = Weapon::default().explosion_radius();
+        assert!(distance < radius, "distance={}", distance);
+    }
+
+    #[test]
+    fn test_bots_match() {
+        let mut players = vec![
+            Player::new(Control::Computer(Difficulty::Hard)),
+            Player::new(Control::Computer(Difficulty::Normal)),
+        ];
+        let mut round =
+            Round::new(800, 600, 2, 5, Terrain::Hills, TerrainParams::default()).unwrap();
+        let mut bot = Bot::new();
+        let mut rng = Pcg32::seed_from_u64(1);
+        for player in players.iter_mut() {
+            go_shopping(player, &mut rng);
+            assert!(PRICE_LIST.iter().all(|o| o.price > player.money));
+        }
+
+        for _ in 0..200_000 {
+            let control = players[round.player_number() as usize - 1].control;
+            if let Control::Computer(difficulty) = control {
+                bot.update(&mut round, &mut players, difficulty, &mut rng);
+            }
+            if let GameState::Finish = round.update(&mut players) {
+                assert_eq!(round.live_tanks_count(), 1);
+                return;
+            }
+        }
+        panic!("Match of bots has not finished");
+    }
+}
@@ -1,9 +1,9 @@
 use crate::types::{Point2, Vector2};
-use std::time::Instant;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Ballistics {
-    created: Instant,
+    /// Game time of start of movement.
+    created: f32,
     start_pos: Point2,
     start_velocity: Vector2,
     acceleration: Vector2,
@@ -14,14 +14,19 @@ pub struct Ballistics {
 }
 
 impl Ballistics {
-    pub fn new<P, V>(start_pos: P, start_velocity: V, acceleration: V) -> Ballistics
+    pub fn new<P, V>(
+        start_time: f32,
+        start_pos: P,
+        start_velocity: V,
+        acceleration: V,
+    ) -> Ballistics
     where
         P: Into<Point2>,
         V: Into<Vector2>,
     {
         let start_pos = start_pos.into();
         Ballistics {
-            created: Instant::now(),
+            created: start_time,
             start_pos,
             start_velocity: start_velocity.into(),
             acceleration: acceleration.into(),
@@ -71,7 +76,17 @@ impl Ballistics {
         )
     }
 
-    fn apply_rebound(&mut self, horizontal: bool, vertical: bool) {
+    /// Returns new ballistics which starts from current position
+    /// with current velocity changed by given delta.
+    pub fn fork(&self, now: f32, velocity_delta: Vector2) -> Ballistics {
+        let (pos, velocity) = self.pos_and_velocity();
+        Ballistics::new(now, pos, velocity + velocity_delta, self.acceleration)
+            .time_scale(self.time_scale)
+            .rebound_efficiency(self.rebound_efficiency)
+    }
+
+    /// Reverses horizontal and/or vertical component of current velocity.
+    pub fn apply_rebound(&mut self, horizontal: bool, vertical: bool) {
         let (pos, mut velocity) = self.pos_and_velocity();
         if horizontal {
             velocity.x = -velocity.x;
@@ -83,18 +98,31 @@ impl Ballistics {
         self.start_pos = pos;
         self.start_velocity = velocity * self.rebound_efficiency;
         self.cur_pos = pos;
-        self.created = Instant::now();
+        self.created += self.last_updated / self.time_scale;
         self.last_updated = 0.0;
     }
 
+    /// Changes acceleration, movement continues from current position
+    /// with current velocity.
+    pub fn set_acceleration(&mut self, acceleration: Vector2) {
+        let (pos, velocity) = self.pos_and_velocity();
+        self.start_pos = pos;
+        self.start_velocity = velocity;
+        self.acceleration = acceleration;
+        self.cur_pos = pos;
+        self.created += self.last_updated / self.time_scale;
+        self.last_updated = 0.0;
+    }
+
+    /// Returns iterator over integer positions passed from last update
+    /// to given game time.
     pub fn positions_iter(
         &mut self,
-        end_time: Option<f32>,
+        now: f32,
         borders: Option<(i32, i32)>,
     ) -> BallisticsPosIterator {
         let start_time = self.last_updated;
-        let end_time =
-            end_time.unwrap_or_else(|| self.created.elapsed().as_secs_f32()) * self.time_scale;
+        let end_time = (now - self.created) * self.time_scale;
 
         let start_velocity = self.velocity(start_time);
         let end_velocity = self.velocity(end_time);
@@ -193,11 +221,12 @@ mod tests {
         let pos = [0., 0.];
         let acceleration = [0.0, 0.0];
         let velocity = [100.0, 0.0];
-        let mut ballistics = Ballistics::new(pos, velocity, acceleration).time_scale(TIME_SCALE);
+        let mut ballistics =
+            Ballistics::new(0.0, pos, velocity, acceleration).time_scale(TIME_SCALE);
 
         //assert_eq!(ballistics.pos_i32(10.0), (3000, 0));
 
-        let mut pos_iterator = ballistics.positions_iter(Some(10.0), None);
+        let mut pos_iterator = ballistics.positions_iter(10.0, None);
         for x in 1..=1000 {
             assert_eq!(pos_iterator.next(), Some((x, 0)));
         }
@@ -205,7 +234,7 @@ mod tests {
         assert_eq!(ballistics.last_updated, 10.0);
         assert_eq!(ballistics.cur_pos.x, 1000.0);
 
-        let mut pos_iterator = ballistics.positions_iter(Some(20.0), None);
+        let mut pos_iterator = ballistics.positions_iter(20.0, None);
         for x in 1001..=2000 {
             assert_eq!(pos_iterator.next(), Some((x, 0)));
         }
@@ -220,11 +249,12 @@ mod tests {
         let pos = [0., 0.];
         let acceleration = [0., 0.];
         let velocity = [0., -100.];
-        let mut ballistics = Ballistics::new(pos, velocity, acceleration).time_scale(TIME_SCALE);
+        let mut ballistics =
+            Ballistics::new(0.0, pos, velocity, acceleration).time_scale(TIME_SCALE);
 
         //assert_eq!(missile.pos(10.0 * TIME_SCALE).y, -3000.0);
 
-        let mut pos_iterator = ballistics.positions_iter(Some(10.0), None);
+        let mut pos_iterator = ballistics.positions_iter(10.0, None);
         for y in 1..=999 {
             assert_eq!(pos_iterator.next(), Some((0, -y)));
         }
@@ -233,4 +263,20 @@ mod tests {
         assert_eq!(ballistics.last_updated, 10.0);
         assert_eq!(ballistics.cur_pos.y, -1000.0);
     }
+
+    #[test]
+    fn test_set_acceleration() {
+        let mut ballistics = Ballistics::new(0.0, [0., 0.], [100., 0.], [0., 0.]).time_scale(2.0);
+        assert_eq!(ballistics.positions_iter(5.0, None).count(), 1000);
+
+        // Movement continues from the same point
+        ballistics.set_acceleration(Vector2::new(0., 10.));
+        assert_eq!(ballistics.cur_pos(), Point2::new(1000., 0.));
+        let mut pos_iterator = ballistics.positions_iter(6.0, None);
+        assert_eq!(pos_iterator.next(), Some((1001, 0)));
+        pos_iterator.last();
+        let (pos, velocity) = ballistics.pos_and_velocity();
+        assert_eq!(pos, Point2::new(1200., 40.));
+        assert_eq!(velocity, Vector2::new(100., 40.));
+    }
 }
@@ -0,0 +1,55 @@
+/// Count of game updates per one second of game time.
+pub const TICKS_PER_SECOND: u32 = 60;
+
+/// Virtual game clock.
+///
+/// Game time is counted in ticks of fixed length, so it does not depend
+/// on a real time spent for processing of a frame. The clock is stopped
+/// while nobody advances it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clock {
+    ticks: u64,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates clock which has already counted given number of ticks.
+    pub fn from_ticks(ticks: u64) -> Self {
+        Clock { ticks }
+    }
+
+    #[inline]
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    #[inline]
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Current game time in seconds.
+    #[inline]
+    pub fn now(&self) -> f32 {
+        self.ticks as f32 / TICKS_PER_SECOND as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_now() {
+        let mut clock = Clock::new();
+        assert_eq!(clock.now(), 0.0);
+        for _ in 0..TICKS_PER_SECOND * 3 / 2 {
+            clock.tick();
+        }
+        assert_eq!(clock.ticks(), 90);
+        assert_eq!(clock.now(), 1.5);
+    }
+}
@@ -0,0 +1,318 @@
+//! Keys of buttons which can be changed by user.
+//!
+//! Keys are stored in user's config file, one binding per line:
+//!
+//! ```text
+//! tank_war controls 1
+//! bind all fire Space
+//! bind all quit Escape
+//! bind 2 fire LControl
+//! ```
+//!
+//! A `bind` record contains set of keys (`all` for common keys or number
+//! of player whose own keys override common ones in hot-seat),
+//! name of button and name of key.
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+use ggez::event::KeyCode;
+
+use crate::input::{self, Button};
+use crate::MAX_PLAYERS_COUNT;
+
+const HEADER: &str = "tank_war controls 1";
+
+type Keys = BTreeMap<Button, KeyCode>;
+
+/// Buttons which are needed to use menus and to quit, so they can't
+/// lose their keys.
+const MENU_BUTTONS: [Button; 6] = [
+    Button::Up,
+    Button::Down,
+    Button::Left,
+    Button::Right,
+    Button::Select,
+    Button::Quit,
+];
+
+pub struct Controls {
+    common: Keys,
+    // Own keys of players in order of their numbers.
+    players: Vec<Keys>,
+    // Bindings for common keys and then for every player.
+    bindings: Vec<input::Binding>,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        let common = input::DEFAULT_KEYS.iter().cloned().collect();
+        let players = vec![Keys::new(); MAX_PLAYERS_COUNT as usize];
+        let mut controls = Controls {
+            common,
+            players,
+            bindings: Vec::new(),
+        };
+        controls.update_bindings();
+        controls
+    }
+}
+
+impl Controls {
+    fn keys(&self, player_number: Option<u8>) -> Option<&Keys> {
+        match player_number {
+            Some(number) => self.players.get(number as usize - 1),
+            None => Some(&self.common),
+        }
+    }
+
+    fn keys_mut(&mut self, player_number: Option<u8>) -> Option<&mut Keys> {
+        match player_number {
+            Some(number) => self.players.get_mut(number as usize - 1),
+            None => Some(&mut self.common),
+        }
+    }
+
+    /// Returns key of button in common keys or own key of player.
+    pub fn key(&self, player_number: Option<u8>, button: Button) -> Option<KeyCode> {
+        self.keys(player_number)
+            .and_then(|keys| keys.get(&button))
+            .cloned()
+    }
+
+    /// Returns key which is used by player for button: own key of player
+    /// or common one.
+    fn used_key(&self, player_number: Option<u8>, button: Button) -> Option<KeyCode> {
+        self.key(player_number, button)
+            .or_else(|| self.key(None, button))
+    }
+
+    /// Binds key to button. Other buttons of the same set which
+    /// were bound to this key lose it. Returns `false` and doesn't
+    /// change anything if the key is used by another menu button,
+    /// so up, down, left, right, select and quit always have keys.
+    pub fn set_key(&mut self, player_number: Option<u8>, button: Button, key: KeyCode) -> bool {
+        let taken = MENU_BUTTONS
+            .iter()
+            .any(|&b| b != button && self.used_key(player_number, b) == Some(key));
+        if taken {
+            return false;
+        }
+        if let Some(keys) = self.keys_mut(player_number) {
+            bind_key(keys, button, key);
+        }
+        self.update_bindings();
+        true
+    }
+
+    /// Removes own key of player, so common key will be used instead.
+    pub fn reset_key(&mut self, player_number: u8, button: Button) {
+        if let Some(keys) = self.keys_mut(Some(player_number)) {
+            keys.remove(&button);
+        }
+        self.update_bindings();
+    }
+
+    /// Restores default common keys or removes all own keys of player.
+    pub fn reset_keys(&mut self, player_number: Option<u8>) {
+        match player_number {
+            Some(_) => {
+                if let Some(keys) = self.keys_mut(player_number) {
+                    keys.clear();
+                }
+            }
+            None => self.common = input::DEFAULT_KEYS.iter().cloned().collect(),
+        }
+        self.update_bindings();
+    }
+
+    /// Returns binding of keys used by given player or common one.
+    pub fn binding(&self, player_number: Option<u8>) -> &input::Binding {
+        let index = player_number.map_or(0, usize::from);
+        self.bindings.get(index).unwrap_or(&self.bindings[0])
+    }
+
+    fn update_bindings(&mut self) {
+        let common = &self.common;
+        let mut bindings = vec![input::create_input_binding(
+            common.iter().map(|(&b, k)| (b, k)),
+        )];
+        for own_keys in self.players.iter() {
+            // Own keys are bound last, so they win if the same key
+            // is bound to another button in common keys.
+            let keys = common
+                .iter()
+                .filter(|(b, _)| !own_keys.contains_key(b))
+                .chain(own_keys.iter())
+                .map(|(&b, k)| (b, k));
+            bindings.push(input::create_input_binding(keys));
+        }
+        self.bindings = bindings;
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        for (button, &key) in self.common.iter() {
+            writeln!(
+                writer,
+                "bind all {} {}",
+                button.name(),
+                input::key_name(key)
+            )?;
+        }
+        for (index, keys) in self.players.iter().enumerate() {
+            for (button, &key) in keys.iter() {
+                writeln!(
+                    writer,
+                    "bind {} {} {}",
+                    index + 1,
+                    button.name(),
+                    input::key_name(key)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(reader: R) -> Result<Controls, String> {
+        let mut lines = reader.lines();
+        match lines.next() {
+            Some(Ok(ref line)) if line.trim() == HEADER => (),
+            _ => return Err("Unknown format of controls".into()),
+        }
+
+        let mut controls = Controls::default();
+        for line in lines {
+            let line = line.map_err(|e| e.to_string())?;
+            let values: Vec<&str> = line.split_whitespace().collect();
+            match values.as_slice() {
+                ["bind", set, button, key] => {
+                    let player_number = match *set {
+                        "all" => None,
+                        number => match number.parse::<u8>() {
+                            Ok(n) if (1..=MAX_PLAYERS_COUNT).contains(&n) => Some(n),
+                            _ => return Err(format!("Invalid set of keys: {}", number)),
+                        },
+                    };
+                    let button: Button = button.parse()?;
+                    let key = input::parse_key(key)?;
+                    // Records are applied without checks of menu buttons,
+                    // because swapped keys can't be bound one by one.
+                    if let Some(keys) = controls.keys_mut(player_number) {
+                        bind_key(keys, button, key);
+                    }
+                }
+                [] => (),
+                _ => return Err(format!("Invalid record in controls: {}", line)),
+            }
+        }
+        for &button in MENU_BUTTONS.iter() {
+            if controls.key(None, button).is_none() {
+                return Err(format!("No key for button: {}", button.name()));
+            }
+        }
+        controls.update_bindings();
+        Ok(controls)
+    }
+}
+
+/// Binds key to button and removes it from other buttons of the set.
+fn bind_key(keys: &mut Keys, button: Button, key: KeyCode) {
+    keys.retain(|_, &mut k| k != key);
+    keys.insert(button, key);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(controls: &Controls, player_number: Option<u8>, key: KeyCode) -> Option<Button> {
+        match controls.binding(player_number).resolve(key) {
+            Some(input::Event::Button(button)) => Some(button),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let mut controls = Controls::default();
+        assert!(controls.set_key(None, Button::Fire, KeyCode::F));
+        assert!(controls.set_key(Some(2), Button::Fire, KeyCode::LControl));
+        let mut data = Vec::new();
+        controls.write(&mut data).unwrap();
+
+        let restored = Controls::read(&data[..]).unwrap();
+        let mut restored_data = Vec::new();
+        restored.write(&mut restored_data).unwrap();
+        assert_eq!(restored_data, data);
+        assert_eq!(restored.key(None, Button::Fire), Some(KeyCode::F));
+        assert_eq!(restored.key(Some(2), Button::Fire), Some(KeyCode::LControl));
+        assert_eq!(restored.key(Some(1), Button::Fire), None);
+
+        // Swapped keys of menu buttons are restored
+        let text = format!("{}\nbind all up Down\nbind all down Up\n", HEADER);
+        let restored = Controls::read(text.as_bytes()).unwrap();
+        assert_eq!(restored.key(None, Button::Up), Some(KeyCode::Down));
+        assert_eq!(restored.key(None, Button::Down), Some(KeyCode::Up));
+
+        let text = format!("{}\nbind all fire Escape\n", HEADER);
+        assert!(Controls::read(text.as_bytes()).is_err());
+        assert!(Controls::read("bind all fire Space\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_set_key_removes_conflicts() {
+        let mut controls = Controls::default();
+        assert!(controls.set_key(None, Button::Fire, KeyCode::Tab));
+        assert_eq!(controls.key(None, Button::Fire), Some(KeyCode::Tab));
+        assert_eq!(controls.key(None, Button::NextWeapon), None);
+        assert_eq!(resolve(&controls, None, KeyCode::Tab), Some(Button::Fire));
+        assert_eq!(resolve(&controls, None, KeyCode::Space), None);
+
+        // Menu buttons don't lose their keys
+        assert!(!controls.set_key(None, Button::Fire, KeyCode::Escape));
+        assert!(!controls.set_key(None, Button::Fire, KeyCode::Up));
+        assert!(!controls.set_key(Some(1), Button::Fire, KeyCode::Return));
+        assert_eq!(controls.key(None, Button::Quit), Some(KeyCode::Escape));
+        assert_eq!(controls.key(None, Button::Fire), Some(KeyCode::Tab));
+        assert!(controls.set_key(None, Button::Quit, KeyCode::Q));
+        assert!(controls.set_key(None, Button::Fire, KeyCode::Escape));
+
+        controls.reset_keys(None);
+        assert_eq!(controls.key(None, Button::Quit), Some(KeyCode::Escape));
+        assert_eq!(controls.key(None, Button::Fire), Some(KeyCode::Space));
+    }
+
+    #[test]
+    fn test_own_keys_of_player() {
+        let mut controls = Controls::default();
+        assert!(controls.set_key(Some(2), Button::Fire, KeyCode::LControl));
+        assert!(controls.set_key(Some(2), Button::NextWeapon, KeyCode::Space));
+        assert_eq!(
+            resolve(&controls, Some(2), KeyCode::LControl),
+            Some(Button::Fire)
+        );
+        assert_eq!(
+            resolve(&controls, Some(2), KeyCode::Space),
+            Some(Button::NextWeapon)
+        );
+        assert_eq!(resolve(&controls, Some(2), KeyCode::Tab), None);
+        assert_eq!(
+            resolve(&controls, Some(2), KeyCode::E),
+            Some(Button::NextItem)
+        );
+        // Other players and common keys are not changed
+        assert_eq!(
+            resolve(&controls, Some(1), KeyCode::Space),
+            Some(Button::Fire)
+        );
+        assert_eq!(resolve(&controls, None, KeyCode::LControl), None);
+
+        controls.reset_key(2, Button::Fire);
+        assert_eq!(resolve(&controls, Some(2), KeyCode::LControl), None);
+        controls.reset_keys(Some(2));
+        assert_eq!(
+            resolve(&controls, Some(2), KeyCode::Space),
+            Some(Button::Fire)
+        );
+    }
+}
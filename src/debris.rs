@@ -0,0 +1,150 @@
+//! Loose ground thrown out of crater by explosion.
+//!
+//! Debris flies as a separate particle and becomes a pixel of landscape
+//! again when it lands. Velocities of debris are calculated from
+//! positions of pixels, so flight is the same in every replay.
+use crate::ballistics::Ballistics;
+use crate::landscape::{Landscape, Material};
+use crate::types::{Point2, Vector2};
+use crate::G;
+
+const TIME_SCALE: f32 = 3.0;
+/// Only one of this count of destroyed pixels is thrown out.
+const THROWN_PIXELS_RATIO: u32 = 6;
+const MIN_SPEED: f32 = 15.0;
+const MAX_SPEED: f32 = 55.0;
+/// Max deviation of direction of flight from direction out of
+/// center of explosion, in radians.
+const MAX_DEVIATION: f32 = 0.4;
+
+/// Result of flight of debris during one update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flight {
+    Flying,
+    /// Debris has landed at given empty pixel.
+    Landed(i32, i32),
+    /// Debris has flown out of landscape.
+    Lost,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Debris {
+    pub material: Material,
+    ballistics: Ballistics,
+}
+
+impl Debris {
+    /// Returns debris for given pixel destroyed by explosion with
+    /// given center, if this pixel is thrown out.
+    pub fn from_crater(
+        now: f32,
+        center: Point2,
+        x: i32,
+        y: i32,
+        material: Material,
+    ) -> Option<Self> {
+        match material {
+            Material::Dirt | Material::Sand => (),
+            _ => return None,
+        }
+        let hash = pixel_hash(x, y);
+        if hash % THROWN_PIXELS_RATIO != 0 {
+            return None;
+        }
+
+        let pos = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+        let offset = pos - center;
+        // Debris always flies upward
+        let angle = offset.y.min(-offset.x.abs() * 0.5).atan2(offset.x);
+        let angle = angle + MAX_DEVIATION * (unit(hash >> 4) * 2. - 1.);
+        let speed = MIN_SPEED + (MAX_SPEED - MIN_SPEED) * unit(hash >> 16);
+        let velocity = Vector2::new(angle.cos(), angle.sin()) * speed;
+        let ballistics = Ballistics::new(now, pos, velocity, Vector2::new(0., G));
+        Some(Debris {
+            material,
+            ballistics: ballistics.time_scale(TIME_SCALE),
+        })
+    }
+
+    #[inline]
+    pub fn cur_pos(&self) -> Point2 {
+        self.ballistics.cur_pos()
+    }
+
+    /// Moves debris up to given game time.
+    pub fn fly(&mut self, now: f32, landscape: &Landscape) -> Flight {
+        let (width, height) = landscape.size();
+        let (width, height) = (i32::from(width), i32::from(height));
+        let pos = self.cur_pos();
+        let mut last_pos = (pos.x.floor() as i32, pos.y.floor() as i32);
+        for (x, y) in self.ballistics.positions_iter(now, None) {
+            if x < 0 || x >= width || y >= height {
+                return Flight::Lost;
+            }
+            if landscape.is_not_empty(x, y) {
+                return if last_pos.1 < 0 {
+                    Flight::Lost
+                } else {
+                    Flight::Landed(last_pos.0, last_pos.1)
+                };
+            }
+            last_pos = (x, y);
+        }
+        Flight::Flying
+    }
+}
+
+/// Returns pseudo-random number for given pixel.
+fn pixel_hash(x: i32, y: i32) -> u32 {
+    let mut hash = (x as u32).wrapping_mul(0x9e37_79b1) ^ (y as u32).wrapping_mul(0x85eb_ca77);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0xc2b2_ae3d);
+    hash ^ (hash >> 13)
+}
+
+/// Returns number from 0 to 1 made from lowest bits of given hash.
+#[inline]
+fn unit(hash: u32) -> f32 {
+    (hash & 0xfff) as f32 / 4095.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debris_lands_on_ground() {
+        let mut landscape = Landscape::new(400, 200).unwrap();
+        for y in 150..200 {
+            let pixels = landscape.get_pixels_line_mut((0, y), 400).unwrap();
+            pixels.iter_mut().for_each(|c| *c = Material::Dirt as u8);
+        }
+        let center = Point2::new(200., 160.);
+        let debris: Vec<Debris> = (180..220)
+            .flat_map(|x| (150..170).map(move |y| (x, y)))
+            .filter_map(|(x, y)| Debris::from_crater(0., center, x, y, Material::Dirt))
+            .collect();
+        let count = debris.len();
+        assert!(count > 0 && count < 800 / 2);
+        assert!(Debris::from_crater(0., center, 200, 150, Material::Rock).is_none());
+
+        // Crater
+        for y in 150..170 {
+            let pixels = landscape.get_pixels_line_mut((180, y), 40).unwrap();
+            pixels.iter_mut().for_each(|c| *c = 0);
+        }
+        let ground = bytecount::count(landscape.buffer(), Material::Dirt as u8);
+        landscape.throw_debris(debris);
+        let mut now = 0.;
+        while !landscape.debris().is_empty() {
+            now += 0.1;
+            assert!(now < 30.);
+            landscape.update_debris(now);
+        }
+        let landed = bytecount::count(landscape.buffer(), Material::Dirt as u8) - ground;
+        assert!(landed > 0 && landed <= count);
+        // Debris is piled up around of crater
+        let piled = bytecount::count(&landscape.buffer()[..150 * 400], Material::Dirt as u8);
+        assert!(piled > 0);
+    }
+}
@@ -1,45 +1,56 @@
-use std::time::Instant;
-
-use ggez::graphics::Rect;
 use itertools::Itertools;
 
-use crate::geometry::Circle;
-use crate::landscape::Landscape;
+use crate::debris::Debris;
+use crate::geometry::{Circle, Rect};
+use crate::landscape::{Landscape, Material};
 use crate::types::Point2;
 
 const SPEED: f32 = 150.0;
+/// Width of scorched ground around crater.
+const SCORCH_WIDTH: f32 = 4.0;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Explosion {
-    created: Instant,
+    /// Game time of start of explosion.
+    created: f32,
     pub pos: Point2,
     max_radius: f32,
+    /// Damage of tank which is fully covered by explosion.
+    damage: f32,
     pub cur_radius: f32,
     pub cur_opacity: f32,
     landscape_updated: bool,
 }
 
 impl Explosion {
-    pub fn new(pos: Point2, max_radius: f32) -> Self {
+    pub fn new(now: f32, pos: Point2, max_radius: f32) -> Self {
         Explosion {
-            created: Instant::now(),
+            created: now,
             pos,
             max_radius,
+            damage: 100.0,
             cur_radius: 0.0,
             cur_opacity: 1.0,
             landscape_updated: false,
         }
     }
 
+    pub fn damage(self, value: f32) -> Self {
+        Self {
+            damage: value,
+            ..self
+        }
+    }
+
     #[inline]
     pub fn is_life(self) -> bool {
         self.cur_opacity > 0.0
     }
 
     /// Returns `true` if explosion has finished.
-    pub fn update(&mut self, landscape: &mut Landscape) -> bool {
+    pub fn update(&mut self, now: f32, landscape: &mut Landscape) -> bool {
         if self.is_life() {
-            let time = self.created.elapsed().as_secs_f32();
+            let time = now - self.created;
             let radius = time * SPEED;
             self.cur_opacity = if radius <= self.max_radius {
                 1.0
@@ -49,14 +60,18 @@ impl Explosion {
             self.cur_radius = radius.min(self.max_radius);
 
             if !self.landscape_updated && radius >= self.max_radius {
-                self.destroy_landscape(landscape);
+                self.destroy_landscape(now, landscape);
             }
         }
 
         !self.is_life()
     }
 
-    fn destroy_landscape(&mut self, landscape: &mut Landscape) {
+    /// Destroys landscape inside of explosion. Hard materials are
+    /// destroyed only near center of explosion. Part of loose ground
+    /// is thrown out of crater.
+    fn destroy_landscape(&mut self, now: f32, landscape: &mut Landscape) {
+        let mut debris = Vec::new();
         let circle = line_drawing::BresenhamCircle::new(
             self.pos.x as i32,
             self.pos.y as i32,
@@ -75,12 +90,27 @@ impl Explosion {
                 continue;
             }
             for &y in [y1, y2].iter() {
+                let dy = y as f32 - self.pos.y;
                 if let Some(pixels) = landscape.get_pixels_line_mut((x, y), len) {
-                    pixels.iter_mut().for_each(|c| *c = 0);
+                    for (dx, pixel) in pixels.iter_mut().enumerate() {
+                        let material = Material::from_value(*pixel).unwrap_or(Material::Empty);
+                        let scale = material.crater_scale();
+                        let destroyed = scale >= 1. || {
+                            let dx = (x + dx as i32) as f32 - self.pos.x;
+                            let radius = self.max_radius * scale;
+                            dx * dx + dy * dy < radius * radius
+                        };
+                        if destroyed {
+                            *pixel = 0;
+                            let px = x + dx as i32;
+                            debris.extend(Debris::from_crater(now, self.pos, px, y, material));
+                        }
+                    }
                 }
             }
         }
-        landscape.set_changed();
+        landscape.throw_debris(debris);
+        landscape.scorch(self.pos, self.max_radius + SCORCH_WIDTH);
         self.landscape_updated = true;
     }
 
@@ -96,4 +126,46 @@ impl Explosion {
         }
         0
     }
+
+    /// Returns damage of tank with given bounds.
+    pub fn get_damage(&self, bound: Rect) -> u8 {
+        let percents = f32::from(self.get_intersection_percents(bound));
+        (percents * self.damage / 100.0).round().min(255.0) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_destroy_landscape_by_materials() {
+        let mut landscape = Landscape::new(300, 100).unwrap();
+        for (x, material) in [Material::Dirt, Material::Rock, Material::Bedrock]
+            .iter()
+            .enumerate()
+        {
+            for y in 0..100 {
+                let pixels = landscape
+                    .get_pixels_line_mut((x as i32 * 100, y), 100)
+                    .unwrap();
+                pixels.iter_mut().for_each(|c| *c = *material as u8);
+            }
+        }
+
+        for &x in [50., 150., 250.].iter() {
+            let mut explosion = Explosion::new(0., Point2::new(x, 50.), 40.);
+            while !explosion.update(10., &mut landscape) {}
+        }
+
+        // Dirt is destroyed in whole radius of explosion
+        assert!(!landscape.is_not_empty(50, 50));
+        assert!(!landscape.is_not_empty(50, 85));
+        // Rock is destroyed only near center
+        assert!(!landscape.is_not_empty(150, 50));
+        assert!(!landscape.is_not_empty(150, 65));
+        assert!(landscape.is_not_empty(150, 85));
+        // Bedrock isn't destroyed at all
+        assert!(landscape.is_not_empty(250, 50));
+    }
 }
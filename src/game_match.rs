@@ -0,0 +1,156 @@
+//! A match consisting of several rounds.
+use rand::Rng;
+use rand_pcg::Pcg32;
+
+use crate::landscape::{Terrain, TerrainParams};
+use crate::player::Player;
+use crate::wind::WindParams;
+
+pub const DEFAULT_ROUNDS_COUNT: u8 = 3;
+pub const MAX_ROUNDS_COUNT: u8 = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    seed: u64,
+    pub rounds_count: u8,
+    /// Number of current round starting from 1.
+    pub round_number: u8,
+    /// Style of landscape of all rounds, `None` means random style
+    /// of every round.
+    pub terrain: Option<Terrain>,
+    pub terrain_params: TerrainParams,
+    pub wind_params: WindParams,
+    /// Name of custom map used instead of generated landscape
+    /// in all rounds.
+    pub map: Option<String>,
+}
+
+impl Default for Match {
+    fn default() -> Self {
+        Match::new(0, DEFAULT_ROUNDS_COUNT)
+    }
+}
+
+impl Match {
+    pub fn new(seed: u64, rounds_count: u8) -> Self {
+        Match {
+            seed,
+            rounds_count: rounds_count.max(1).min(MAX_ROUNDS_COUNT),
+            round_number: 1,
+            terrain: None,
+            terrain_params: TerrainParams::default(),
+            wind_params: WindParams::default(),
+            map: None,
+        }
+    }
+
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns seed of current round. The first round uses seed of match
+    /// itself, seeds of next rounds are derived from it.
+    pub fn round_seed(&self) -> u64 {
+        if self.round_number <= 1 {
+            self.seed
+        } else {
+            Pcg32::new(self.seed, u64::from(self.round_number)).gen()
+        }
+    }
+
+    /// Returns style of landscape of current round.
+    pub fn round_terrain(&self) -> Terrain {
+        match self.terrain {
+            Some(terrain) => terrain,
+            None => {
+                let index = self.round_seed() % Terrain::ALL.len() as u64;
+                Terrain::ALL[index as usize]
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_last_round(&self) -> bool {
+        self.round_number >= self.rounds_count
+    }
+
+    pub fn next_round(&mut self) {
+        self.round_number = self.round_number.saturating_add(1);
+    }
+}
+
+/// Returns numbers of players with the highest score.
+pub fn winners(players: &[Player]) -> Vec<u8> {
+    let max_score = players.iter().map(|p| p.score).max().unwrap_or(0);
+    players
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.score == max_score)
+        .map(|(i, _)| i as u8 + 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::round::{GameState, Round};
+
+    #[test]
+    fn test_round_seeds() {
+        let mut game_match = Match::new(42, 3);
+        assert_eq!(game_match.round_seed(), 42);
+        game_match.next_round();
+        let second_seed = game_match.round_seed();
+        assert_ne!(second_seed, 42);
+        assert!(!game_match.is_last_round());
+        game_match.next_round();
+        assert_ne!(game_match.round_seed(), second_seed);
+        assert!(game_match.is_last_round());
+    }
+
+    #[test]
+    fn test_round_terrains() {
+        let mut game_match = Match::new(42, 10);
+        let mut terrains = Vec::new();
+        for _ in 0..10 {
+            terrains.push(game_match.round_terrain());
+            game_match.next_round();
+        }
+        assert!(terrains.iter().any(|&t| t != terrains[0]));
+
+        game_match.terrain = Some(Terrain::Valleys);
+        assert_eq!(game_match.round_terrain(), Terrain::Valleys);
+    }
+
+    #[test]
+    fn test_scores_of_round() {
+        let mut players: Vec<Player> = (0..3).map(|_| Player::default()).collect();
+        let mut round =
+            Round::new(800, 600, 3, 1, Terrain::Hills, TerrainParams::default()).unwrap();
+        while let GameState::TanksThrowing = round.update(&mut players) {}
+
+        // Current player destroys all enemies
+        let current_player = round.player_number();
+        for tank in round.tanks.iter_mut() {
+            if tank.player_number != current_player {
+                tank.health = 0;
+                tank.throw_down(round.clock.now(), None);
+            }
+        }
+        round.state = GameState::TanksThrowing;
+        loop {
+            if let GameState::Finish = round.update(&mut players) {
+                break;
+            }
+        }
+
+        let results = round.results();
+        let index = current_player as usize - 1;
+        assert_eq!(results[index].kills, 2);
+        assert!(results[index].survived);
+        assert_eq!(players[index].score, results[index].score);
+        assert!(results[index].score > 0);
+        assert_eq!(winners(&players), vec![current_player]);
+    }
+}
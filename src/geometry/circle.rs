@@ -1,8 +1,8 @@
 use std::cmp::Ordering;
 
 use cgmath::prelude::*;
-use ggez::graphics::Rect;
 
+use crate::geometry::Rect;
 use crate::types::{Point2, Vector2};
 
 pub struct Circle {
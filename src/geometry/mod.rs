@@ -1,5 +1,7 @@
 pub use circle::Circle;
 pub use ellipse::Ellipse;
+pub use rect::Rect;
 
 pub mod circle;
 pub mod ellipse;
+pub mod rect;
@@ -0,0 +1,67 @@
+use crate::types::{Point2, Vector2};
+
+/// Axis-aligned rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Rect { x, y, w, h }
+    }
+
+    #[inline]
+    pub fn left(&self) -> f32 {
+        self.x
+    }
+
+    #[inline]
+    pub fn right(&self) -> f32 {
+        self.x + self.w
+    }
+
+    #[inline]
+    pub fn top(&self) -> f32 {
+        self.y
+    }
+
+    #[inline]
+    pub fn bottom(&self) -> f32 {
+        self.y + self.h
+    }
+
+    pub fn translate<V: Into<Vector2>>(&mut self, offset: V) {
+        let offset = offset.into();
+        self.x += offset.x;
+        self.y += offset.y;
+    }
+
+    /// Returns `true` if given point locates inside of rectangle.
+    pub fn contains<P: Into<Point2>>(&self, point: P) -> bool {
+        let point = point.into();
+        point.x >= self.left()
+            && point.x <= self.right()
+            && point.y >= self.top()
+            && point.y <= self.bottom()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains() {
+        let rect = Rect::new(10., 20., 5., 4.);
+
+        assert!(rect.contains((10., 20.)));
+        assert!(rect.contains((15., 24.)));
+        assert!(rect.contains((12., 22.)));
+        assert!(!rect.contains((9., 22.)));
+        assert!(!rect.contains((12., 25.)));
+    }
+}
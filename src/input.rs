@@ -1,8 +1,12 @@
 //! Typedefs for input shortcuts.
-use ggez::event::KeyCode;
+use std::str::FromStr;
+
+use ggez::event::{self, KeyCode, MouseButton};
 use ggez_goodies::input;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+use crate::types::Point2;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Button {
     Up,
     Down,
@@ -11,6 +15,94 @@ pub enum Button {
     Select,
     Quit,
     Fire,
+    NextWeapon,
+    NextItem,
+    UseItem,
+    /// Modifier which makes aiming more precise while it is held.
+    FineAim,
+}
+
+impl Button {
+    pub const ALL: [Button; 11] = [
+        Button::Up,
+        Button::Down,
+        Button::Left,
+        Button::Right,
+        Button::Select,
+        Button::Quit,
+        Button::Fire,
+        Button::NextWeapon,
+        Button::NextItem,
+        Button::UseItem,
+        Button::FineAim,
+    ];
+
+    /// Name of button used in config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Button::Up => "up",
+            Button::Down => "down",
+            Button::Left => "left",
+            Button::Right => "right",
+            Button::Select => "select",
+            Button::Quit => "quit",
+            Button::Fire => "fire",
+            Button::NextWeapon => "next_weapon",
+            Button::NextItem => "next_item",
+            Button::UseItem => "use_item",
+            Button::FineAim => "fine_aim",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Button::Up => "Up",
+            Button::Down => "Down",
+            Button::Left => "Left",
+            Button::Right => "Right",
+            Button::Select => "Select",
+            Button::Quit => "Quit",
+            Button::Fire => "Fire",
+            Button::NextWeapon => "Next weapon",
+            Button::NextItem => "Next item",
+            Button::UseItem => "Use item",
+            Button::FineAim => "Fine aim",
+        }
+    }
+}
+
+impl FromStr for Button {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Button::ALL
+            .iter()
+            .find(|b| b.name() == s)
+            .cloned()
+            .ok_or_else(|| format!("Unknown button: {}", s))
+    }
+}
+
+/// Returns axis which is moved while given direction button is held
+/// and whether it is moved in positive direction.
+pub fn button_axis(button: Button) -> Option<(Axis, bool)> {
+    match button {
+        Button::Up => Some((Axis::Vertical, true)),
+        Button::Down => Some((Axis::Vertical, false)),
+        Button::Left => Some((Axis::Horizontal, false)),
+        Button::Right => Some((Axis::Horizontal, true)),
+        _ => None,
+    }
+}
+
+/// Returns direction button which moves axis in given direction.
+pub fn axis_button(axis: Axis, positive: bool) -> Button {
+    match (axis, positive) {
+        (Axis::Vertical, true) => Button::Up,
+        (Axis::Vertical, false) => Button::Down,
+        (Axis::Horizontal, false) => Button::Left,
+        (Axis::Horizontal, true) => Button::Right,
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -23,14 +115,219 @@ pub type Binding = input::InputBinding<Axis, Button>;
 pub type Event = input::InputEffect<Axis, Button>;
 pub type State = input::InputState<Axis, Button>;
 
-/// Create the default keybindings for our input state.
-pub fn create_input_binding() -> Binding {
-    Binding::new()
-        .bind_key_to_button(KeyCode::Up, Button::Up)
-        .bind_key_to_button(KeyCode::Down, Button::Down)
-        .bind_key_to_button(KeyCode::Left, Button::Left)
-        .bind_key_to_button(KeyCode::Right, Button::Right)
-        .bind_key_to_button(KeyCode::Return, Button::Select)
-        .bind_key_to_button(KeyCode::Escape, Button::Quit)
-        .bind_key_to_button(KeyCode::Space, Button::Fire)
+/// State of mouse collected from events since last update.
+#[derive(Debug, Default, Clone)]
+pub struct Mouse {
+    /// Position of cursor. It is unknown until cursor is moved.
+    pub position: Option<Point2>,
+    pub moved: bool,
+    pub clicked: Option<MouseButton>,
+    /// Distance of wheel scrolling, positive values mean scrolling up.
+    pub wheel: f32,
+}
+
+impl Mouse {
+    /// Returns position of click with given button.
+    pub fn click_position(&self, button: MouseButton) -> Option<Point2> {
+        if self.clicked == Some(button) {
+            self.position
+        } else {
+            None
+        }
+    }
+
+    /// Forgets events which were handled during update.
+    pub fn clear(&mut self) {
+        self.moved = false;
+        self.clicked = None;
+        self.wheel = 0.;
+    }
+}
+
+/// Deflections of gamepad sticks which are less than this are ignored.
+pub const STICK_DEAD_ZONE: f32 = 0.25;
+
+/// Deflection of gamepad stick or directional pad.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Stick {
+    pub horizontal: f32,
+    pub vertical: f32,
+}
+
+impl Stick {
+    /// Returns axis moved by axis of gamepad.
+    pub fn gamepad_axis(axis: event::Axis) -> Option<Axis> {
+        match axis {
+            event::Axis::LeftStickX | event::Axis::DPadX => Some(Axis::Horizontal),
+            event::Axis::LeftStickY | event::Axis::DPadY => Some(Axis::Vertical),
+            _ => None,
+        }
+    }
+
+    pub fn value(&self, axis: Axis) -> f32 {
+        match axis {
+            Axis::Horizontal => self.horizontal,
+            Axis::Vertical => self.vertical,
+        }
+    }
+
+    pub fn set_value(&mut self, axis: Axis, value: f32) {
+        let value = value.max(-1.).min(1.);
+        match axis {
+            Axis::Horizontal => self.horizontal = value,
+            Axis::Vertical => self.vertical = value,
+        }
+    }
+
+    /// Returns whether axis is moved in positive direction or `None`
+    /// if stick is in dead zone.
+    pub fn direction(&self, axis: Axis) -> Option<bool> {
+        let value = self.value(axis);
+        if value.abs() < STICK_DEAD_ZONE {
+            None
+        } else {
+            Some(value > 0.)
+        }
+    }
+
+    /// Returns part of full speed of axis. Stick which is not moved
+    /// doesn't slow down axis moved by keys.
+    pub fn speed(&self, axis: Axis) -> f32 {
+        match self.direction(axis) {
+            Some(_) => self.value(axis).abs(),
+            None => 1.,
+        }
+    }
+}
+
+/// Default keys of buttons.
+pub const DEFAULT_KEYS: [(Button, KeyCode); 11] = [
+    (Button::Up, KeyCode::Up),
+    (Button::Down, KeyCode::Down),
+    (Button::Left, KeyCode::Left),
+    (Button::Right, KeyCode::Right),
+    (Button::Select, KeyCode::Return),
+    (Button::Quit, KeyCode::Escape),
+    (Button::Fire, KeyCode::Space),
+    (Button::NextWeapon, KeyCode::Tab),
+    (Button::NextItem, KeyCode::E),
+    (Button::UseItem, KeyCode::R),
+    (Button::FineAim, KeyCode::LShift),
+];
+
+/// Keys which can be bound to buttons.
+const KEYS: [KeyCode; 90] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Escape,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::Delete,
+    KeyCode::End,
+    KeyCode::PageDown,
+    KeyCode::PageUp,
+    KeyCode::Left,
+    KeyCode::Up,
+    KeyCode::Right,
+    KeyCode::Down,
+    KeyCode::Back,
+    KeyCode::Return,
+    KeyCode::Space,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::Add,
+    KeyCode::Subtract,
+    KeyCode::Multiply,
+    KeyCode::Divide,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Minus,
+    KeyCode::Equals,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Tab,
+    KeyCode::LAlt,
+    KeyCode::LControl,
+    KeyCode::LShift,
+    KeyCode::RAlt,
+    KeyCode::RControl,
+    KeyCode::RShift,
+    KeyCode::NumpadEnter,
+];
+
+/// Name of key used in config file.
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+pub fn parse_key(name: &str) -> Result<KeyCode, String> {
+    KEYS.iter()
+        .find(|&&k| key_name(k) == name)
+        .cloned()
+        .ok_or_else(|| format!("Unknown key: {}", name))
+}
+
+/// Creates bindings of given keys to buttons.
+pub fn create_input_binding<'a, I>(keys: I) -> Binding
+where
+    I: IntoIterator<Item = (Button, &'a KeyCode)>,
+{
+    keys.into_iter()
+        .fold(Binding::new(), |binding, (button, &key)| {
+            binding.bind_key_to_button(key, button)
+        })
 }
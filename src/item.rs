@@ -0,0 +1,64 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::shield::{Shield, ShieldKind};
+use crate::tank::Tank;
+
+/// Things which tank can use during aiming instead of a shot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Item {
+    RepairKit,
+    Shield(ShieldKind),
+}
+
+/// How much health is restored by repair kit.
+const REPAIR_KIT_HEALTH: u8 = 50;
+
+impl Item {
+    pub const ALL: [Item; 4] = [
+        Item::RepairKit,
+        Item::Shield(ShieldKind::Light),
+        Item::Shield(ShieldKind::Heavy),
+        Item::Shield(ShieldKind::Deflector),
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Item::RepairKit => "repair_kit",
+            Item::Shield(kind) => kind.name(),
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Item::RepairKit => "Repair Kit",
+            Item::Shield(kind) => kind.title(),
+        }
+    }
+
+    /// Applies the item to given tank. A new shield replaces the old one.
+    pub fn apply(self, tank: &mut Tank) {
+        match self {
+            Item::RepairKit => tank.health = tank.health.saturating_add(REPAIR_KIT_HEALTH).min(100),
+            Item::Shield(kind) => tank.shield = Some(Shield::new(kind)),
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Item {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Item::ALL
+            .iter()
+            .find(|i| i.name() == s)
+            .cloned()
+            .ok_or_else(|| format!("Unknown item: {}", s))
+    }
+}
@@ -1,29 +1,328 @@
-use std::time::Instant;
+use std::fmt;
+use std::str::FromStr;
 
-use ggez::graphics;
 use itertools::Itertools;
 use noise::{self, Fbm, MultiFractal, NoiseFn, Seedable};
 
+use crate::debris::{Debris, Flight};
+use crate::map::Map;
+use crate::parse::parse_value;
+use crate::texture::Textures;
+use crate::types::{Point2, Vector2};
 use crate::G;
 
 const TIME_SCALE: f32 = 3.0;
+/// Thickness of ground at bottom of pit between islands.
+const PIT_FLOOR: f64 = 5.;
+/// Thickness of layer of bedrock at bottom of generated landscape.
+const BEDROCK_DEPTH: usize = 4;
+/// Average depth of top of rock below surface of generated landscape.
+const ROCK_DEPTH: f64 = 60.;
+/// Average thickness of patches of sand on surface of generated landscape.
+const SAND_DEPTH: f64 = 10.;
+
+/// Thickness of grass or snow on surface of ground.
+const SURFACE_DEPTH: u16 = 3;
+/// Part of height of landscape above which surface is covered by snow.
+const SNOW_LINE: f32 = 0.35;
+const GRASS_COLOR: [u8; 3] = [0x4e, 0x8c, 0x2e];
+const SNOW_COLOR: [u8; 3] = [0xee, 0xf2, 0xf6];
+/// Depth below surface at which ground is the darkest.
+const MAX_SHADED_DEPTH: u16 = 200;
+/// Part of brightness which ground loses at max shaded depth.
+const DEPTH_SHADING: f32 = 0.5;
+/// Brightness of scorched ground.
+const SCORCH_BRIGHTNESS: f32 = 0.45;
+/// Angle of repose of sand in degrees. Sand slides down along
+/// steeper slopes.
+const ANGLE_OF_REPOSE: f32 = 30.;
+/// Max count of separate dirty rectangles. If there are more of them,
+/// they are merged into one rectangle.
+const MAX_DIRTY_RECTS: usize = 8;
+
+/// Material of pixel of landscape. Buffer of landscape contains
+/// values of materials.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Material {
+    Empty = 0,
+    /// Normal ground.
+    Dirt = 1,
+    /// Hard ground which is destroyed only near center of explosion.
+    Rock = 2,
+    /// Loose ground which slides down along slopes during subsidence.
+    Sand = 3,
+    /// Indestructible ground which never falls.
+    Bedrock = 4,
+}
+
+impl Material {
+    pub const ALL: [Material; 5] = [
+        Material::Empty,
+        Material::Dirt,
+        Material::Rock,
+        Material::Sand,
+        Material::Bedrock,
+    ];
+
+    /// Returns material with given value of pixel.
+    pub fn from_value(value: u8) -> Option<Material> {
+        Material::ALL.get(value as usize).cloned()
+    }
+
+    /// Returns part of radius of explosion in which material is destroyed.
+    pub fn crater_scale(self) -> f32 {
+        match self {
+            Material::Empty | Material::Dirt | Material::Sand => 1.,
+            Material::Rock => 0.5,
+            Material::Bedrock => 0.,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Material::Empty => "empty",
+            Material::Dirt => "dirt",
+            Material::Rock => "rock",
+            Material::Sand => "sand",
+            Material::Bedrock => "bedrock",
+        }
+    }
+
+    /// Returns RGB color of material.
+    pub fn color(self) -> [u8; 3] {
+        match self {
+            Material::Empty => [0, 0, 0],
+            Material::Dirt => [0x9c, 0x71, 0x40],
+            Material::Rock => [0x78, 0x70, 0x70],
+            Material::Sand => [0xd8, 0xc0, 0x78],
+            Material::Bedrock => [0x3a, 0x34, 0x30],
+        }
+    }
+}
+
+/// Rectangle of pixels of landscape which have been changed since
+/// last update of RGBA buffer. Right and bottom bounds are exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirtyRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl DirtyRect {
+    #[inline]
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    #[inline]
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+
+    #[inline]
+    pub fn area(&self) -> usize {
+        self.width() as usize * self.height() as usize
+    }
+
+    /// Returns `true` if rectangles overlap or touch each other.
+    fn touches(&self, other: &DirtyRect) -> bool {
+        self.left <= other.right
+            && other.left <= self.right
+            && self.top <= other.bottom
+            && other.top <= self.bottom
+    }
+
+    fn union(&self, other: &DirtyRect) -> DirtyRect {
+        DirtyRect {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+}
+
+/// Style of generated landscape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terrain {
+    Hills,
+    Mountains,
+    Plains,
+    CentralMountain,
+    Valleys,
+    Islands,
+}
+
+impl Default for Terrain {
+    fn default() -> Self {
+        Terrain::Hills
+    }
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 6] = [
+        Terrain::Hills,
+        Terrain::Mountains,
+        Terrain::Plains,
+        Terrain::CentralMountain,
+        Terrain::Valleys,
+        Terrain::Islands,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Terrain::Hills => "hills",
+            Terrain::Mountains => "mountains",
+            Terrain::Plains => "plains",
+            Terrain::CentralMountain => "central_mountain",
+            Terrain::Valleys => "valleys",
+            Terrain::Islands => "islands",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Terrain::Hills => "Rolling Hills",
+            Terrain::Mountains => "Jagged Mountains",
+            Terrain::Plains => "Plains",
+            Terrain::CentralMountain => "Central Mountain",
+            Terrain::Valleys => "Valleys",
+            Terrain::Islands => "Islands",
+        }
+    }
+}
+
+/// Parameters of noise used for generating of landscape.
+/// Values which depend on size of landscape are relative to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerrainParams {
+    /// Count of noise layers with increasing frequency.
+    pub octaves: usize,
+    /// Count of noise periods along width of landscape.
+    pub frequency: f64,
+    /// Multiplier of frequency of every next octave.
+    pub lacunarity: f64,
+    /// Multiplier of amplitude of every next octave.
+    pub persistence: f64,
+    /// Amplitude of landscape relative to its height.
+    pub amplitude: f64,
+    /// Average level of ground relative to height of landscape.
+    pub base_height: f64,
+}
+
+impl Default for TerrainParams {
+    fn default() -> Self {
+        TerrainParams {
+            octaves: 4,
+            frequency: 2.,
+            lacunarity: 2.,
+            persistence: 0.5,
+            amplitude: 0.5,
+            base_height: 0.5,
+        }
+    }
+}
+
+impl TerrainParams {
+    pub const MAX_OCTAVES: usize = 8;
+    pub const MAX_FREQUENCY: f64 = 10.;
+    pub const MAX_LACUNARITY: f64 = 4.;
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.octaves < 1 || self.octaves > Self::MAX_OCTAVES {
+            return Err(format!("Invalid count of octaves: {}", self.octaves));
+        }
+        let ranges = [
+            ("frequency", self.frequency, 0.5, Self::MAX_FREQUENCY),
+            ("lacunarity", self.lacunarity, 1., Self::MAX_LACUNARITY),
+            ("persistence", self.persistence, 0.1, 1.),
+            ("amplitude", self.amplitude, 0., 1.),
+            ("base height", self.base_height, 0., 1.),
+        ];
+        for &(name, value, min, max) in ranges.iter() {
+            if !(min..=max).contains(&value) {
+                return Err(format!("Invalid {} of terrain: {}", name, value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses parameters from values in the same order as they are
+    /// written by `Display`.
+    pub fn parse<'a, I>(values: &mut I) -> Result<Self, String>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let params = TerrainParams {
+            octaves: parse_value(values, "octaves")?,
+            frequency: parse_value(values, "frequency")?,
+            lacunarity: parse_value(values, "lacunarity")?,
+            persistence: parse_value(values, "persistence")?,
+            amplitude: parse_value(values, "amplitude")?,
+            base_height: parse_value(values, "base_height")?,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+impl fmt::Display for TerrainParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            self.octaves,
+            self.frequency,
+            self.lacunarity,
+            self.persistence,
+            self.amplitude,
+            self.base_height
+        )
+    }
+}
+
+impl fmt::Display for Terrain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Terrain {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Terrain::ALL
+            .iter()
+            .find(|t| t.name() == s)
+            .cloned()
+            .ok_or_else(|| format!("Unknown terrain: {}", s))
+    }
+}
 
 pub struct Landscape {
     width: i32,
     height: i32,
     buffer: Vec<u8>,
+    // Pixels of ground burnt by explosions, they are only drawn darker.
+    scorched: Vec<bool>,
     rgba_buffer: Vec<u8>,
     noise: Fbm,
-    amplitude: f64,
+    params: TerrainParams,
     pub dx: i32,
-    changed: bool,
-    subsidence_started: Option<Instant>,
+    // Regions which must be redrawn in RGBA buffer.
+    dirty_rects: Vec<DirtyRect>,
+    // Game time of start of subsidence.
+    subsidence_started: Option<f32>,
     // Last position of virtual pixel of landscape on the way of it falling.
     // Used for calculate speed of fall.
     subsidence_last_pos: u32,
-    // "Skip" and "take" used for optimize process of landscape subsidence.
-    subsidence_skip: usize,
-    subsidence_take: usize,
+    // State of every column during subsidence.
+    subsidence_columns: Vec<FallingColumn>,
+    // Loose ground thrown out by explosions.
+    debris: Vec<Debris>,
 }
 
 impl Landscape {
@@ -38,27 +337,40 @@ impl Landscape {
             width: width as i32,
             height: height as i32,
             buffer: vec![0; res_size],
+            scorched: vec![false; res_size],
             rgba_buffer: vec![0; res_size * 4],
-            amplitude: f64::from(height) / 2.,
+            params: TerrainParams::default(),
             dx: 0,
-            noise: Self::create_noise(width as i32, 0),
-            changed: true,
+            noise: Self::create_noise(width as i32, 0, &TerrainParams::default()),
+            dirty_rects: vec![DirtyRect {
+                left: 0,
+                top: 0,
+                right: width as i32,
+                bottom: height as i32,
+            }],
             subsidence_started: None,
             subsidence_last_pos: 0,
-            subsidence_skip: 0,
-            subsidence_take: stride,
+            subsidence_columns: vec![FallingColumn::new(height as usize); stride],
+            debris: Vec::new(),
         })
     }
 
-    fn create_noise(width: i32, seed: u32) -> Fbm {
+    fn create_noise(width: i32, seed: u32, params: &TerrainParams) -> Fbm {
         Fbm::new()
             .set_seed(seed)
-            .set_octaves(4)
-            .set_frequency(2. / f64::from(width))
+            .set_octaves(params.octaves)
+            .set_frequency(params.frequency / f64::from(width))
+            .set_lacunarity(params.lacunarity)
+            .set_persistence(params.persistence)
     }
 
     pub fn set_seed(&mut self, seed: u32) {
-        self.noise = Self::create_noise(self.width, seed);
+        self.noise = Self::create_noise(self.width, seed, &self.params);
+    }
+
+    pub fn set_params(&mut self, params: TerrainParams) {
+        self.params = params;
+        self.noise = Self::create_noise(self.width, self.seed(), &params);
     }
 
     pub fn seed(&self) -> u32 {
@@ -67,12 +379,46 @@ impl Landscape {
 
     #[inline]
     pub fn changed(&self) -> bool {
-        self.changed
+        !self.dirty_rects.is_empty()
     }
 
+    /// Marks all pixels of landscape as changed.
     #[inline]
     pub fn set_changed(&mut self) {
-        self.changed = true;
+        self.set_rect_changed(0, 0, self.width, self.height);
+    }
+
+    /// Marks pixels inside of given rectangle as changed.
+    /// Shading of ground below of changed pixels depends on them,
+    /// so these pixels are also marked.
+    pub fn set_rect_changed(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        let mut rect = DirtyRect {
+            left: x.max(0),
+            top: y.max(0),
+            right: (x + width).min(self.width),
+            bottom: (y + height + i32::from(MAX_SHADED_DEPTH)).min(self.height),
+        };
+        if rect.width() <= 0 || rect.height() <= 0 {
+            return;
+        }
+        // Merge new rectangle with all rectangles touching it
+        while let Some(i) = self.dirty_rects.iter().position(|r| r.touches(&rect)) {
+            rect = rect.union(&self.dirty_rects.swap_remove(i));
+        }
+        self.dirty_rects.push(rect);
+        if self.dirty_rects.len() > MAX_DIRTY_RECTS {
+            let first = self.dirty_rects[0];
+            let bounds = self.dirty_rects.iter().fold(first, |b, r| b.union(r));
+            self.dirty_rects.clear();
+            self.dirty_rects.push(bounds);
+        }
+    }
+
+    /// Returns regions which have been changed since last update
+    /// of RGBA buffer.
+    #[inline]
+    pub fn dirty_rects(&self) -> &[DirtyRect] {
+        &self.dirty_rects
     }
 
     #[inline]
@@ -80,32 +426,105 @@ impl Landscape {
         (self.width as u16, self.height as u16)
     }
 
-    pub fn generate(&mut self) {
+    /// Returns top of ground in given column for given style of landscape.
+    fn surface(&self, terrain: Terrain, x: i32) -> f64 {
+        let width = f64::from(self.width);
+        let height = f64::from(self.height);
+        let base = height * (1. - self.params.base_height);
+        let amplitude = height * self.params.amplitude;
+        let sx = f64::from(x + self.dx);
+        let value = self.noise.get([sx, 0.]);
+        match terrain {
+            Terrain::Hills => base + value * amplitude,
+            Terrain::Mountains => {
+                // Ridges appear where noise crosses zero
+                let ridges = 1. - 2. * value.abs();
+                let details = self.noise.get([sx * 4., 1.]);
+                base - (0.7 * ridges - 0.1 * details - 0.1) * amplitude
+            }
+            Terrain::Plains => {
+                let level = (value * 2.).round() / 2.;
+                base - (0.6 * level.max(0.) - 0.4) * amplitude
+            }
+            Terrain::CentralMountain => {
+                let distance = (f64::from(x) - width / 2.) / (width / 5.);
+                let mountain = (-distance * distance).exp();
+                base - (1.2 * mountain - 0.16 * value - 0.7) * amplitude
+            }
+            Terrain::Valleys => {
+                let valley = 1. - (value.abs() * 4.).min(1.);
+                base - (0.4 - 1.1 * valley) * amplitude
+            }
+            Terrain::Islands if value > -0.1 => base - (0.1 - 0.5 * value) * amplitude,
+            Terrain::Islands => height - PIT_FLOOR,
+        }
+    }
+
+    pub fn generate(&mut self, terrain: Terrain) {
         let stride = self.width as usize;
-        let y_center: f64 = f64::from(self.height) / 2.;
+        let height = self.height as usize;
+        let bedrock_top = height.saturating_sub(BEDROCK_DEPTH);
+        self.clear_scorch();
+        self.debris.clear();
+        self.set_changed();
 
         for x in 0..self.width {
+            let top = self.surface(terrain, x).round().max(0.) as usize;
+            let top = top.min(height);
+            // Layers of materials vary along landscape
             let sx = f64::from(x + self.dx);
-            let value = self.noise.get([sx, 0.]) * self.amplitude;
-            let y = (y_center + value).round().max(0.) as usize;
-            let y = y.min(self.height as usize);
-            let index = y * stride + (x as usize);
+            let sand_depth = (self.noise.get([sx * 3., 2.]) * 2. * SAND_DEPTH).max(0.);
+            let rock_depth = ROCK_DEPTH * (1. + 0.5 * self.noise.get([sx * 2., 3.]));
+            let sand_bottom = top + sand_depth as usize;
+            let rock_top = top + rock_depth.max(0.) as usize;
 
-            if y > 0 {
-                self.buffer
-                    .iter_mut()
-                    .skip(x as usize)
-                    .step_by(stride)
-                    .take(y)
-                    .for_each(|v| *v = 0);
+            let column = self.buffer.iter_mut().skip(x as usize).step_by(stride);
+            for (y, v) in column.enumerate() {
+                let material = if y < top {
+                    Material::Empty
+                } else if y >= bedrock_top {
+                    Material::Bedrock
+                } else if y >= rock_top {
+                    Material::Rock
+                } else if y < sand_bottom {
+                    Material::Sand
+                } else {
+                    Material::Dirt
+                };
+                *v = material as u8;
             }
+        }
+    }
 
-            self.buffer
-                .iter_mut()
-                .skip(index)
-                .step_by(stride)
-                .for_each(|v| *v = 1);
+    /// Returns all pixels of landscape row by row.
+    #[inline]
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Replaces all pixels of landscape.
+    pub fn set_buffer(&mut self, buffer: Vec<u8>) -> Result<(), String> {
+        if buffer.len() != self.buffer.len() {
+            return Err("Size of buffer doesn't match size of landscape".into());
+        }
+        if buffer.iter().any(|&v| Material::from_value(v).is_none()) {
+            return Err("Unknown material in buffer of landscape".into());
         }
+        self.buffer = buffer;
+        self.clear_scorch();
+        self.debris.clear();
+        self.set_changed();
+        Ok(())
+    }
+
+    /// Replaces all pixels of landscape by pixels of given map
+    /// scaled to size of landscape.
+    pub fn load_map(&mut self, map: &Map) {
+        let (width, height) = self.size();
+        self.buffer = map.scaled_pixels(width, height);
+        self.clear_scorch();
+        self.debris.clear();
+        self.set_changed();
     }
 
     /// Get mutable slice with row of pixels given length
@@ -119,6 +538,35 @@ impl Landscape {
         Some(&mut self.buffer[index..index + length])
     }
 
+    /// Removes landscape along a straight tunnel with given half of width.
+    /// Bedrock isn't removed.
+    pub fn dig_tunnel(&mut self, start: Point2, direction: Vector2, length: f32, radius: f32) {
+        let r = radius as i32;
+        let mut distance = 0.0;
+        while distance <= length {
+            let center = start + direction * distance;
+            let (cx, cy) = (center.x as i32, center.y as i32);
+            for dy in -r..=r {
+                let dx = ((r * r - dy * dy) as f32).sqrt() as i32;
+                let x = (cx - dx).max(0);
+                let len = (cx + dx + 1 - x).max(0) as u16;
+                if let Some(pixels) = self.get_pixels_line_mut((x, cy + dy), len) {
+                    pixels
+                        .iter_mut()
+                        .filter(|c| **c != Material::Bedrock as u8)
+                        .for_each(|c| *c = 0);
+                }
+            }
+            distance += 1.0;
+        }
+        let end = start + direction * length;
+        let left = start.x.min(end.x) as i32 - r;
+        let top = start.y.min(end.y) as i32 - r;
+        let right = start.x.max(end.x) as i32 + r + 1;
+        let bottom = start.y.max(end.y) as i32 + r + 1;
+        self.set_rect_changed(left, top, right - left, bottom - top);
+    }
+
     pub fn is_not_empty(&self, x: i32, y: i32) -> bool {
         if x < 0 || y < 0 || x >= self.width || y >= self.height as i32 {
             return false;
@@ -127,27 +575,185 @@ impl Landscape {
         self.buffer[index] > 0
     }
 
-    pub fn create_image(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult<graphics::Image> {
-        let buf = unsafe { self.rgba_buffer.align_to_mut::<u32>().1 };
-        for (&v, d) in self.buffer.iter().zip(buf) {
-            *d = if v == 0 { 0 } else { 0xff_40_71_9c } // 0xff_cf_bd_00
+    /// Marks solid pixels inside of circle as scorched.
+    pub fn scorch(&mut self, center: Point2, radius: f32) {
+        let r = radius as i32;
+        let (cx, cy) = (center.x as i32, center.y as i32);
+        for y in (cy - r).max(0)..(cy + r + 1).min(self.height) {
+            let dy = y - cy;
+            let dx = ((r * r - dy * dy) as f32).sqrt() as i32;
+            let left = (cx - dx).max(0);
+            let right = (cx + dx + 1).min(self.width);
+            for x in left..right {
+                let index = (y * self.width + x) as usize;
+                if self.buffer[index] != 0 {
+                    self.scorched[index] = true;
+                }
+            }
         }
-        self.changed = false;
+        self.set_rect_changed(cx - r, cy - r, 2 * r + 1, 2 * r + 1);
+    }
 
-        graphics::Image::from_rgba8(
-            ctx,
-            self.width as u16,
-            self.height as u16,
-            &self.rgba_buffer,
-        )
+    fn clear_scorch(&mut self) {
+        self.scorched.iter_mut().for_each(|s| *s = false);
+    }
+
+    /// Returns debris flying over landscape.
+    #[inline]
+    pub fn debris(&self) -> &[Debris] {
+        &self.debris
+    }
+
+    pub fn throw_debris<I: IntoIterator<Item = Debris>>(&mut self, debris: I) {
+        self.debris.extend(debris);
+    }
+
+    /// Moves flying debris and puts landed debris into landscape.
+    pub fn update_debris(&mut self, now: f32) {
+        let mut debris = std::mem::take(&mut self.debris);
+        let mut flying = Vec::with_capacity(debris.len());
+        for mut d in debris.drain(..) {
+            match d.fly(now, self) {
+                Flight::Flying => flying.push(d),
+                Flight::Landed(x, y) => self.land_debris(x, y, d.material),
+                Flight::Lost => (),
+            }
+        }
+        self.debris = flying;
+    }
+
+    fn land_debris(&mut self, x: i32, mut y: i32, material: Material) {
+        // Other debris may have landed at the same place
+        while self.is_not_empty(x, y) {
+            y -= 1;
+        }
+        if y < 0 {
+            return;
+        }
+        let index = (y * self.width + x) as usize;
+        self.buffer[index] = material as u8;
+        self.scorched[index] = false;
+        self.set_rect_changed(x, y, 1, 1);
+        if self.subsidence_started.is_some() {
+            // Debris may land on side of slope and fall further
+            let column = &mut self.subsidence_columns[x as usize];
+            column.top = column.top.min(y as usize);
+            column.uncheck(y as usize, y as usize);
+        }
     }
 
-    pub fn subsidence(&mut self) {
+    /// Returns RGBA pixels of landscape image and resets "changed" flag.
+    /// Surface of ground is covered by grass or snow and ground becomes
+    /// darker with depth below surface.
+    pub fn rgba_buffer(&mut self, textures: &Textures) -> &[u8] {
+        self.update_rgba_buffer(textures);
+        &self.rgba_buffer
+    }
+
+    /// Redraws only changed regions of RGBA buffer and returns them.
+    pub fn update_rgba_buffer(&mut self, textures: &Textures) -> Vec<DirtyRect> {
+        let rects = std::mem::take(&mut self.dirty_rects);
+        for rect in rects.iter() {
+            self.render_rect(rect, textures);
+        }
+        rects
+    }
+
+    /// Returns copy of RGBA pixels inside of given rectangle row by row.
+    pub fn rgba_pixels(&self, rect: &DirtyRect) -> Vec<u8> {
+        let stride = self.width as usize * 4;
+        let left = rect.left as usize * 4;
+        let right = rect.right as usize * 4;
+        let mut pixels = Vec::with_capacity(rect.area() * 4);
+        for y in rect.top as usize..rect.bottom as usize {
+            pixels.extend_from_slice(&self.rgba_buffer[y * stride + left..y * stride + right]);
+        }
+        pixels
+    }
+
+    fn render_rect(&mut self, rect: &DirtyRect, textures: &Textures) {
+        let width = self.width as usize;
+        let snow_line = (self.height as f32 * SNOW_LINE) as usize;
+        let (left, right) = (rect.left as usize, rect.right as usize);
+        let (top, bottom) = (rect.top as usize, rect.bottom as usize);
+        // Depth below surface of current pixel of every column.
+        // Deeper pixels than max shaded depth look the same.
+        let mut depths: Vec<u16> = (left..right)
+            .map(|x| {
+                let column = (top.saturating_sub(MAX_SHADED_DEPTH as usize)..top).rev();
+                column
+                    .take_while(|&y| self.buffer[y * width + x] != 0)
+                    .count() as u16
+            })
+            .collect();
+        for y in top..bottom {
+            let row = y * width;
+            let rgba_row = &mut self.rgba_buffer[(row + left) * 4..(row + right) * 4];
+            let pixels = self.buffer[row + left..row + right]
+                .iter()
+                .zip(&self.scorched[row + left..row + right])
+                .zip(rgba_row.chunks_mut(4))
+                .zip(depths.iter_mut());
+            for (x, (((&value, &scorched), rgba), depth)) in pixels.enumerate() {
+                let x = left + x;
+                let material = match Material::from_value(value) {
+                    Some(Material::Empty) | None => {
+                        *depth = 0;
+                        rgba.copy_from_slice(&[0, 0, 0, 0]);
+                        continue;
+                    }
+                    Some(material) => material,
+                };
+                *depth = depth.saturating_add(1);
+
+                let on_surface = *depth <= SURFACE_DEPTH && !scorched;
+                let color = match material {
+                    Material::Bedrock => textures.color(material, x, y),
+                    _ if on_surface && y < snow_line => SNOW_COLOR,
+                    Material::Dirt if on_surface => GRASS_COLOR,
+                    _ => textures.color(material, x, y),
+                };
+                let shaded_depth = f32::from((*depth).min(MAX_SHADED_DEPTH));
+                let mut brightness =
+                    1. - DEPTH_SHADING * shaded_depth / f32::from(MAX_SHADED_DEPTH);
+                if scorched {
+                    brightness *= SCORCH_BRIGHTNESS;
+                }
+                for (c, &v) in rgba.iter_mut().zip(color.iter()) {
+                    *c = (f32::from(v) * brightness) as u8;
+                }
+                rgba[3] = 0xff;
+            }
+        }
+    }
+
+    pub fn subsidence(&mut self, now: f32) {
         if self.subsidence_started.is_none() {
-            self.subsidence_started = Some(Instant::now());
+            self.subsidence_started = Some(now);
             self.subsidence_last_pos = 0;
-            self.subsidence_skip = 0;
-            self.subsidence_take = self.width as usize;
+            let stride = self.width as usize;
+            let height = self.height as usize;
+            let columns = &mut self.subsidence_columns;
+            columns
+                .iter_mut()
+                .for_each(|c| *c = FallingColumn::new(height));
+            // Rows are scanned in order of pixels in memory, it is much
+            // faster than scanning of every column.
+            for y in (0..height).rev() {
+                let row = &self.buffer[y * stride..(y + 1) * stride];
+                for (column, &value) in columns.iter_mut().zip(row) {
+                    if value == Material::Empty as u8 {
+                        continue;
+                    }
+                    column.top = y;
+                    if column.settled == y + 1 {
+                        column.settled = y;
+                    }
+                }
+            }
+            for column in columns.iter_mut() {
+                column.unchecked = Some((column.top, height - 1));
+            }
         }
     }
 
@@ -156,60 +762,526 @@ impl Landscape {
     }
 
     /// Returns `true` if current subsidence has finished.
-    pub fn update(&mut self) -> bool {
+    ///
+    /// Every column falls separately. Falling pixels of column move down
+    /// by distance passed since last update, but not lower than their
+    /// place in compacted column. Only rows between top of column and
+    /// its settled part are processed. Sand lying on slope which is
+    /// steeper than angle of repose slides down. Subsidence doesn't
+    /// finish while debris is flying.
+    pub fn update(&mut self, now: f32) -> bool {
         if let Some(subsidence_started) = self.subsidence_started {
-            let time = subsidence_started.elapsed().as_secs_f32();
+            self.update_debris(now);
+            let time = now - subsidence_started;
             let subsidence_cur_pos = (G * time * time * TIME_SCALE).round() as u32;
             let delta = subsidence_cur_pos - self.subsidence_last_pos;
             self.subsidence_last_pos = subsidence_cur_pos;
-            let stride = self.width as usize;
+            if delta == 0 {
+                return false;
+            }
 
-            for _ in 0..delta {
-                let mut changed = false;
-                let mut cur_row_index = stride * self.height as usize;
-                let mut left_changed_pos: usize = self.subsidence_take;
-                let mut right_changed_pos = 0;
-
-                for _ in 1..self.height {
-                    cur_row_index -= stride;
-                    let (top_rows, current_row) = self.buffer.split_at_mut(cur_row_index);
-                    let (_, top_row) = top_rows.split_at_mut(cur_row_index - stride);
-                    let pixels_for_change = top_row
-                        .iter_mut()
-                        .zip(current_row)
-                        .skip(self.subsidence_skip)
-                        .take(self.subsidence_take)
-                        .enumerate()
-                        .filter(|(_, (&mut top_pixel, &mut cur_pixel))| {
-                            cur_pixel == 0 && top_pixel != 0
-                        });
-                    let min_max = pixels_for_change
-                        .map(|(i, (top_pixel, cur_pixel))| {
-                            *cur_pixel = *top_pixel;
-                            *top_pixel = 0;
-                            i
-                        })
-                        .minmax();
-
-                    if let Some((min, max)) = min_max.into_option() {
-                        changed = true;
-                        left_changed_pos = left_changed_pos.min(min);
-                        right_changed_pos = right_changed_pos.max(max);
-                    };
-                }
+            let mut changes = Changes::new(self.width as usize, self.height as usize);
+            for x in 0..self.width as usize {
+                self.fall_column(x, delta as usize, &mut changes);
+            }
+            self.slide_sand(&mut changes);
+            if changes.is_empty() && self.debris.is_empty() {
+                self.subsidence_started = None;
+                return true;
+            }
+            if changes.is_empty() {
+                return false;
+            }
+
+            self.set_rect_changed(
+                changes.left as i32,
+                changes.top as i32,
+                (changes.right - changes.left + 1) as i32,
+                (changes.bottom - changes.top + 1) as i32,
+            );
+        }
+
+        false
+    }
+
+    /// Moves falling pixels of column down by given distance.
+    /// Pixels are processed from bottom to top, so every pixel lands
+    /// on the pixel below of it which is already in its new place.
+    fn fall_column(&mut self, x: usize, distance: usize, changes: &mut Changes) {
+        let stride = self.width as usize;
+        let column = &mut self.subsidence_columns[x];
+        // New row of the nearest pixel below of current one
+        let mut floor = column.settled;
+        let mut settled = true;
+        let mut top = column.settled;
+        for y in (column.top..column.settled).rev() {
+            let index = y * stride + x;
+            let value = self.buffer[index];
+            if value == Material::Empty as u8 {
+                settled = false;
+                continue;
+            }
+            let new_y = if value == Material::Bedrock as u8 {
+                y
+            } else {
+                (y + distance).min(floor - 1)
+            };
+            floor = new_y;
+            top = new_y;
+            // Pixel lying on settled part of column is also settled
+            if settled && new_y == column.settled - 1 {
+                column.settled = new_y;
+            } else {
+                settled = false;
+            }
+            if new_y == y {
+                continue;
+            }
+            let new_index = new_y * stride + x;
+            self.buffer[new_index] = value;
+            self.buffer[index] = 0;
+            // Burnt ground stays burnt while falling
+            self.scorched[new_index] = self.scorched[index];
+            self.scorched[index] = false;
+            changes.add(x, y, new_y);
+        }
+        column.top = top;
+    }
 
-                self.subsidence_skip += left_changed_pos;
-                self.subsidence_take = right_changed_pos + 1;
+    /// Moves sand lying on slopes steeper than angle of repose to
+    /// empty place below. Sand is searched only near pixels changed
+    /// since previous search.
+    fn slide_sand(&mut self, changes: &mut Changes) {
+        let stride = self.width as usize;
+        let height = self.height as usize;
+        // Grain of sand slides if there is empty place lower than it
+        // in this count of columns beside of it.
+        let run = (1. / ANGLE_OF_REPOSE.to_radians().tan()).round().max(1.) as usize;
+        // Rows in which grains of sand may have become loose
+        let ranges: Vec<Option<(usize, usize)>> = (0..stride)
+            .map(|x| {
+                let columns = &self.subsidence_columns;
+                let neighbours = columns[x.saturating_sub(run)..(x + run + 1).min(stride)].iter();
+                let rows = neighbours
+                    .filter_map(|c| c.unchecked)
+                    .chain(changes.rows(x, run));
+                rows.fold1(|(t1, b1), (t2, b2)| (t1.min(t2), b1.max(b2)))
+                    .map(|(top, bottom)| (top.saturating_sub(1).max(columns[x].top), bottom))
+            })
+            .collect();
+        self.subsidence_columns
+            .iter_mut()
+            .for_each(|c| c.unchecked = None);
 
-                if changed {
-                    self.changed = true;
+        let buffer = &self.buffer;
+        // Returns column of empty place where grain of sand can slide
+        // from given pixel in given direction.
+        let slide_target = |x: usize, y: usize, left: bool| {
+            for distance in 1..=run {
+                let target = if left {
+                    x.checked_sub(distance)?
                 } else {
-                    self.subsidence_started = None;
-                    return true;
+                    Some(x + distance).filter(|&t| t < stride)?
+                };
+                if buffer[y * stride + target] != 0 {
+                    return None;
+                }
+                if buffer[(y + 1) * stride + target] == 0 {
+                    return Some(target);
+                }
+            }
+            None
+        };
+        // Pixels which slide as (from_x, to_x, from_y)
+        let mut slides = Vec::new();
+        for (x, range) in ranges.into_iter().enumerate() {
+            let (top, bottom) = match range {
+                Some(range) => range,
+                None => continue,
+            };
+            for y in top..=bottom.min(height - 2) {
+                if buffer[y * stride + x] != Material::Sand as u8
+                    || buffer[(y + 1) * stride + x] == 0
+                {
+                    continue;
+                }
+                // Neighbour grains prefer different sides
+                let left = (x + y) % 2 == 0;
+                let target = slide_target(x, y, left).or_else(|| slide_target(x, y, !left));
+                if let Some(target) = target {
+                    slides.push((x, target, y));
                 }
             }
         }
 
-        false
+        for (from_x, to_x, y) in slides {
+            let from = y * stride + from_x;
+            let to = (y + 1) * stride + to_x;
+            // Two grains may slide into the same place
+            if self.buffer[from] == 0 || self.buffer[to] != 0 {
+                continue;
+            }
+            self.buffer[to] = self.buffer[from];
+            self.buffer[from] = 0;
+            self.scorched[to] = self.scorched[from];
+            self.scorched[from] = false;
+            changes.add(from_x, y, y);
+            changes.add(to_x, y + 1, y + 1);
+
+            let column = &mut self.subsidence_columns[from_x];
+            column.settled = column.settled.max(y + 1);
+            column.uncheck(y, y);
+            let column = &mut self.subsidence_columns[to_x];
+            column.top = column.top.min(y + 1);
+            column.uncheck(y + 1, y + 1);
+        }
+    }
+}
+
+/// State of column of landscape during subsidence.
+#[derive(Debug, Clone, Copy)]
+struct FallingColumn {
+    /// There are no pixels above of this row.
+    top: usize,
+    /// Pixels in this row and below of it are not falling.
+    settled: usize,
+    /// Rows changed by sliding of sand which haven't been checked
+    /// for loose sand yet.
+    unchecked: Option<(usize, usize)>,
+}
+
+impl FallingColumn {
+    fn new(height: usize) -> Self {
+        FallingColumn {
+            top: height,
+            settled: height,
+            unchecked: None,
+        }
+    }
+
+    fn uncheck(&mut self, top: usize, bottom: usize) {
+        self.unchecked = Some(match self.unchecked {
+            Some((t, b)) => (t.min(top), b.max(bottom)),
+            None => (top, bottom),
+        });
+    }
+}
+
+/// Pixels changed during one update of subsidence.
+struct Changes {
+    // Bounds of changed rows of every column.
+    rows: Vec<Option<(usize, usize)>>,
+    left: usize,
+    right: usize,
+    top: usize,
+    bottom: usize,
+}
+
+impl Changes {
+    fn new(width: usize, height: usize) -> Self {
+        Changes {
+            rows: vec![None; width],
+            left: width,
+            right: 0,
+            top: height,
+            bottom: 0,
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.left > self.right
+    }
+
+    /// Returns bounds of changed rows in given column and in given
+    /// count of columns beside of it.
+    fn rows(&self, x: usize, count: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let right = (x + count + 1).min(self.rows.len());
+        self.rows[x.saturating_sub(count)..right]
+            .iter()
+            .filter_map(|r| *r)
+    }
+
+    /// Adds changed pixels of column between given rows.
+    #[inline]
+    fn add(&mut self, x: usize, top: usize, bottom: usize) {
+        self.rows[x] = Some(match self.rows[x] {
+            Some((t, b)) => (t.min(top), b.max(bottom)),
+            None => (top, bottom),
+        });
+        self.left = self.left.min(x);
+        self.right = self.right.max(x);
+        self.top = self.top.min(top);
+        self.bottom = self.bottom.max(bottom);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::clock::Clock;
+
+    /// Digs a hole under the hill and waits for end of subsidence.
+    fn subside(landscape: &mut Landscape) -> u64 {
+        landscape.set_seed(42);
+        landscape.generate(Terrain::Hills);
+        for y in 350..400 {
+            let pixels = landscape.get_pixels_line_mut((100, y), 200).unwrap();
+            pixels.iter_mut().for_each(|c| *c = 0);
+        }
+
+        subside_to_end(landscape)
+    }
+
+    /// Runs subsidence until it ends, returns count of ticks.
+    fn subside_to_end(landscape: &mut Landscape) -> u64 {
+        let mut clock = Clock::new();
+        landscape.subsidence(clock.now());
+        loop {
+            clock.tick();
+            if landscape.update(clock.now()) {
+                return clock.ticks();
+            }
+        }
+    }
+
+    /// Returns empty buffer of landscape with row of bedrock at the bottom.
+    fn bedrock_floor(width: usize, height: usize) -> Vec<u8> {
+        let mut buffer = vec![0; width * height];
+        buffer[(height - 1) * width..]
+            .iter_mut()
+            .for_each(|v| *v = Material::Bedrock as u8);
+        buffer
+    }
+
+    #[test]
+    fn test_subsidence_is_deterministic() {
+        let mut landscape1 = Landscape::new(400, 400).unwrap();
+        let ticks1 = subside(&mut landscape1);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        let mut landscape2 = Landscape::new(400, 400).unwrap();
+        let ticks2 = subside(&mut landscape2);
+
+        assert_eq!(ticks1, ticks2);
+        assert!(landscape1.buffer == landscape2.buffer);
+        // Nothing hangs in the air after subsidence.
+        for x in 100..300 {
+            let column = (0..400).map(|y| landscape1.is_not_empty(x, y));
+            let gaps = column
+                .tuple_windows()
+                .filter(|&(top, bottom)| top && !bottom);
+            assert_eq!(gaps.count(), 0);
+        }
+    }
+
+    #[test]
+    fn test_terrains() {
+        let mut landscape = Landscape::new(400, 300).unwrap();
+        landscape.set_seed(7);
+        let mut buffers: Vec<Vec<u8>> = Vec::new();
+        for &terrain in Terrain::ALL.iter() {
+            landscape.generate(terrain);
+            // Every column has ground at the bottom
+            for x in 0..400 {
+                assert!(landscape.is_not_empty(x, 299), "{} at {}", terrain, x);
+            }
+            assert!(!buffers.contains(&landscape.buffer), "{}", terrain);
+            buffers.push(landscape.buffer.clone());
+            assert_eq!(terrain.name().parse::<Terrain>(), Ok(terrain));
+        }
+    }
+
+    #[test]
+    fn test_terrain_params() {
+        let params = TerrainParams {
+            octaves: 6,
+            frequency: 3.5,
+            lacunarity: 2.2,
+            persistence: 0.4,
+            amplitude: 0.3,
+            base_height: 0.6,
+        };
+        let text = params.to_string();
+        assert_eq!(
+            TerrainParams::parse(&mut text.split_whitespace()),
+            Ok(params)
+        );
+        assert!(TerrainParams::parse(&mut "0 2 2 0.5 0.5 0.5".split_whitespace()).is_err());
+
+        // Flat landscape at given level
+        let mut landscape = Landscape::new(200, 100).unwrap();
+        landscape.set_params(TerrainParams {
+            amplitude: 0.,
+            base_height: 0.25,
+            ..TerrainParams::default()
+        });
+        landscape.generate(Terrain::Hills);
+        for x in 0..200 {
+            assert!(!landscape.is_not_empty(x, 74));
+            assert!(landscape.is_not_empty(x, 75));
+        }
+    }
+
+    #[test]
+    fn test_sand_slides_during_subsidence() {
+        let mut buffer = bedrock_floor(100, 50);
+        for y in 39..49 {
+            buffer[y * 100 + 20] = Material::Dirt as u8;
+            buffer[y * 100 + 60] = Material::Sand as u8;
+        }
+        let mut landscape = Landscape::new(100, 50).unwrap();
+        landscape.set_buffer(buffer).unwrap();
+        subside_to_end(&mut landscape);
+
+        let column = |x: i32| (0..49).filter(|&y| landscape.is_not_empty(x, y)).count();
+        assert_eq!(column(20), 10);
+        assert!(column(60) < 10);
+        let sand = bytecount::count(&landscape.buffer, Material::Sand as u8);
+        assert_eq!(sand, 10);
+        assert!(landscape.buffer[49 * 100..]
+            .iter()
+            .all(|&v| v == Material::Bedrock as u8));
+
+        let mut buffer = landscape.buffer.clone();
+        buffer[0] = 7;
+        assert!(landscape.set_buffer(buffer).is_err());
+    }
+
+    #[test]
+    fn test_column_is_compacted() {
+        let (d, r, s, b) = (
+            Material::Dirt as u8,
+            Material::Rock as u8,
+            Material::Sand as u8,
+            Material::Bedrock as u8,
+        );
+        let mut column = bedrock_floor(1, 20);
+        column[..19].copy_from_slice(&[0, 0, d, r, 0, 0, d, 0, 0, b, 0, 0, s, 0, 0, d, 0, 0, 0]);
+        let mut landscape = Landscape::new(1, 20).unwrap();
+        landscape.set_buffer(column).unwrap();
+        subside_to_end(&mut landscape);
+
+        // Pixels keep their order and stay on bedrock
+        let expected = vec![0, 0, 0, 0, 0, 0, d, r, d, b, 0, 0, 0, 0, 0, 0, 0, s, d, b];
+        assert_eq!(landscape.buffer, expected);
+    }
+
+    #[test]
+    fn test_sand_pile_has_angle_of_repose() {
+        let mut buffer = bedrock_floor(100, 60);
+        for y in 19..59 {
+            buffer[y * 100 + 50] = Material::Sand as u8;
+        }
+        let mut landscape = Landscape::new(100, 60).unwrap();
+        landscape.set_buffer(buffer).unwrap();
+        subside_to_end(&mut landscape);
+
+        let heights: Vec<usize> = (0..100)
+            .map(|x| (0..59).filter(|&y| landscape.is_not_empty(x, y)).count())
+            .collect();
+        assert_eq!(heights.iter().sum::<usize>(), 40);
+        // Slope of pile isn't steeper than one pixel per two columns
+        for x in 0..98 {
+            assert!((heights[x] as i32 - heights[x + 2] as i32).abs() <= 1);
+        }
+        assert!(heights[50] >= 3);
+    }
+
+    #[test]
+    fn test_rgba_buffer() {
+        let mut landscape = Landscape::new(100, 300).unwrap();
+        landscape.set_params(TerrainParams {
+            amplitude: 0.,
+            base_height: 0.5,
+            ..TerrainParams::default()
+        });
+        landscape.generate(Terrain::Hills);
+        let textures = Textures::default();
+        let pixel = |landscape: &mut Landscape, x: usize, y: usize| -> [u8; 4] {
+            let index = (y * 100 + x) * 4;
+            let rgba = landscape.rgba_buffer(&textures);
+            [
+                rgba[index],
+                rgba[index + 1],
+                rgba[index + 2],
+                rgba[index + 3],
+            ]
+        };
+
+        // Column without sand on surface
+        let x = (0..100)
+            .find(|&x| landscape.buffer[150 * 100 + x] == Material::Dirt as u8)
+            .unwrap();
+        assert_eq!(pixel(&mut landscape, x, 149), [0, 0, 0, 0]);
+        let grass = pixel(&mut landscape, x, 150);
+        assert!(grass[1] > grass[0] && grass[1] > grass[2]);
+        // Ground becomes darker with depth
+        let shallow = pixel(&mut landscape, x, 175);
+        let deep = pixel(&mut landscape, x, 195);
+        assert!(deep.iter().zip(shallow.iter()).take(3).all(|(d, s)| d < s));
+
+        landscape.scorch(Point2::new(x as f32, 150.), 30.);
+        let scorched = pixel(&mut landscape, x, 175);
+        assert!(scorched
+            .iter()
+            .zip(shallow.iter())
+            .take(3)
+            .all(|(d, s)| d < s));
+        assert!(!landscape.changed());
+    }
+
+    #[test]
+    fn test_incremental_rgba_buffer() {
+        let mut landscape = Landscape::new(300, 400).unwrap();
+        landscape.set_seed(3);
+        landscape.generate(Terrain::Hills);
+        let textures = Textures::default();
+        landscape.rgba_buffer(&textures);
+        assert!(!landscape.changed());
+
+        landscape.scorch(Point2::new(50., 300.), 10.);
+        landscape.dig_tunnel(Point2::new(200., 300.), Vector2::new(1., 0.), 20., 3.);
+        assert_eq!(landscape.dirty_rects().len(), 2);
+        let rects = landscape.update_rgba_buffer(&textures);
+        assert!(rects.iter().all(|r| r.width() < 40 && r.height() <= 110));
+        assert_eq!(landscape.rgba_pixels(&rects[0]).len(), rects[0].area() * 4);
+
+        // Redraw changed regions on every step of subsidence
+        for y in 250..350 {
+            let pixels = landscape.get_pixels_line_mut((100, y), 100).unwrap();
+            pixels.iter_mut().for_each(|c| *c = 0);
+        }
+        landscape.set_rect_changed(100, 250, 100, 100);
+        let mut clock = Clock::new();
+        landscape.subsidence(clock.now());
+        loop {
+            let finished = landscape.update(clock.now());
+            assert!(landscape.dirty_rects().iter().all(|r| r.width() < 300));
+            landscape.update_rgba_buffer(&textures);
+            if finished {
+                break;
+            }
+            clock.tick();
+        }
+
+        let incremental = landscape.rgba_buffer.clone();
+        landscape.set_changed();
+        assert!(landscape.rgba_buffer(&textures) == &incremental[..]);
+    }
+
+    #[test]
+    fn test_dig_tunnel() {
+        let mut landscape = Landscape::new(200, 100).unwrap();
+        for y in 50..100 {
+            let pixels = landscape.get_pixels_line_mut((0, y), 200).unwrap();
+            pixels.iter_mut().for_each(|c| *c = 1);
+        }
+        landscape.dig_tunnel(Point2::new(20., 50.), Vector2::new(1., 0.), 100., 5.);
+        assert!(!landscape.is_not_empty(20, 55));
+        assert!(!landscape.is_not_empty(120, 50));
+        assert!(landscape.is_not_empty(20, 56));
+        assert!(landscape.is_not_empty(126, 50));
     }
 }
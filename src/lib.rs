@@ -1,16 +1,40 @@
-mod ballistics;
-mod explosion;
-mod geometry;
+pub mod ai;
+pub mod ballistics;
+pub mod clock;
+#[cfg(feature = "gui")]
+pub mod controls;
+pub mod debris;
+pub mod explosion;
+pub mod game_match;
+pub mod geometry;
+#[cfg(feature = "gui")]
 pub mod input;
-mod landscape;
-mod missile;
-mod player;
-mod round;
+pub mod item;
+pub mod landscape;
+pub mod map;
+pub mod missile;
+pub mod net;
+pub mod parse;
+pub mod player;
+pub mod profile;
+pub mod replay;
+pub mod round;
+pub mod save;
+#[cfg(feature = "gui")]
 pub mod scenes;
+pub mod settings;
+#[cfg(feature = "gui")]
 mod shaders;
-mod tank;
-mod types;
+pub mod shield;
+pub mod shop;
+pub mod tank;
+pub mod texture;
+pub mod types;
+#[cfg(feature = "gui")]
 mod utils;
+pub mod weapon;
+pub mod wind;
+#[cfg(feature = "gui")]
 pub mod world;
 
 pub const G: f32 = 9.80665;
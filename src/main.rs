@@ -1,33 +1,55 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::time::{Duration, Instant};
+
 use ggez::conf::{WindowMode, WindowSetup};
-use ggez::{event, graphics, timer};
+use ggez::{event, graphics, timer, GameError};
 
-use tank_war::{input, scenes, world};
+use tank_war::replay::Replay;
+use tank_war::settings::Settings;
+use tank_war::types::Point2;
+use tank_war::{clock, input, scenes, world};
 
 struct MainState {
     scenes: scenes::Stack,
-    input_binding: input::Binding,
+    last_frame: Instant,
+    // Events of held keys resolved when they were pressed, because
+    // binding of keys can change before they are released.
+    held_keys: HashMap<event::KeyCode, input::Event>,
 }
 
 impl MainState {
-    fn new(ctx: &mut ggez::Context) -> ggez::GameResult<MainState> {
+    fn new(ctx: &mut ggez::Context, replay: Option<Replay>) -> ggez::GameResult<MainState> {
         let world = world::World::new(ctx)?;
         let mut scene_stack = scenes::Stack::new(ctx, world);
         let main_menu_scene = scenes::MainMenuScene::new(ctx, &mut scene_stack.world);
         scene_stack.push(Box::new(main_menu_scene));
+        if let Some(replay) = replay {
+            let replay_scene =
+                scenes::GamePlayScene::from_replay(ctx, &mut scene_stack.world, replay)?;
+            scene_stack.push(Box::new(replay_scene));
+        }
 
         Ok(Self {
-            input_binding: input::create_input_binding(),
             scenes: scene_stack,
+            last_frame: Instant::now(),
+            held_keys: HashMap::new(),
         })
     }
 }
 
 impl event::EventHandler for MainState {
     fn update(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
-        const DESIRED_FPS: u32 = 60;
-        while timer::check_update_time(ctx, DESIRED_FPS) {
+        // Every update advances game clock by one tick.
+        while timer::check_update_time(ctx, clock::TICKS_PER_SECOND) {
+            self.scenes.world.active_player = None;
             self.scenes.update(ctx);
-            self.scenes.world.input.update(0.0);
+            let dt = 1.0 / clock::TICKS_PER_SECOND as f32;
+            self.scenes.world.input.update(dt);
+            self.scenes.world.text_input.clear();
+            self.scenes.world.last_key = None;
+            self.scenes.world.mouse.clear();
         }
 
         Ok(())
@@ -35,7 +57,19 @@ impl event::EventHandler for MainState {
 
     fn draw(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
         self.scenes.draw(ctx);
-        graphics::present(ctx)
+        graphics::present(ctx)?;
+
+        // Limit count of drawn frames per second
+        let fps = self.scenes.world.settings.fps;
+        if fps > 0 {
+            let frame_time = Duration::from_secs(1) / fps;
+            let elapsed = self.last_frame.elapsed();
+            if elapsed < frame_time {
+                std::thread::sleep(frame_time - elapsed);
+            }
+        }
+        self.last_frame = Instant::now();
+        Ok(())
     }
 
     fn key_down_event(
@@ -45,9 +79,24 @@ impl event::EventHandler for MainState {
         _key_mods: event::KeyMods,
         _repeat: bool,
     ) {
-        if let Some(ev) = self.input_binding.resolve(key_code) {
+        let world = &mut self.scenes.world;
+        world.last_key = Some(key_code);
+        let ev = match self.held_keys.get(&key_code) {
+            Some(&ev) => Some(ev),
+            None => world
+                .controls
+                .binding(world.active_player)
+                .resolve(key_code),
+        };
+        if let Some(ev) = ev {
+            self.held_keys.insert(key_code, ev);
             if let input::Event::Button(button) = ev {
-                self.scenes.world.input.update_button_down(button)
+                let input_state = &mut self.scenes.world.input;
+                input_state.update_button_down(button);
+                // Direction buttons also move axes while they are held
+                if let Some((axis, positive)) = input::button_axis(button) {
+                    input_state.update_axis_start(axis, positive);
+                }
             }
             self.scenes.input(ev, true);
         }
@@ -59,16 +108,99 @@ impl event::EventHandler for MainState {
         key_code: event::KeyCode,
         _key_mods: event::KeyMods,
     ) {
-        if let Some(ev) = self.input_binding.resolve(key_code) {
+        // Exactly the button pressed by the key is released
+        if let Some(ev) = self.held_keys.remove(&key_code) {
             if let input::Event::Button(button) = ev {
-                self.scenes.world.input.update_button_up(button)
+                let input_state = &mut self.scenes.world.input;
+                input_state.update_button_up(button);
+                if let Some((axis, positive)) = input::button_axis(button) {
+                    input_state.update_axis_stop(axis, positive);
+                }
             }
             self.scenes.input(ev, false);
         }
     }
+
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut ggez::Context,
+        button: event::MouseButton,
+        x: f32,
+        y: f32,
+    ) {
+        let mouse = &mut self.scenes.world.mouse;
+        mouse.position = Some(Point2::new(x, y));
+        mouse.clicked = Some(button);
+    }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut ggez::Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        let mouse = &mut self.scenes.world.mouse;
+        mouse.position = Some(Point2::new(x, y));
+        mouse.moved = true;
+    }
+
+    fn gamepad_axis_event(
+        &mut self,
+        _ctx: &mut ggez::Context,
+        axis: event::Axis,
+        value: f32,
+        _id: event::GamepadId,
+    ) {
+        let axis = match input::Stick::gamepad_axis(axis) {
+            Some(axis) => axis,
+            None => return,
+        };
+        let world = &mut self.scenes.world;
+        let old_direction = world.stick.direction(axis);
+        world.stick.set_value(axis, value);
+        let new_direction = world.stick.direction(axis);
+        if old_direction == new_direction {
+            return;
+        }
+        // Stick which leaves dead zone works like held direction key
+        if let Some(positive) = old_direction {
+            let button = input::axis_button(axis, positive);
+            self.scenes.world.input.update_button_up(button);
+            self.scenes.world.input.update_axis_stop(axis, positive);
+            self.scenes.input(input::Event::Button(button), false);
+        }
+        if let Some(positive) = new_direction {
+            let button = input::axis_button(axis, positive);
+            self.scenes.world.input.update_button_down(button);
+            self.scenes.world.input.update_axis_start(axis, positive);
+            self.scenes.input(input::Event::Button(button), true);
+        }
+    }
+
+    fn mouse_wheel_event(&mut self, _ctx: &mut ggez::Context, _x: f32, y: f32) {
+        self.scenes.world.mouse.wheel += y;
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut ggez::Context, character: char) {
+        self.scenes.world.text_input.push(character);
+    }
+}
+
+fn read_replay(path: &str) -> ggez::GameResult<Replay> {
+    let file = File::open(path)?;
+    Replay::read(BufReader::new(file)).map_err(GameError::ResourceLoadError)
 }
 
 pub fn main() -> ggez::GameResult {
+    let mut replay = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--replay" => {
+                let path = args.next().ok_or_else(|| {
+                    GameError::ConfigError("Path to replay file is required".into())
+                })?;
+                replay = Some(read_replay(&path)?);
+            }
+            _ => return Err(GameError::ConfigError(format!("Unknown argument: {}", arg))),
+        }
+    }
+
     let resource_dir = if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
         let mut path = std::path::PathBuf::from(manifest_dir);
         path.push("assets");
@@ -81,8 +213,13 @@ pub fn main() -> ggez::GameResult {
     win_setup = win_setup
         .title("Tank War - Rust edition")
         .icon("/sprites/app_icon.png");
+    // Saved settings are applied after creating of context
+    let settings = Settings::default();
     let mut win_mode: WindowMode = Default::default();
-    win_mode = win_mode.dimensions(1024., 768.);
+    win_mode = win_mode.dimensions(
+        f32::from(settings.window_width),
+        f32::from(settings.window_height),
+    );
 
     let cb = ggez::ContextBuilder::new("tank_war", "cykooz")
         .window_setup(win_setup)
@@ -90,6 +227,6 @@ pub fn main() -> ggez::GameResult {
         .add_resource_path(resource_dir);
     let (ctx, event_loop) = &mut cb.build()?;
 
-    let state = &mut MainState::new(ctx)?;
+    let state = &mut MainState::new(ctx, replay)?;
     event::run(ctx, event_loop, state)
 }
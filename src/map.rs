@@ -0,0 +1,230 @@
+//! Custom maps drawn by hand.
+//!
+//! Map is a PNG image from `maps` directory of assets. Every
+//! non-transparent pixel of image is a solid ground made of material
+//! which color is the closest one to color of pixel (see
+//! `Material::color`). Pure red pixels
+//! (`#ff0000`) mark spawn points of tanks and aren't solid, a spawn
+//! point is at top left pixel of every red mark. Image is scaled
+//! to size of landscape, so it is better to draw it with the same
+//! aspect ratio as window has.
+//!
+//! Tanks are placed at spawn points in random order if map has enough
+//! of them, otherwise they are placed as on generated landscape.
+use std::io::Read;
+
+use crate::landscape::Material;
+use crate::texture::decode_png;
+use crate::types::Point2;
+
+const SPAWN_POINT_COLOR: [u8; 4] = [255, 0, 0, 255];
+/// Pixels with less alpha are transparent.
+const MIN_SOLID_ALPHA: u8 = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Map {
+    name: String,
+    width: u16,
+    height: u16,
+    // Materials of pixels of map row by row.
+    pixels: Vec<u8>,
+    // Spawn points in order from left to right.
+    spawn_points: Vec<(u16, u16)>,
+}
+
+impl Map {
+    /// Creates map from RGBA pixels of image.
+    pub fn from_rgba(name: &str, width: u16, height: u16, rgba: &[u8]) -> Result<Map, String> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(format!("Invalid name of map: '{}'", name));
+        }
+        if width == 0 || height == 0 {
+            return Err(format!("Map '{}' is empty", name));
+        }
+        if rgba.len() != width as usize * height as usize * 4 {
+            return Err(format!("Size of pixels of map '{}' is invalid", name));
+        }
+
+        let is_spawn_mark = |x: i32, y: i32| {
+            if x < 0 || y < 0 {
+                return false;
+            }
+            let index = (y as usize * width as usize + x as usize) * 4;
+            rgba[index..index + 4] == SPAWN_POINT_COLOR
+        };
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        let mut spawn_points = Vec::new();
+        for y in 0..i32::from(height) {
+            for x in 0..i32::from(width) {
+                if is_spawn_mark(x, y) {
+                    pixels.push(0);
+                    if !is_spawn_mark(x - 1, y) && !is_spawn_mark(x, y - 1) {
+                        spawn_points.push((x as u16, y as u16));
+                    }
+                } else {
+                    let index = (y as usize * width as usize + x as usize) * 4;
+                    let material = if rgba[index + 3] >= MIN_SOLID_ALPHA {
+                        closest_material(&rgba[index..index + 3])
+                    } else {
+                        Material::Empty
+                    };
+                    pixels.push(material as u8);
+                }
+            }
+        }
+        spawn_points.sort_by_key(|&(x, y)| (x, y));
+
+        Ok(Map {
+            name: name.to_string(),
+            width,
+            height,
+            pixels,
+            spawn_points,
+        })
+    }
+
+    /// Reads map from PNG image.
+    pub fn read_png<R: Read>(name: &str, reader: R) -> Result<Map, String> {
+        let (width, height, rgba) = decode_png(reader)?;
+        Map::from_rgba(name, width, height, &rgba)
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Returns checksum of size, pixels and spawn points of map, so
+    /// maps with the same name can be compared over network.
+    pub fn checksum(&self) -> u32 {
+        let mut values = vec![self.width, self.height];
+        values.extend(self.spawn_points.iter().flat_map(|&(x, y)| vec![x, y]));
+        let bytes = values
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .chain(self.pixels.iter().cloned());
+        // FNV-1a hash
+        bytes.fold(0x811c_9dc5, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        })
+    }
+
+    /// Returns materials of pixels of map scaled to given size.
+    pub fn scaled_pixels(&self, width: u16, height: u16) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            let map_y = self.scale_y(y, height);
+            let row = &self.pixels[map_y * self.width as usize..][..self.width as usize];
+            pixels.extend((0..width).map(|x| row[self.scale_x(x, width)]));
+        }
+        pixels
+    }
+
+    /// Returns spawn points scaled to given size.
+    pub fn scaled_spawn_points(&self, width: u16, height: u16) -> Vec<Point2> {
+        let scale_x = f32::from(width) / f32::from(self.width);
+        let scale_y = f32::from(height) / f32::from(self.height);
+        self.spawn_points
+            .iter()
+            .map(|&(x, y)| Point2::new(f32::from(x) * scale_x, f32::from(y) * scale_y))
+            .collect()
+    }
+
+    // Returns column of map which corresponds to given column of
+    // scaled map.
+    fn scale_x(&self, x: u16, width: u16) -> usize {
+        x as usize * self.width as usize / width as usize
+    }
+
+    fn scale_y(&self, y: u16, height: u16) -> usize {
+        y as usize * self.height as usize / height as usize
+    }
+}
+
+/// Returns solid material which color is the closest one to given color.
+fn closest_material(rgb: &[u8]) -> Material {
+    let distance = |material: &&Material| -> i32 {
+        let color = material.color();
+        rgb.iter()
+            .zip(color.iter())
+            .map(|(&a, &b)| (i32::from(a) - i32::from(b)).pow(2))
+            .sum()
+    };
+    Material::ALL[1..]
+        .iter()
+        .min_by_key(distance)
+        .cloned()
+        .unwrap_or(Material::Dirt)
+}
+
+/// Returns map with given name if name isn't `None`.
+pub fn find<'a>(maps: &'a [Map], name: Option<&str>) -> Result<Option<&'a Map>, String> {
+    match name {
+        Some(name) => maps
+            .iter()
+            .find(|map| map.name == name)
+            .map(Some)
+            .ok_or_else(|| format!("Map '{}' is not found", name)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+    const GROUND: [u8; 4] = [120, 80, 40, 255];
+    const ROCK: [u8; 4] = [128, 128, 128, 255];
+    const SPAWN: [u8; 4] = SPAWN_POINT_COLOR;
+
+    #[test]
+    fn test_from_rgba() {
+        let rows = [
+            [CLEAR, SPAWN, SPAWN, CLEAR],
+            [CLEAR, SPAWN, SPAWN, SPAWN],
+            [GROUND, ROCK, CLEAR, GROUND],
+        ];
+        let rgba: Vec<u8> = rows.iter().flatten().flatten().cloned().collect();
+        let map = Map::from_rgba("test", 4, 3, &rgba).unwrap();
+        assert_eq!(map.size(), (4, 3));
+        assert_eq!(map.spawn_points, vec![(1, 0)]);
+        assert_eq!(map.pixels, vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 0, 1]);
+
+        assert_eq!(
+            map.scaled_pixels(8, 6)[8 * 4..],
+            [1, 1, 2, 2, 0, 0, 1, 1, 1, 1, 2, 2, 0, 0, 1, 1]
+        );
+        assert_eq!(map.scaled_spawn_points(8, 6), vec![Point2::new(2., 0.)]);
+        assert_eq!(map.scaled_pixels(2, 1), vec![0, 0]);
+
+        assert!(Map::from_rgba("bad name", 4, 3, &rgba).is_err());
+        assert!(Map::from_rgba("test", 4, 2, &rgba).is_err());
+    }
+
+    #[test]
+    fn test_find() {
+        let map = Map::from_rgba("hill", 1, 1, &GROUND).unwrap();
+        let maps = vec![map.clone()];
+        assert_eq!(find(&maps, None), Ok(None));
+        assert_eq!(find(&maps, Some("hill")), Ok(Some(&map)));
+        assert!(find(&maps, Some("valley")).is_err());
+    }
+
+    #[test]
+    fn test_checksum() {
+        let map = Map::from_rgba("hill", 2, 1, &[GROUND, CLEAR].concat()).unwrap();
+        let same_map = Map::from_rgba("valley", 2, 1, &[GROUND, CLEAR].concat()).unwrap();
+        assert_eq!(map.checksum(), same_map.checksum());
+        let other_map = Map::from_rgba("hill", 2, 1, &[CLEAR, GROUND].concat()).unwrap();
+        assert_ne!(map.checksum(), other_map.checksum());
+        let other_map = Map::from_rgba("hill", 1, 2, &[GROUND, CLEAR].concat()).unwrap();
+        assert_ne!(map.checksum(), other_map.checksum());
+    }
+}
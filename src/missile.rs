@@ -1,41 +1,335 @@
 use std::f32::consts::PI;
 
+use cgmath::InnerSpace;
+
 use crate::ballistics::Ballistics;
 use crate::landscape::Landscape;
+use crate::tank::Tank;
 use crate::types::{Point2, Vector2};
+use crate::weapon::{Impact, Weapon};
 
 const TIME_SCALE: f32 = 3.0;
+/// Speed of rolling missile in pixels per second of game time.
+const ROLLING_SPEED: f32 = 80.0;
+/// Max height of step which rolling missile can climb.
+const MAX_CLIMB: i32 = 1;
+/// Difference of horizontal velocities of neighbour warheads of MIRV.
+const WARHEADS_SPREAD: f32 = 15.0;
 
 #[derive(Debug, Clone, Copy)]
+enum Motion {
+    Flying,
+    Rolling {
+        started: f32,
+        start_x: i32,
+        direction: i32,
+        pos: (i32, i32),
+    },
+    Finished,
+}
+
+/// Things happened with missile during update.
+#[derive(Debug, Clone)]
+pub enum MissileEvent {
+    /// Missile has split into warheads.
+    Split(Vec<Missile>),
+    /// Missile has hit something in given point and finished.
+    Hit(Point2),
+}
+
+#[derive(Debug, Clone)]
 pub struct Missile {
+    pub weapon: Weapon,
     ballistics: Ballistics,
+    motion: Motion,
+    // Warheads of MIRV are not split again.
+    is_warhead: bool,
+}
+
+/// Returns `true` if given point is occupied by landscape or some live tank.
+fn has_collision(landscape: &Landscape, tanks: &[Tank], x: i32, y: i32) -> bool {
+    landscape.is_not_empty(x, y) || has_tank_collision(tanks, x, y)
+}
+
+fn has_tank_collision(tanks: &[Tank], x: i32, y: i32) -> bool {
+    tanks
+        .iter()
+        .filter(|t| !t.dead)
+        .any(|t| t.has_collision((x as f32, y as f32)))
 }
 
 impl Missile {
-    pub fn new(pos: Point2, angle: f32, power: f32, acceleration: Vector2) -> Missile {
+    pub fn new(
+        weapon: Weapon,
+        now: f32,
+        pos: Point2,
+        angle: f32,
+        power: f32,
+        acceleration: Vector2,
+    ) -> Missile {
         let rad = angle * PI / 180.;
         let velocity: Vector2 = Vector2::new(rad.sin(), -rad.cos()) * power;
 
         Missile {
-            ballistics: Ballistics::new(pos, velocity, acceleration).time_scale(TIME_SCALE),
+            weapon,
+            ballistics: Ballistics::new(now, pos, velocity, acceleration).time_scale(TIME_SCALE),
+            motion: Motion::Flying,
+            is_warhead: false,
         }
     }
 
     #[inline]
     pub fn cur_pos(&self) -> Point2 {
-        self.ballistics.cur_pos()
+        match self.motion {
+            Motion::Rolling { pos, .. } => Point2::new(pos.0 as f32, pos.1 as f32),
+            _ => self.ballistics.cur_pos(),
+        }
+    }
+
+    /// Returns unit vector of direction of flight.
+    pub fn direction(&self) -> Vector2 {
+        let (_, velocity) = self.ballistics.pos_and_velocity();
+        if velocity.magnitude2() > 0. {
+            velocity.normalize()
+        } else {
+            Vector2::new(0., 1.)
+        }
+    }
+
+    /// Changes acceleration of flying missile, e.g. by gust of wind.
+    pub fn set_acceleration(&mut self, acceleration: Vector2) {
+        if let Motion::Flying = self.motion {
+            self.ballistics.set_acceleration(acceleration);
+        }
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        if let Motion::Finished = self.motion {
+            return true;
+        }
+        false
+    }
+
+    /// Moves missile by ballistic trajectory up to given game time and
+    /// returns a point where it hit landscape, some live tank or bottom border.
+    /// Missile bounces off deflectors of tanks.
+    pub fn fly(&mut self, now: f32, landscape: &Landscape, tanks: &[Tank]) -> Option<Point2> {
+        let size = landscape.size();
+        let borders = (size.0 as i32, size.1 as i32);
+        loop {
+            let mut prev_pos = self.ballistics.cur_pos();
+            let mut deflector_normal: Option<Vector2> = None;
+            for (x, y) in self.ballistics.positions_iter(now, Some(borders)) {
+                let pos = Point2::new(x as f32, y as f32);
+                let deflector = tanks.iter().find(|t| {
+                    !t.dead && t.deflector_contains(pos) && !t.deflector_contains(prev_pos)
+                });
+                if let Some(tank) = deflector {
+                    deflector_normal = Some(pos - tank.center());
+                    break;
+                }
+                if has_collision(landscape, tanks, x, y) || y >= borders.1 {
+                    return Some(pos);
+                }
+                prev_pos = pos;
+            }
+
+            match deflector_normal {
+                Some(normal) => {
+                    let horizontal = normal.x.abs() * 2. >= normal.y.abs();
+                    let vertical = normal.y.abs() * 2. >= normal.x.abs();
+                    self.ballistics.apply_rebound(horizontal, vertical);
+                }
+                None => return None,
+            }
+        }
     }
 
-    pub fn update<F>(&mut self, borders: (i32, i32), has_collision: F) -> Option<Point2>
-    where
-        F: Fn(i32, i32) -> bool,
-    {
-        for (x, y) in self.ballistics.positions_iter(None, Some(borders)) {
-            if has_collision(x, y) || y >= borders.1 {
-                return Some(Point2::new(x as f32, y as f32));
+    pub fn update(
+        &mut self,
+        now: f32,
+        landscape: &Landscape,
+        tanks: &[Tank],
+    ) -> Option<MissileEvent> {
+        match self.motion {
+            Motion::Flying => {
+                if let Some(pos) = self.fly(now, landscape, tanks) {
+                    let (x, y) = (pos.x as i32, pos.y as i32);
+                    let on_ground =
+                        y < landscape.size().1 as i32 && !has_tank_collision(tanks, x, y);
+                    if self.weapon.impact() == Impact::Rolling && on_ground {
+                        let direction = if self.direction().x < 0. { -1 } else { 1 };
+                        self.motion = Motion::Rolling {
+                            started: now,
+                            start_x: x,
+                            direction,
+                            pos: (x, y - 1),
+                        };
+                        return None;
+                    }
+                    self.motion = Motion::Finished;
+                    return Some(MissileEvent::Hit(pos));
+                }
+
+                let count = self.weapon.warheads_count();
+                if count > 1 && !self.is_warhead && self.direction().y >= 0. {
+                    self.motion = Motion::Finished;
+                    return Some(MissileEvent::Split(self.split(now, count)));
+                }
+                None
             }
+            Motion::Rolling { .. } => self.roll(now, landscape, tanks),
+            Motion::Finished => None,
         }
+    }
 
+    /// Moves rolling missile along surface of landscape.
+    fn roll(&mut self, now: f32, landscape: &Landscape, tanks: &[Tank]) -> Option<MissileEvent> {
+        if let Motion::Rolling {
+            started,
+            start_x,
+            direction,
+            ref mut pos,
+        } = self.motion
+        {
+            let height = landscape.size().1 as i32;
+            let distance = ((now - started) * ROLLING_SPEED) as i32;
+            let target_x = start_x + direction * distance;
+            let mut stopped = false;
+            while pos.0 != target_x {
+                let (x, y) = (pos.0 + direction, pos.1);
+                if x < 0 || x >= landscape.size().0 as i32 || has_tank_collision(tanks, x, y) {
+                    stopped = true;
+                    break;
+                }
+                // Climb up a small step or stop before a big one.
+                let mut new_y = y;
+                while landscape.is_not_empty(x, new_y) && y - new_y <= MAX_CLIMB {
+                    new_y -= 1;
+                }
+                if y - new_y > MAX_CLIMB {
+                    stopped = true;
+                    break;
+                }
+                // Fall down to the surface.
+                while new_y + 1 < height && !landscape.is_not_empty(x, new_y + 1) {
+                    new_y += 1;
+                }
+                *pos = (x, new_y);
+                if new_y + 1 >= height {
+                    stopped = true;
+                    break;
+                }
+            }
+            if stopped {
+                let pos = Point2::new(pos.0 as f32, pos.1 as f32);
+                self.motion = Motion::Finished;
+                return Some(MissileEvent::Hit(pos));
+            }
+        }
         None
     }
+
+    fn split(&self, now: f32, count: usize) -> Vec<Missile> {
+        let middle = (count - 1) as f32 / 2.;
+        (0..count)
+            .map(|i| {
+                let delta = Vector2::new((i as f32 - middle) * WARHEADS_SPREAD, 0.);
+                Missile {
+                    weapon: self.weapon,
+                    ballistics: self.ballistics.fork(now, delta),
+                    motion: Motion::Flying,
+                    is_warhead: true,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::shield::{Shield, ShieldKind};
+
+    /// Flat landscape which is filled from given height to the bottom.
+    fn flat_landscape(surface: i32) -> Landscape {
+        let mut landscape = Landscape::new(400, 300).unwrap();
+        for y in surface..300 {
+            let pixels = landscape.get_pixels_line_mut((0, y), 400).unwrap();
+            pixels.iter_mut().for_each(|c| *c = 1);
+        }
+        landscape
+    }
+
+    fn update_until_event(missile: &mut Missile, landscape: &Landscape) -> MissileEvent {
+        let mut clock = Clock::new();
+        for _ in 0..10_000 {
+            clock.tick();
+            if let Some(event) = missile.update(clock.now(), landscape, &[]) {
+                return event;
+            }
+        }
+        panic!("Missile has not hit anything");
+    }
+
+    #[test]
+    fn test_mirv_splits() {
+        let landscape = flat_landscape(250);
+        let acceleration = Vector2::new(0., 10.);
+        let pos = Point2::new(100., 240.);
+        let mut missile = Missile::new(Weapon::Mirv, 0., pos, 30., 100., acceleration);
+        match update_until_event(&mut missile, &landscape) {
+            MissileEvent::Split(warheads) => {
+                assert_eq!(warheads.len(), Weapon::Mirv.warheads_count());
+                assert!(warheads.iter().all(|w| w.is_warhead));
+            }
+            event => panic!("Unexpected event: {:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_roller_rolls_to_the_border() {
+        let landscape = flat_landscape(250);
+        let acceleration = Vector2::new(0., 10.);
+        let pos = Point2::new(100., 240.);
+        let mut missile = Missile::new(Weapon::Roller, 0., pos, 30., 10., acceleration);
+        match update_until_event(&mut missile, &landscape) {
+            MissileEvent::Hit(pos) => {
+                assert_eq!(pos, Point2::new(399., 249.));
+            }
+            event => panic!("Unexpected event: {:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_deflector_bounces_missile_back() {
+        let landscape = flat_landscape(290);
+        let mut tank = Tank::new(1, [300., 249.], 0.);
+        tank.shield = Some(Shield::new(ShieldKind::Deflector));
+        let tanks = vec![tank];
+        // Missile flies horizontally to the center of tank
+        let pos = Point2::new(100., tanks[0].center().y);
+        let mut missile = Missile::new(
+            Weapon::BabyMissile,
+            0.,
+            pos,
+            90.,
+            100.,
+            Vector2::new(0., 0.),
+        );
+
+        let mut clock = Clock::new();
+        loop {
+            clock.tick();
+            if missile.fly(clock.now(), &landscape, &tanks).is_some() {
+                panic!("Missile has hit something");
+            }
+            if missile.cur_pos().x < 90. {
+                break;
+            }
+        }
+        assert!(missile.direction().x < 0.);
+    }
 }
@@ -0,0 +1,908 @@
+//! Networked multiplayer over TCP.
+//!
+//! One instance of game hosts a round and other instances join it.
+//! Every instance controls tank of one player. Host is authoritative:
+//! it applies commands of players to its round only during their turns
+//! and sends applied commands to all joined instances together with
+//! every update of round. Round is deterministic, so joined instances
+//! replay these commands at the same ticks and stay in sync with host.
+//!
+//! Instances exchange text messages, one message per line:
+//!
+//! ```text
+//! hello 1                   -> joined player greets host with version of protocol
+//! welcome 2 897235 1022 766 hills 4 2 2 0.5 0.5 0.5 gusty 10 canyon 3141592653
+//!                           <- number of joined player, seed, width, height,
+//!                              style of landscape, parameters of terrain,
+//!                              parameters of wind, name and checksum of
+//!                              custom map (if it is used)
+//! player 500 human 0        <- players in order of their numbers (as in saved match)
+//! start                     <- round is started
+//! request angle -1          -> command of joined player
+//! command 1520 2 angle -1   <- command which host has applied before given tick
+//! step                      <- host has updated round by one tick
+//! ```
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::item::Item;
+use crate::landscape::{Terrain, TerrainParams};
+use crate::map::{self, Map};
+use crate::parse::parse_value;
+use crate::player::Player;
+use crate::round::{GameState, Round};
+use crate::save;
+use crate::weapon::Weapon;
+use crate::wind::WindParams;
+use crate::MAX_PLAYERS_COUNT;
+
+pub const DEFAULT_PORT: u16 = 7777;
+const PROTOCOL_VERSION: u32 = 7;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Number of player of hosting instance.
+const HOST_PLAYER_NUMBER: u8 = 1;
+/// Max length of received line which has no end yet.
+const MAX_LINE_LEN: usize = 64 * 1024;
+/// Max size of sent messages which other side has not read yet.
+const MAX_OUTPUT_LEN: usize = 1024 * 1024;
+
+/// Action of player with their tank.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Angle(f32),
+    Power(f32),
+    /// Sets angle of gun to given value instead of changing it.
+    SetAngle(f32),
+    /// Sets power of gun to given value instead of changing it.
+    SetPower(f32),
+    SelectWeapon(Weapon),
+    NextWeapon,
+    UseItem(Item),
+    Shoot,
+}
+
+impl Command {
+    /// Applies command to current tank of round.
+    pub fn apply(self, round: &mut Round, players: &mut [Player]) {
+        match self {
+            Command::Angle(delta) => round.inc_gun_angle(delta),
+            Command::Power(delta) => round.inc_gun_power(delta),
+            Command::SetAngle(angle) => round.inc_gun_angle(angle - round.gun_angle()),
+            Command::SetPower(power) => round.inc_gun_power(power - round.gun_power()),
+            Command::SelectWeapon(weapon) => {
+                round.select_weapon(weapon, players);
+            }
+            Command::NextWeapon => round.select_next_weapon(players),
+            Command::UseItem(item) => {
+                round.use_item(item, players);
+            }
+            Command::Shoot => round.shoot(players),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Angle(delta) => write!(f, "angle {}", delta),
+            Command::Power(delta) => write!(f, "power {}", delta),
+            Command::SetAngle(angle) => write!(f, "set_angle {}", angle),
+            Command::SetPower(power) => write!(f, "set_power {}", power),
+            Command::SelectWeapon(weapon) => write!(f, "weapon {}", weapon),
+            Command::NextWeapon => write!(f, "next_weapon"),
+            Command::UseItem(item) => write!(f, "item {}", item),
+            Command::Shoot => write!(f, "shoot"),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut values = s.split_whitespace();
+        let command = match values.next() {
+            Some("angle") => Command::Angle(parse_value(&mut values, s)?),
+            Some("power") => Command::Power(parse_value(&mut values, s)?),
+            Some("set_angle") => Command::SetAngle(parse_value(&mut values, s)?),
+            Some("set_power") => Command::SetPower(parse_value(&mut values, s)?),
+            Some("weapon") => Command::SelectWeapon(parse_value(&mut values, s)?),
+            Some("next_weapon") => Command::NextWeapon,
+            Some("item") => Command::UseItem(parse_value(&mut values, s)?),
+            Some("shoot") => Command::Shoot,
+            _ => return Err(format!("Unknown command: {}", s)),
+        };
+        Ok(command)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Hello(u32),
+    Welcome {
+        player_number: u8,
+        seed: u64,
+        width: u16,
+        height: u16,
+        terrain: Terrain,
+        terrain_params: TerrainParams,
+        wind_params: WindParams,
+        map: Option<(String, u32)>,
+    },
+    Player(Player),
+    Start,
+    Request(Command),
+    Command {
+        tick: u64,
+        player_number: u8,
+        command: Command,
+    },
+    Step,
+}
+
+impl Message {
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Message::Hello(version) => writeln!(writer, "hello {}", version),
+            Message::Welcome {
+                player_number,
+                seed,
+                width,
+                height,
+                terrain,
+                terrain_params,
+                wind_params,
+                map,
+            } => {
+                write!(
+                    writer,
+                    "welcome {} {} {} {} {} {} {}",
+                    player_number, seed, width, height, terrain, terrain_params, wind_params
+                )?;
+                match map {
+                    Some((name, checksum)) => writeln!(writer, " {} {}", name, checksum),
+                    None => writeln!(writer),
+                }
+            }
+            Message::Player(player) => save::write_player(writer, player),
+            Message::Start => writeln!(writer, "start"),
+            Message::Request(command) => writeln!(writer, "request {}", command),
+            Message::Command {
+                tick,
+                player_number,
+                command,
+            } => writeln!(writer, "command {} {} {}", tick, player_number, command),
+            Message::Step => writeln!(writer, "step"),
+        }
+    }
+
+    fn parse(line: &str) -> Result<Message, String> {
+        let mut values = line.split_whitespace();
+        let message = match values.next() {
+            Some("hello") => Message::Hello(parse_value(&mut values, line)?),
+            Some("welcome") => Message::Welcome {
+                player_number: parse_value(&mut values, line)?,
+                seed: parse_value(&mut values, line)?,
+                width: parse_value(&mut values, line)?,
+                height: parse_value(&mut values, line)?,
+                terrain: parse_value(&mut values, line)?,
+                terrain_params: TerrainParams::parse(&mut values)?,
+                wind_params: WindParams::parse(&mut values)?,
+                map: match values.next() {
+                    Some(name) => Some((name.to_string(), parse_value(&mut values, line)?)),
+                    None => None,
+                },
+            },
+            Some("player") => Message::Player(save::parse_player(values)?),
+            Some("start") => Message::Start,
+            Some("request") => {
+                let command: Vec<&str> = values.collect();
+                Message::Request(command.join(" ").parse()?)
+            }
+            Some("command") => {
+                let tick = parse_value(&mut values, line)?;
+                let player_number = parse_value(&mut values, line)?;
+                let command: Vec<&str> = values.collect();
+                Message::Command {
+                    tick,
+                    player_number,
+                    command: command.join(" ").parse()?,
+                }
+            }
+            Some("step") => Message::Step,
+            _ => return Err(format!("Unknown message: {}", line)),
+        };
+        Ok(message)
+    }
+}
+
+/// Non-blocking connection which sends and receives messages.
+struct Connection {
+    stream: TcpStream,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    closed: bool,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Connection> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Connection {
+            stream,
+            input: Vec::new(),
+            output: Vec::new(),
+            closed: false,
+        })
+    }
+
+    fn send(&mut self, message: &Message) -> io::Result<()> {
+        message.write(&mut self.output)?;
+        self.flush()?;
+        if self.output.len() > MAX_OUTPUT_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Other side doesn't read sent messages",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Writes as much of buffered output as socket accepts without blocking.
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.output.is_empty() {
+            match self.stream.write(&self.output) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(count) => {
+                    self.output.drain(..count);
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns next received message or `None` if it has not been received yet.
+    fn receive(&mut self) -> io::Result<Option<Message>> {
+        self.flush()?;
+        if !self.input.contains(&b'\n') {
+            self.read_available()?;
+        }
+        match self.input.iter().position(|&b| b == b'\n') {
+            Some(end) => {
+                let line: Vec<u8> = self.input.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let message = Message::parse(line.trim())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                Ok(Some(message))
+            }
+            None if self.closed => Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "Connection closed by other side",
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn read_available(&mut self) -> io::Result<()> {
+        let mut buf = [0u8; 4096];
+        while !self.closed {
+            match self.stream.read(&mut buf) {
+                Ok(0) => self.closed = true,
+                Ok(count) => {
+                    self.input.extend_from_slice(&buf[..count]);
+                    let line_start = self
+                        .input
+                        .iter()
+                        .rposition(|&b| b == b'\n')
+                        .map_or(0, |end| end + 1);
+                    if self.input.len() - line_start > MAX_LINE_LEN {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Received line is too long",
+                        ));
+                    }
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn broadcast(connections: &mut [Connection], message: &Message) -> io::Result<()> {
+    for connection in connections.iter_mut() {
+        connection.send(message)?;
+    }
+    Ok(())
+}
+
+/// Parameters of networked round which host sends to joined players.
+#[derive(Debug, Clone)]
+pub struct Setup {
+    pub seed: u64,
+    pub width: u16,
+    pub height: u16,
+    pub terrain: Terrain,
+    pub terrain_params: TerrainParams,
+    pub wind_params: WindParams,
+    /// Name of custom map used instead of generated landscape.
+    pub map: Option<String>,
+    /// Checksum of custom map, joined players refuse to play on
+    /// another map with the same name.
+    pub map_checksum: u32,
+    pub players: Vec<Player>,
+}
+
+impl Setup {
+    /// Creates round with given setup. Map of round has to be among
+    /// given maps.
+    pub fn create_round(&self, maps: &[Map]) -> Result<Round, String> {
+        let mut round = Round::new(
+            self.width,
+            self.height,
+            self.players.len() as u8,
+            self.seed,
+            self.terrain,
+            self.terrain_params,
+        )?;
+        round.set_wind_params(self.wind_params);
+        if let Some(map) = map::find(maps, self.map.as_deref())? {
+            if map.checksum() != self.map_checksum {
+                return Err(format!("Map '{}' differs from map of host", map.name()));
+            }
+            round.load_map(map);
+        }
+        Ok(round)
+    }
+}
+
+/// Hosted game which waits for other players.
+pub struct Lobby {
+    listener: TcpListener,
+    clients: Vec<Connection>,
+    players_count: u8,
+}
+
+impl Lobby {
+    pub fn bind<A: ToSocketAddrs>(addr: A, players_count: u8) -> io::Result<Lobby> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Lobby {
+            listener,
+            clients: Vec::new(),
+            players_count,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    #[inline]
+    pub fn players_count(&self) -> u8 {
+        self.players_count
+    }
+
+    /// Changes count of players but not less than count of connected ones.
+    pub fn set_players_count(&mut self, count: u8) {
+        self.players_count = count.max(self.connected_count());
+    }
+
+    /// Returns count of connected players including host.
+    #[inline]
+    pub fn connected_count(&self) -> u8 {
+        self.clients.len() as u8 + 1
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.connected_count() >= self.players_count
+    }
+
+    /// Accepts new players and forgets disconnected ones.
+    pub fn update(&mut self) -> io::Result<()> {
+        loop {
+            match self.listener.accept() {
+                // Connections over count of players are closed at once.
+                Ok((stream, _)) if !self.is_full() => self.clients.push(Connection::new(stream)?),
+                Ok(_) => (),
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        for client in self.clients.iter_mut() {
+            loop {
+                match client.receive() {
+                    Ok(Some(Message::Hello(version))) if version == PROTOCOL_VERSION => (),
+                    Ok(Some(_)) | Err(_) => {
+                        client.closed = true;
+                        break;
+                    }
+                    Ok(None) => break,
+                }
+            }
+        }
+        self.clients.retain(|client| !client.closed);
+        Ok(())
+    }
+
+    /// Sends setup of round to connected players and starts a session.
+    pub fn start(mut self, setup: &Setup) -> io::Result<Session> {
+        for (index, client) in self.clients.iter_mut().enumerate() {
+            client.send(&Message::Welcome {
+                player_number: index as u8 + HOST_PLAYER_NUMBER + 1,
+                seed: setup.seed,
+                width: setup.width,
+                height: setup.height,
+                terrain: setup.terrain,
+                terrain_params: setup.terrain_params,
+                wind_params: setup.wind_params,
+                map: setup.map.clone().map(|name| (name, setup.map_checksum)),
+            })?;
+            for player in setup.players.iter() {
+                client.send(&Message::Player(player.clone()))?;
+            }
+            client.send(&Message::Start)?;
+        }
+        Ok(Session::new(HOST_PLAYER_NUMBER, true, self.clients))
+    }
+}
+
+/// Connection to host which waits for start of round.
+pub struct Joining {
+    // Connection is moved into session when round is started.
+    connection: Option<Connection>,
+    welcome: Option<(u8, Setup)>,
+}
+
+impl Joining {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Joining> {
+        let mut last_error = io::Error::new(io::ErrorKind::InvalidInput, "Invalid address");
+        for addr in addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    let mut connection = Connection::new(stream)?;
+                    connection.send(&Message::Hello(PROTOCOL_VERSION))?;
+                    return Ok(Joining {
+                        connection: Some(connection),
+                        welcome: None,
+                    });
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Returns session and setup of round when host starts it.
+    pub fn update(&mut self) -> io::Result<Option<(Session, Setup)>> {
+        let connection = match self.connection {
+            Some(ref mut connection) => connection,
+            None => return Ok(None),
+        };
+        while let Some(message) = connection.receive()? {
+            match message {
+                Message::Welcome {
+                    player_number,
+                    seed,
+                    width,
+                    height,
+                    terrain,
+                    terrain_params,
+                    wind_params,
+                    map,
+                } => {
+                    let (map, map_checksum) = match map {
+                        Some((name, checksum)) => (Some(name), checksum),
+                        None => (None, 0),
+                    };
+                    let setup = Setup {
+                        seed,
+                        width,
+                        height,
+                        terrain,
+                        terrain_params,
+                        wind_params,
+                        map,
+                        map_checksum,
+                        players: Vec::new(),
+                    };
+                    self.welcome = Some((player_number, setup));
+                }
+                Message::Player(player) => {
+                    if let Some((_, ref mut setup)) = self.welcome {
+                        if setup.players.len() >= MAX_PLAYERS_COUNT as usize {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "Too many players in setup",
+                            ));
+                        }
+                        setup.players.push(player);
+                    }
+                }
+                Message::Start => {
+                    let (player_number, setup) = match self.welcome.take() {
+                        Some(welcome) => welcome,
+                        None => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "Round is started without setup",
+                            ))
+                        }
+                    };
+                    let count = setup.players.len();
+                    if !(2..=MAX_PLAYERS_COUNT as usize).contains(&count)
+                        || !(1..=count).contains(&usize::from(player_number))
+                    {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Invalid players in setup",
+                        ));
+                    }
+                    let connection = self.connection.take().into_iter().collect();
+                    let session = Session::new(player_number, false, connection);
+                    return Ok(Some((session, setup)));
+                }
+                _ => (),
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Networked round which is played by several instances of game.
+pub struct Session {
+    player_number: u8,
+    is_host: bool,
+    // Host has connections to all joined players, others only to host.
+    connections: Vec<Connection>,
+    // Commands of players which host has to apply during next update.
+    requests: Vec<(u8, Command)>,
+    // Commands applied by host which are not applied to local round yet.
+    commands: VecDeque<(u64, Command)>,
+    // Count of updates made by host but not made with local round yet.
+    steps: usize,
+}
+
+impl Session {
+    fn new(player_number: u8, is_host: bool, connections: Vec<Connection>) -> Session {
+        Session {
+            player_number,
+            is_host,
+            connections,
+            requests: Vec::new(),
+            commands: VecDeque::new(),
+            steps: 0,
+        }
+    }
+
+    /// Number of player who plays on this instance.
+    #[inline]
+    pub fn player_number(&self) -> u8 {
+        self.player_number
+    }
+
+    #[inline]
+    pub fn is_host(&self) -> bool {
+        self.is_host
+    }
+
+    /// Returns `true` if current tank of round belongs to local player.
+    #[inline]
+    pub fn is_local_turn(&self, round: &Round) -> bool {
+        round.player_number() == self.player_number
+    }
+
+    /// Sends command of local player to host. It will be applied to round
+    /// during one of next updates if it is turn of local player.
+    pub fn send_command(&mut self, command: Command) -> io::Result<()> {
+        if self.is_host {
+            self.requests.push((self.player_number, command));
+            Ok(())
+        } else {
+            self.connections[0].send(&Message::Request(command))
+        }
+    }
+
+    /// Exchanges messages with other instances and updates round
+    /// as many times as host has done it.
+    pub fn update(&mut self, round: &mut Round, players: &mut [Player]) -> io::Result<()> {
+        if self.is_host {
+            self.update_host(round, players)
+        } else {
+            self.update_client(round, players)
+        }
+    }
+
+    fn update_host(&mut self, round: &mut Round, players: &mut [Player]) -> io::Result<()> {
+        for (index, connection) in self.connections.iter_mut().enumerate() {
+            let player_number = index as u8 + HOST_PLAYER_NUMBER + 1;
+            while let Some(message) = connection.receive()? {
+                if let Message::Request(command) = message {
+                    self.requests.push((player_number, command));
+                }
+            }
+        }
+
+        let tick = round.clock.ticks();
+        for (player_number, command) in self.requests.drain(..) {
+            // Players can control only their own tanks while aiming.
+            match round.state {
+                GameState::Aiming if round.player_number() == player_number => (),
+                _ => continue,
+            }
+            command.apply(round, players);
+            let message = Message::Command {
+                tick,
+                player_number,
+                command,
+            };
+            broadcast(&mut self.connections, &message)?;
+        }
+        round.update(players);
+        broadcast(&mut self.connections, &Message::Step)
+    }
+
+    fn update_client(&mut self, round: &mut Round, players: &mut [Player]) -> io::Result<()> {
+        while let Some(message) = self.connections[0].receive()? {
+            match message {
+                Message::Command { tick, command, .. } => self.commands.push_back((tick, command)),
+                Message::Step => self.steps += 1,
+                _ => (),
+            }
+        }
+
+        while self.steps > 0 {
+            let tick = round.clock.ticks();
+            while let Some(&(command_tick, command)) = self.commands.front() {
+                if command_tick > tick {
+                    break;
+                }
+                command.apply(round, players);
+                self.commands.pop_front();
+            }
+            round.update(players);
+            self.steps -= 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shop::Goods;
+    use crate::wind::WindMode;
+
+    #[test]
+    fn test_parse_messages() {
+        let mut player = Player::default();
+        player.inventory.add(Goods::Weapon(Weapon::Nuke), 1);
+        let messages = [
+            Message::Hello(PROTOCOL_VERSION),
+            Message::Welcome {
+                player_number: 2,
+                seed: 42,
+                width: 800,
+                height: 600,
+                terrain: Terrain::Valleys,
+                terrain_params: TerrainParams::default(),
+                wind_params: WindParams::default(),
+                map: None,
+            },
+            Message::Welcome {
+                player_number: 3,
+                seed: 42,
+                width: 800,
+                height: 600,
+                terrain: Terrain::Hills,
+                terrain_params: TerrainParams::default(),
+                wind_params: WindParams {
+                    mode: WindMode::Calm,
+                    max_power: 0.,
+                },
+                map: Some(("canyon".into(), 3_141_592_653)),
+            },
+            Message::Player(player),
+            Message::Request(Command::Angle(-0.5)),
+            Message::Request(Command::SetPower(87.)),
+            Message::Command {
+                tick: 120,
+                player_number: 2,
+                command: Command::UseItem(Item::RepairKit),
+            },
+        ];
+        for message in messages.iter() {
+            let mut line = Vec::new();
+            message.write(&mut line).unwrap();
+            let line = String::from_utf8(line).unwrap();
+            let parsed = Message::parse(line.trim()).unwrap();
+            assert_eq!(format!("{:?}", parsed), format!("{:?}", message));
+        }
+        assert!(Message::parse("request fly").is_err());
+    }
+
+    #[test]
+    fn test_setup_checks_map() {
+        let map = Map::from_rgba("canyon", 1, 1, &[0, 0, 0, 255]).unwrap();
+        let mut setup = Setup {
+            seed: 5,
+            width: 80,
+            height: 60,
+            terrain: Terrain::Hills,
+            terrain_params: TerrainParams::default(),
+            wind_params: WindParams::default(),
+            map: Some("canyon".into()),
+            map_checksum: map.checksum(),
+            players: vec![Player::default(), Player::default()],
+        };
+        assert!(setup.create_round(&[map.clone()]).is_ok());
+        setup.map_checksum ^= 1;
+        assert!(setup.create_round(&[map]).is_err());
+    }
+
+    #[test]
+    fn test_connection_limits_pending_data() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut connection = Connection::new(listener.accept().unwrap().0).unwrap();
+
+        // Line without end
+        let writer = std::thread::spawn(move || {
+            let _ = stream.write_all(&vec![b'a'; MAX_LINE_LEN + 4096]);
+            stream
+        });
+        let error = loop {
+            match connection.receive() {
+                Ok(_) => continue,
+                Err(error) => break error,
+            }
+        };
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let _stream = writer.join().unwrap();
+
+        // Other side doesn't read sent messages
+        let mut sent = 0;
+        let error = loop {
+            match connection.send(&Message::Step) {
+                Ok(()) => sent += 1,
+                Err(error) => break error,
+            }
+            assert!(sent < 100_000_000);
+        };
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_joined_player_checks_setup() {
+        let setup = Message::Welcome {
+            player_number: 3,
+            seed: 42,
+            width: 800,
+            height: 600,
+            terrain: Terrain::Hills,
+            terrain_params: TerrainParams::default(),
+            wind_params: WindParams::default(),
+            map: None,
+        };
+        // Too few players and number of joined player without player
+        for &count in [0, 1, 2].iter() {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let mut joining = Joining::connect(listener.local_addr().unwrap()).unwrap();
+            let mut host = Connection::new(listener.accept().unwrap().0).unwrap();
+            host.send(&setup).unwrap();
+            for _ in 0..count {
+                host.send(&Message::Player(Player::default())).unwrap();
+            }
+            host.send(&Message::Start).unwrap();
+            let error = loop {
+                match joining.update() {
+                    Ok(started) => assert!(started.is_none()),
+                    Err(error) => break error,
+                }
+            };
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    /// Sends commands of local player if it is their turn.
+    fn play_turn(session: &mut Session, round: &Round, last_turn: &mut Option<usize>) {
+        match round.state {
+            GameState::Aiming if session.is_local_turn(round) => (),
+            _ => return,
+        }
+        if *last_turn == Some(round.number_of_iteration) {
+            return;
+        }
+        *last_turn = Some(round.number_of_iteration);
+        let angle = if session.player_number() == 1 {
+            10.
+        } else {
+            -10.
+        };
+        session.send_command(Command::Angle(angle)).unwrap();
+        session.send_command(Command::Power(15.)).unwrap();
+        session.send_command(Command::Shoot).unwrap();
+    }
+
+    #[test]
+    fn test_host_and_joined_player_stay_in_sync() {
+        let mut lobby = Lobby::bind("127.0.0.1:0", 2).unwrap();
+        let mut joining = Joining::connect(lobby.local_addr().unwrap()).unwrap();
+        while !lobby.is_full() {
+            lobby.update().unwrap();
+        }
+        let setup = Setup {
+            seed: 5,
+            width: 800,
+            height: 600,
+            terrain: Terrain::Mountains,
+            terrain_params: TerrainParams {
+                persistence: 0.4,
+                ..TerrainParams::default()
+            },
+            wind_params: WindParams {
+                mode: WindMode::Gusty,
+                max_power: 8.,
+            },
+            map: None,
+            map_checksum: 0,
+            players: vec![Player::default(), Player::default()],
+        };
+        let mut host = lobby.start(&setup).unwrap();
+        let (mut client, client_setup) = loop {
+            if let Some(started) = joining.update().unwrap() {
+                break started;
+            }
+        };
+        assert_eq!(client.player_number(), 2);
+        assert_eq!(client_setup.seed, setup.seed);
+        assert_eq!(client_setup.terrain, setup.terrain);
+        assert_eq!(client_setup.terrain_params, setup.terrain_params);
+        assert_eq!(client_setup.wind_params, setup.wind_params);
+        assert_eq!(client_setup.map, setup.map);
+
+        let mut host_round = setup.create_round(&[]).unwrap();
+        let mut host_players = setup.players.clone();
+        let mut client_round = client_setup.create_round(&[]).unwrap();
+        let mut client_players = client_setup.players.clone();
+        let mut host_turn = None;
+        let mut client_turn = None;
+        while host_round.number_of_iteration < 4 {
+            play_turn(&mut host, &host_round, &mut host_turn);
+            play_turn(&mut client, &client_round, &mut client_turn);
+            host.update(&mut host_round, &mut host_players).unwrap();
+            while client_round.clock.ticks() < host_round.clock.ticks() {
+                client
+                    .update(&mut client_round, &mut client_players)
+                    .unwrap();
+            }
+            if let GameState::Finish = host_round.state {
+                break;
+            }
+        }
+
+        assert_eq!(client_round.replay(), host_round.replay());
+        // Both players have shot
+        assert!(client_round.replay().shots.len() >= 2);
+        assert_eq!(
+            client_round.landscape.buffer(),
+            host_round.landscape.buffer()
+        );
+        for (tank, client_tank) in host_round.tanks.iter().zip(client_round.tanks.iter()) {
+            assert_eq!(tank.rect, client_tank.rect);
+            assert_eq!(tank.angle, client_tank.angle);
+            assert_eq!(tank.health, client_tank.health);
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! Parsing of values of text records which are used by files of game
+//! and network messages.
+use std::str::FromStr;
+
+/// Parses next value of record with given name.
+pub fn parse_value<'a, T, I>(values: &mut I, name: &str) -> Result<T, String>
+where
+    T: FromStr,
+    I: Iterator<Item = &'a str>,
+{
+    values
+        .next()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| format!("Invalid value of '{}'", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_value() {
+        let mut values = "12 x".split_whitespace();
+        assert_eq!(parse_value::<u8, _>(&mut values, "count"), Ok(12));
+        assert_eq!(
+            parse_value::<u8, _>(&mut values, "count"),
+            Err("Invalid value of 'count'".to_string())
+        );
+        assert!(parse_value::<u8, _>(&mut values, "count").is_err());
+    }
+}
@@ -1,3 +1,144 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::ai::Difficulty;
+use crate::shop::Goods;
+
+/// Money of every player at start of match.
+pub const INITIAL_MONEY: u32 = 500;
+
+/// Who controls a tank of player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Human,
+    Computer(Difficulty),
+}
+
+impl Default for Control {
+    fn default() -> Self {
+        Control::Human
+    }
+}
+
+impl Control {
+    pub fn name(self) -> &'static str {
+        match self {
+            Control::Human => "human",
+            Control::Computer(Difficulty::Easy) => "easy",
+            Control::Computer(Difficulty::Normal) => "normal",
+            Control::Computer(Difficulty::Hard) => "hard",
+        }
+    }
+}
+
+impl FromStr for Control {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Control::Human),
+            "easy" => Ok(Control::Computer(Difficulty::Easy)),
+            "normal" => Ok(Control::Computer(Difficulty::Normal)),
+            "hard" => Ok(Control::Computer(Difficulty::Hard)),
+            _ => Err(format!("Unknown control of player: {}", s)),
+        }
+    }
+}
+
+/// Weapons and items owned by player.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Inventory {
+    goods: BTreeMap<Goods, u32>,
+}
+
+impl Inventory {
+    /// Returns count of given goods. Unlimited weapons are always available
+    /// but never counted.
+    pub fn count(&self, goods: Goods) -> u32 {
+        self.goods.get(&goods).cloned().unwrap_or(0)
+    }
+
+    pub fn has(&self, goods: Goods) -> bool {
+        match goods {
+            Goods::Weapon(weapon) if weapon.is_unlimited() => true,
+            _ => self.count(goods) > 0,
+        }
+    }
+
+    pub fn add(&mut self, goods: Goods, count: u32) {
+        let value = self.goods.entry(goods).or_insert(0);
+        *value = value.saturating_add(count);
+    }
+
+    pub fn remove(&mut self, goods: Goods, count: u32) {
+        if let Some(value) = self.goods.get_mut(&goods) {
+            *value = value.saturating_sub(count);
+            if *value == 0 {
+                self.goods.remove(&goods);
+            }
+        }
+    }
+
+    /// Takes one piece of given goods. Returns `false` if there is nothing to take.
+    pub fn take(&mut self, goods: Goods) -> bool {
+        if !self.has(goods) {
+            return false;
+        }
+        self.remove(goods, 1);
+        true
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Goods, u32)> + '_ {
+        self.goods.iter().map(|(&goods, &count)| (goods, count))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Player {
+    /// Name chosen by player. It is empty for anonymous players.
+    pub name: String,
+    /// Offset of hue of tank's color chosen by player in degrees.
+    pub hue: Option<f32>,
     pub money: u32,
+    pub control: Control,
+    pub inventory: Inventory,
+    /// Total score earned in all rounds of match.
+    pub score: u32,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Player::new(Control::Human)
+    }
+}
+
+impl Player {
+    pub fn new(control: Control) -> Self {
+        Player {
+            name: String::new(),
+            hue: None,
+            money: INITIAL_MONEY,
+            control,
+            inventory: Inventory::default(),
+            score: 0,
+        }
+    }
+
+    /// Returns name of player or "Player N" if the player is anonymous.
+    pub fn title(&self, player_number: u8) -> String {
+        if self.name.is_empty() {
+            format!("Player {}", player_number)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+/// Returns `true` if given name can be used as name of player.
+/// Names are used as single words in saved files, so they can't contain spaces.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
 }
@@ -0,0 +1,223 @@
+//! Profiles of players which keep their names, colors and stats
+//! between sessions.
+//!
+//! Profiles are stored as a text file with one profile per line:
+//!
+//! ```text
+//! tank_war profiles 1
+//! profile Alice 144 5 2 15 21 38
+//! ```
+//!
+//! A `profile` record contains name, hue of color, count of played
+//! matches, count of won matches, count of played rounds, count of
+//! destroyed tanks and total score.
+use std::io::{self, BufRead, Write};
+
+use crate::parse::parse_value;
+use crate::player::{is_valid_name, Player};
+use crate::round::RoundResult;
+
+const HEADER: &str = "tank_war profiles 1";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    /// Offset of hue of tank's color in degrees.
+    pub hue: f32,
+    pub matches: u32,
+    pub wins: u32,
+    pub rounds: u32,
+    pub kills: u32,
+    pub score: u32,
+}
+
+impl Profile {
+    pub fn new(name: &str, hue: f32) -> Self {
+        Profile {
+            name: name.to_string(),
+            hue,
+            matches: 0,
+            wins: 0,
+            rounds: 0,
+            kills: 0,
+            score: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profiles {
+    list: Vec<Profile>,
+}
+
+impl Profiles {
+    pub fn iter(&self) -> impl Iterator<Item = &Profile> {
+        self.list.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.list.iter().find(|p| p.name == name)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut Profile> {
+        self.list.iter_mut().find(|p| p.name == name)
+    }
+
+    /// Creates profile for every named player or updates color of existing one.
+    pub fn update_players(&mut self, players: &[Player]) {
+        for player in players.iter().filter(|p| is_valid_name(&p.name)) {
+            let hue = player.hue.unwrap_or(0.);
+            match self.get_mut(&player.name) {
+                Some(profile) => profile.hue = hue,
+                None => self.list.push(Profile::new(&player.name, hue)),
+            }
+        }
+    }
+
+    /// Adds results of finished round to profiles of named players.
+    pub fn record_round(&mut self, players: &[Player], results: &[RoundResult]) {
+        for result in results.iter() {
+            let player = match players.get(result.player_number as usize - 1) {
+                Some(player) => player,
+                None => continue,
+            };
+            if let Some(profile) = self.get_mut(&player.name) {
+                profile.rounds += 1;
+                profile.kills += result.kills;
+                profile.score += result.score;
+            }
+        }
+    }
+
+    /// Counts finished match in profiles of named players.
+    pub fn record_match(&mut self, players: &[Player], winners: &[u8]) {
+        for (index, player) in players.iter().enumerate() {
+            if let Some(profile) = self.get_mut(&player.name) {
+                profile.matches += 1;
+                if winners.contains(&(index as u8 + 1)) {
+                    profile.wins += 1;
+                }
+            }
+        }
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        for profile in self.list.iter() {
+            writeln!(
+                writer,
+                "profile {} {} {} {} {} {} {}",
+                profile.name,
+                profile.hue,
+                profile.matches,
+                profile.wins,
+                profile.rounds,
+                profile.kills,
+                profile.score
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(reader: R) -> Result<Profiles, String> {
+        let mut lines = reader.lines();
+        match lines.next() {
+            Some(Ok(ref line)) if line.trim() == HEADER => (),
+            _ => return Err("Unknown format of profiles".into()),
+        }
+
+        let mut profiles = Profiles::default();
+        for line in lines {
+            let line = line.map_err(|e| e.to_string())?;
+            let mut values = line.split_whitespace();
+            match values.next() {
+                Some("profile") => {
+                    let name: String = parse_value(&mut values, "profile")?;
+                    if !is_valid_name(&name) || profiles.get(&name).is_some() {
+                        return Err(format!("Invalid name of profile: {}", name));
+                    }
+                    profiles.list.push(Profile {
+                        name,
+                        hue: parse_value(&mut values, "profile")?,
+                        matches: parse_value(&mut values, "profile")?,
+                        wins: parse_value(&mut values, "profile")?,
+                        rounds: parse_value(&mut values, "profile")?,
+                        kills: parse_value(&mut values, "profile")?,
+                        score: parse_value(&mut values, "profile")?,
+                    });
+                }
+                Some(key) => return Err(format!("Unknown record '{}' in profiles", key)),
+                None => (),
+            }
+        }
+        Ok(profiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_stats_and_restore() {
+        let mut players: Vec<Player> = (0..3).map(|_| Player::default()).collect();
+        players[0].name = "Alice".into();
+        players[0].hue = Some(144.);
+        players[1].name = "Bob".into();
+        players[2].name = "Carol".into();
+        let mut profiles = Profiles::default();
+        profiles.update_players(&players);
+        assert_eq!(profiles.len(), 3);
+
+        let results = [
+            RoundResult {
+                player_number: 1,
+                survived: true,
+                kills: 2,
+                money_earned: 700,
+                score: 4,
+            },
+            RoundResult {
+                player_number: 2,
+                survived: false,
+                kills: 0,
+                money_earned: 0,
+                score: 0,
+            },
+            RoundResult {
+                player_number: 3,
+                survived: true,
+                kills: 1,
+                money_earned: 200,
+                score: 3,
+            },
+        ];
+        profiles.record_round(&players, &results);
+        profiles.record_match(&players, &[1]);
+
+        let alice = profiles.get("Alice").unwrap();
+        assert_eq!(alice.hue, 144.);
+        assert_eq!((alice.matches, alice.wins, alice.rounds), (1, 1, 1));
+        assert_eq!((alice.kills, alice.score), (2, 4));
+        let bob = profiles.get("Bob").unwrap();
+        assert_eq!((bob.matches, bob.wins, bob.rounds), (1, 0, 1));
+        assert_eq!((bob.kills, bob.score), (0, 0));
+        let carol = profiles.get("Carol").unwrap();
+        assert_eq!((carol.matches, carol.wins, carol.rounds), (1, 0, 1));
+        assert_eq!((carol.kills, carol.score), (1, 3));
+
+        let mut buf: Vec<u8> = Vec::new();
+        profiles.write(&mut buf).unwrap();
+        assert_eq!(Profiles::read(&buf[..]).unwrap(), profiles);
+    }
+}
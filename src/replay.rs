@@ -0,0 +1,372 @@
+//! Recording of a match which allows to play it again.
+//!
+//! Replay is stored as a text file with one record per line:
+//!
+//! ```text
+//! tank_war replay 1
+//! width 1022
+//! height 766
+//! players 2
+//! seed 8972350171023
+//! terrain hills
+//! terrain_params 4 2 2 0.5 0.5 0.5
+//! wind_params gusty 10
+//! map canyon
+//! shot 248 -45 87 baby_missile
+//! item 1310 repair_kit
+//! shot 1702 30 64 big_missile
+//! ```
+//!
+//! A `terrain` record contains style of landscape (`hills` if omitted).
+//! A `terrain_params` record contains count of octaves, frequency,
+//! lacunarity, persistence, amplitude and base height of noise used
+//! for generating of landscape (default ones if omitted).
+//! A `wind_params` record contains mode of wind and its max power
+//! (`constant 10` if omitted).
+//! A `map` record contains name of custom map which replaced generated
+//! landscape (there is no record if landscape was generated).
+//! Every `shot` record contains the tick of game clock at which the shot
+//! was fired, the angle and the power of gun and the weapon
+//! (`baby_missile` if omitted). Every `item` record contains the tick at which
+//! the item was used and its name.
+use std::io::{self, BufRead, Write};
+
+use crate::item::Item;
+use crate::landscape::{Terrain, TerrainParams};
+use crate::map::{self, Map};
+use crate::parse::parse_value;
+use crate::round::Round;
+use crate::weapon::Weapon;
+use crate::wind::WindParams;
+use crate::MAX_PLAYERS_COUNT;
+
+const HEADER: &str = "tank_war replay 1";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shot {
+    pub tick: u64,
+    pub angle: f32,
+    pub power: f32,
+    pub weapon: Weapon,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItemUse {
+    pub tick: u64,
+    pub item: Item,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub width: u16,
+    pub height: u16,
+    pub players_count: u8,
+    pub seed: u64,
+    pub terrain: Terrain,
+    pub terrain_params: TerrainParams,
+    pub wind_params: WindParams,
+    /// Name of custom map used instead of generated landscape.
+    pub map: Option<String>,
+    pub shots: Vec<Shot>,
+    pub item_uses: Vec<ItemUse>,
+}
+
+impl Replay {
+    pub fn new(
+        width: u16,
+        height: u16,
+        players_count: u8,
+        seed: u64,
+        terrain: Terrain,
+        terrain_params: TerrainParams,
+    ) -> Self {
+        Replay {
+            width,
+            height,
+            players_count,
+            seed,
+            terrain,
+            terrain_params,
+            wind_params: WindParams::default(),
+            map: None,
+            shots: Vec::new(),
+            item_uses: Vec::new(),
+        }
+    }
+
+    /// Creates a round in the same state as recorded round had on its start.
+    /// Map used by recorded round has to be among given maps.
+    pub fn create_round(&self, maps: &[Map]) -> Result<Round, String> {
+        let mut round = Round::new(
+            self.width,
+            self.height,
+            self.players_count,
+            self.seed,
+            self.terrain,
+            self.terrain_params,
+        )?;
+        round.set_wind_params(self.wind_params);
+        if let Some(map) = map::find(maps, self.map.as_deref())? {
+            round.load_map(map);
+        }
+        Ok(round)
+    }
+
+    /// Returns a shot fired at given tick of game clock.
+    pub fn shot(&self, tick: u64) -> Option<&Shot> {
+        self.shots
+            .binary_search_by_key(&tick, |s| s.tick)
+            .ok()
+            .map(|i| &self.shots[i])
+    }
+
+    /// Returns an item used at given tick of game clock.
+    pub fn item_use(&self, tick: u64) -> Option<&ItemUse> {
+        self.item_uses
+            .binary_search_by_key(&tick, |u| u.tick)
+            .ok()
+            .map(|i| &self.item_uses[i])
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(writer, "width {}", self.width)?;
+        writeln!(writer, "height {}", self.height)?;
+        writeln!(writer, "players {}", self.players_count)?;
+        writeln!(writer, "seed {}", self.seed)?;
+        writeln!(writer, "terrain {}", self.terrain)?;
+        writeln!(writer, "terrain_params {}", self.terrain_params)?;
+        writeln!(writer, "wind_params {}", self.wind_params)?;
+        if let Some(ref map) = self.map {
+            writeln!(writer, "map {}", map)?;
+        }
+        // Records are written in order of time
+        let mut item_uses = self.item_uses.iter().peekable();
+        for shot in self.shots.iter() {
+            while let Some(item_use) = item_uses.peek() {
+                if item_use.tick > shot.tick {
+                    break;
+                }
+                writeln!(writer, "item {} {}", item_use.tick, item_use.item)?;
+                item_uses.next();
+            }
+            writeln!(
+                writer,
+                "shot {} {} {} {}",
+                shot.tick, shot.angle, shot.power, shot.weapon
+            )?;
+        }
+        for item_use in item_uses {
+            writeln!(writer, "item {} {}", item_use.tick, item_use.item)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, String> {
+        let mut lines = reader.lines();
+        match lines.next() {
+            Some(Ok(ref line)) if line.trim() == HEADER => (),
+            _ => return Err("Unknown format of replay".into()),
+        }
+
+        let mut replay = Replay::new(0, 0, 0, 0, Terrain::default(), TerrainParams::default());
+        for line in lines {
+            let line = line.map_err(|e| e.to_string())?;
+            let mut values = line.split_whitespace();
+            let key = match values.next() {
+                Some(key) => key,
+                None => continue,
+            };
+            match key {
+                "width" => replay.width = parse_value(&mut values, key)?,
+                "height" => replay.height = parse_value(&mut values, key)?,
+                "players" => replay.players_count = parse_value(&mut values, key)?,
+                "seed" => replay.seed = parse_value(&mut values, key)?,
+                "terrain" => replay.terrain = parse_value(&mut values, key)?,
+                "terrain_params" => replay.terrain_params = TerrainParams::parse(&mut values)?,
+                "wind_params" => replay.wind_params = WindParams::parse(&mut values)?,
+                "map" => replay.map = Some(parse_value(&mut values, key)?),
+                "shot" => {
+                    let shot = Shot {
+                        tick: parse_value(&mut values, key)?,
+                        angle: parse_value(&mut values, key)?,
+                        power: parse_value(&mut values, key)?,
+                        weapon: match values.next() {
+                            Some(weapon) => weapon.parse()?,
+                            None => Weapon::default(),
+                        },
+                    };
+                    if let Some(last_shot) = replay.shots.last() {
+                        if last_shot.tick >= shot.tick {
+                            return Err("Shots of replay are not sorted by time".into());
+                        }
+                    }
+                    replay.shots.push(shot);
+                }
+                "item" => {
+                    let item_use = ItemUse {
+                        tick: parse_value(&mut values, key)?,
+                        item: parse_value(&mut values, key)?,
+                    };
+                    if let Some(last_use) = replay.item_uses.last() {
+                        if last_use.tick >= item_use.tick {
+                            return Err("Used items of replay are not sorted by time".into());
+                        }
+                    }
+                    replay.item_uses.push(item_use);
+                }
+                _ => return Err(format!("Unknown record '{}' in replay", key)),
+            }
+        }
+
+        // Records of size and players are required, they are zero if missing
+        if replay.width == 0 || replay.height == 0 {
+            return Err("Invalid size of landscape in replay".into());
+        }
+        if !(2..=MAX_PLAYERS_COUNT).contains(&replay.players_count) {
+            return Err(format!(
+                "Invalid count of players in replay: {}",
+                replay.players_count
+            ));
+        }
+        Ok(replay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+    use crate::round::GameState;
+    use crate::shop::Goods;
+    use crate::wind::WindMode;
+
+    #[test]
+    fn test_write_and_read() {
+        let params = TerrainParams {
+            octaves: 6,
+            amplitude: 0.25,
+            ..TerrainParams::default()
+        };
+        let mut replay = Replay::new(800, 600, 3, 1234567, Terrain::Mountains, params);
+        replay.shots.push(Shot {
+            tick: 100,
+            angle: -45.,
+            power: 87.,
+            weapon: Weapon::BabyMissile,
+        });
+        replay.item_uses.push(ItemUse {
+            tick: 500,
+            item: Item::RepairKit,
+        });
+        replay.shots.push(Shot {
+            tick: 900,
+            angle: 12.5,
+            power: 0.1,
+            weapon: Weapon::BigMissile,
+        });
+        replay.map = Some("canyon".into());
+        replay.wind_params = WindParams {
+            mode: WindMode::Changing,
+            max_power: 4.5,
+        };
+
+        let mut buf: Vec<u8> = Vec::new();
+        replay.write(&mut buf).unwrap();
+        let read_replay = Replay::read(&buf[..]).unwrap();
+        assert_eq!(read_replay, replay);
+        assert_eq!(read_replay.shot(900), Some(&replay.shots[1]));
+        assert_eq!(read_replay.shot(901), None);
+
+        assert!(Replay::read(&b"some text"[..]).is_err());
+        assert!(Replay::read(&b"tank_war replay 1\nseed abc\n"[..]).is_err());
+
+        // Size of landscape and count of players are required
+        let text = String::from_utf8(buf).unwrap();
+        for (record, replaced) in [
+            ("players 3", "players 0"),
+            ("players 3", ""),
+            ("width 800", ""),
+        ]
+        .iter()
+        {
+            let broken = text.replace(record, replaced);
+            assert!(Replay::read(broken.as_bytes()).is_err(), "{}", replaced);
+        }
+    }
+
+    fn play(round: &mut Round, players: &mut [Player], replay: Option<&Replay>) {
+        let mut shots = vec![
+            (30., 70., Weapon::BabyMissile),
+            (-20., 60., Weapon::BigMissile),
+            (50., 40., Weapon::BabyMissile),
+        ]
+        .into_iter();
+        for _ in 0..100_000 {
+            if let Some(replay) = replay {
+                let last_tick = replay.shots.last().map_or(0, |s| s.tick);
+                if round.clock.ticks() > last_tick {
+                    if let GameState::Aiming = round.state {
+                        return;
+                    }
+                }
+                round.play_replay(replay);
+            } else if let GameState::Aiming = round.state {
+                // Player aims some time before shot
+                if round.clock.ticks() % 50 == 0 {
+                    match shots.next() {
+                        Some((angle, power, weapon)) => {
+                            round.use_item(Item::RepairKit, players);
+                            round.inc_gun_angle(angle - round.gun_angle());
+                            round.inc_gun_power(power - round.gun_power());
+                            round.select_weapon(weapon, players);
+                            round.shoot(players);
+                        }
+                        None => return,
+                    }
+                }
+            }
+            if let GameState::Finish = round.update(players) {
+                return;
+            }
+        }
+    }
+
+    #[test]
+    fn test_play_replay() {
+        let mut players: Vec<Player> = (0..2).map(|_| Player::default()).collect();
+        for player in players.iter_mut() {
+            player.inventory.add(Goods::Weapon(Weapon::BigMissile), 1);
+            player.inventory.add(Goods::Item(Item::RepairKit), 1);
+        }
+        let mut round =
+            Round::new(800, 600, 2, 42, Terrain::Hills, TerrainParams::default()).unwrap();
+        round.set_wind_params(WindParams {
+            mode: WindMode::Gusty,
+            max_power: 15.,
+        });
+        // Flat ground at bottom half of map with two spawn points above it
+        let mut rgba = vec![0; 8 * 6 * 4];
+        rgba[8 * 3 * 4..].iter_mut().for_each(|v| *v = 255);
+        for &x in [1, 6].iter() {
+            rgba[(8 + x) * 4..(8 + x) * 4 + 4].copy_from_slice(&[255, 0, 0, 255]);
+        }
+        let map = Map::from_rgba("flat", 8, 6, &rgba).unwrap();
+        round.load_map(&map);
+        assert_eq!(round.map(), Some("flat"));
+        play(&mut round, &mut players, None);
+        let replay = round.replay().clone();
+        assert!(!replay.shots.is_empty());
+        assert!(!replay.item_uses.is_empty());
+
+        assert!(replay.create_round(&[]).is_err());
+        let mut replayed_round = replay.create_round(&[map]).unwrap();
+        play(&mut replayed_round, &mut players, Some(&replay));
+        assert_eq!(replayed_round.replay(), &replay);
+        for (tank, replayed_tank) in round.tanks.iter().zip(replayed_round.tanks.iter()) {
+            assert_eq!(tank.rect, replayed_tank.rect);
+            assert_eq!(tank.health, replayed_tank.health);
+            assert_eq!(tank.dead, replayed_tank.dead);
+        }
+    }
+}
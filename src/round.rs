@@ -1,79 +1,273 @@
-use ggez::audio::SoundSource;
-use rand::rngs::ThreadRng;
 use rand::seq::SliceRandom;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
 
+use crate::clock::Clock;
 use crate::explosion::Explosion;
-use crate::landscape::Landscape;
-use crate::missile::Missile;
+use crate::item::Item;
+use crate::landscape::{Landscape, Terrain, TerrainParams};
+use crate::map::Map;
+use crate::missile::{Missile, MissileEvent};
+use crate::player::Player;
+use crate::replay::{ItemUse, Replay, Shot};
+use crate::shop::Goods;
 use crate::tank::{Tank, TankState};
-use crate::types::Vector2;
-use crate::world::World;
+use crate::types::{HueOffset, Point2, Vector2};
+use crate::weapon::{Impact, Weapon};
+use crate::wind::{WindMode, WindParams};
 use crate::{G, MAX_PLAYERS_COUNT};
 
 /// A damage per one pixel of height with which tank was dropped.
 const TANK_THROWING_DAMAGE_POWER: f32 = 0.1;
+pub const EXPLOSION_RADIUS: f32 = 50.0;
+/// Max game time of flight of missile used in simulation of shot.
+const MAX_SIMULATED_FLIGHT_TIME: f32 = 20.0;
+/// Length of tunnel dug by digger.
+const TUNNEL_LENGTH: f32 = 200.0;
+/// Money which player gets for every destroyed enemy tank.
+const KILL_MONEY: u32 = 200;
+/// Money which every player gets if their tank survived in round.
+const SURVIVAL_MONEY: u32 = 300;
+const KILL_SCORE: u32 = 1;
+const SURVIVAL_SCORE: u32 = 2;
 
 #[derive(Debug, Clone)]
 pub enum GameState {
     TanksThrowing,
     Aiming,
-    FlyingOfMissile(Missile),
+    FlyingOfMissiles(Vec<Missile>),
     Exploding(Vec<Explosion>),
     Subsidence,
     Finish,
 }
 
+/// Things that happened inside of round and that may be interesting
+/// for an outside code (e.g. for playing sounds).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundEvent {
+    Shot,
+    Explosion,
+}
+
+/// Achievements of player in current round.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PlayerStats {
+    pub kills: u32,
+    pub money_earned: u32,
+}
+
+/// Result of finished round for one player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundResult {
+    pub player_number: u8,
+    pub survived: bool,
+    pub kills: u32,
+    pub money_earned: u32,
+    /// Score earned in the round.
+    pub score: u32,
+}
+
 pub struct Round {
-    pub rng: ThreadRng,
+    seed: u64,
+    pub rng: Pcg32,
+    pub clock: Clock,
     pub width: f32,
     pub height: f32,
     pub landscape: Landscape,
+    /// Power of wind during current turn without gusts.
     pub wind_power: f32,
+    // Wind drawn at creation of round for default limit of wind.
+    drawn_wind_power: f32,
     pub tanks: Vec<Tank>,
     pub current_tank: usize,
     pub state: GameState,
     pub number_of_iteration: usize,
+    /// Stats of players in order of their numbers.
+    pub stats: Vec<PlayerStats>,
+    events: Vec<RoundEvent>,
+    // Weapons and points of missiles which have hit something during current shot.
+    missile_hits: Vec<(Weapon, Point2)>,
+    pub(crate) replay: Replay,
+}
+
+/// Returns offset of hue of tank's color for given player.
+pub(crate) fn player_hue_offset(player_number: u8) -> u16 {
+    (u16::from(player_number) - 1) * (360 / u16::from(MAX_PLAYERS_COUNT))
+}
+
+/// Generates landscape of round using given generator of random numbers.
+fn create_landscape(
+    rng: &mut Pcg32,
+    width: u16,
+    height: u16,
+    terrain: Terrain,
+    params: TerrainParams,
+) -> Result<Landscape, String> {
+    let mut landscape = Landscape::new(width, height)?;
+    landscape.set_params(params);
+    landscape.set_seed(rng.gen());
+    landscape.dx = rng.gen_range(0, width as i32 / 2);
+    landscape.generate(terrain);
+    Ok(landscape)
+}
+
+/// Returns landscape which round with given seed will have.
+pub fn preview_landscape(
+    width: u16,
+    height: u16,
+    seed: u64,
+    terrain: Terrain,
+    params: TerrainParams,
+) -> Result<Landscape, String> {
+    create_landscape(
+        &mut Pcg32::seed_from_u64(seed),
+        width,
+        height,
+        terrain,
+        params,
+    )
 }
 
 impl Round {
-    pub fn new(width: u16, height: u16, count_of_tanks: u8) -> Result<Round, String> {
-        let mut rng = rand::thread_rng();
-        let mut landscape = Landscape::new(width, height)?;
-        landscape.set_seed(rng.gen());
-        landscape.dx = rng.gen_range(0, width as i32 / 2);
-        landscape.generate();
+    /// Creates new round. Landscape of given style, order of players
+    /// and wind are fully determined by given `seed`.
+    pub fn new(
+        width: u16,
+        height: u16,
+        count_of_tanks: u8,
+        seed: u64,
+        terrain: Terrain,
+        params: TerrainParams,
+    ) -> Result<Round, String> {
+        if !(2..=MAX_PLAYERS_COUNT).contains(&count_of_tanks) {
+            return Err(format!("Invalid count of tanks: {}", count_of_tanks));
+        }
+        let mut rng = Pcg32::seed_from_u64(seed);
+        let landscape = create_landscape(&mut rng, width, height, terrain, params)?;
 
         let size_between_tanks = (width as f32 - 200.) / (count_of_tanks - 1) as f32;
 
         let mut player_numbers: Vec<u8> = (1..=count_of_tanks).collect();
         player_numbers.shuffle(&mut rng);
 
+        let clock = Clock::new();
         let tanks: Vec<Tank> = player_numbers
             .iter()
             .enumerate()
             .map(|(i, &player_number)| {
                 let x = 100. + size_between_tanks * i as f32;
-                let hue_offset = (player_number as u16 - 1) * (360 / MAX_PLAYERS_COUNT as u16);
-                Tank::new(player_number, [x, 50.], hue_offset)
+                let mut tank = Tank::new(player_number, [x, 50.], player_hue_offset(player_number));
+                tank.throw_down(clock.now(), None);
+                tank
             })
             .collect();
 
         let mut round = Round {
+            seed,
             rng,
+            clock,
             width: width as f32,
             height: height as f32,
             landscape,
             wind_power: 0.0,
+            drawn_wind_power: 0.0,
             tanks,
             current_tank: 0,
             state: GameState::TanksThrowing,
             number_of_iteration: 0,
+            stats: vec![PlayerStats::default(); count_of_tanks as usize],
+            events: Vec::new(),
+            missile_hits: Vec::new(),
+            replay: Replay::new(width, height, count_of_tanks, seed, terrain, params),
         };
-        round.change_wind();
+        round.drawn_wind_power = round.rng.gen_range(-10.0_f32, 10.0_f32);
+        round.wind_power = round.wind_params().initial_power(round.drawn_wind_power);
         Ok(round)
     }
 
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns style of landscape of this round.
+    #[inline]
+    pub fn terrain(&self) -> Terrain {
+        self.replay.terrain
+    }
+
+    #[inline]
+    pub fn terrain_params(&self) -> TerrainParams {
+        self.replay.terrain_params
+    }
+
+    #[inline]
+    pub fn wind_params(&self) -> WindParams {
+        self.replay.wind_params
+    }
+
+    /// Sets mode and limit of wind. Wind of first turn is scaled
+    /// to given limit.
+    pub fn set_wind_params(&mut self, params: WindParams) {
+        self.replay.wind_params = params;
+        self.wind_power = params.initial_power(self.drawn_wind_power);
+    }
+
+    /// Returns power of wind at current moment including gusts.
+    pub fn current_wind_power(&self) -> f32 {
+        let gust = self
+            .wind_params()
+            .gust(self.seed, self.number_of_iteration, self.clock.now());
+        self.wind_power + gust
+    }
+
+    /// Returns name of custom map used instead of generated landscape.
+    #[inline]
+    pub fn map(&self) -> Option<&str> {
+        self.replay.map.as_deref()
+    }
+
+    /// Replaces generated landscape by given map. Tanks are placed
+    /// at spawn points of map if it has enough of them.
+    pub fn load_map(&mut self, map: &Map) {
+        let (width, height) = self.landscape.size();
+        self.landscape.load_map(map);
+        let spawn_points = map.scaled_spawn_points(width, height);
+        if spawn_points.len() >= self.tanks.len() {
+            // Order of tanks is already random
+            let now = self.clock.now();
+            for (tank, &point) in self.tanks.iter_mut().zip(spawn_points.iter()) {
+                tank.set_bottom_center(point);
+                tank.throw_down(now, None);
+            }
+        }
+        self.replay.map = Some(map.name().to_string());
+    }
+
+    /// Returns a record of all shots fired in this round.
+    #[inline]
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Uses an item and fires a shot from given replay if they were
+    /// used and fired at current tick of clock.
+    pub fn play_replay(&mut self, replay: &Replay) {
+        if let GameState::Aiming = self.state {
+            if let Some(item_use) = replay.item_use(self.clock.ticks()) {
+                self.apply_item(item_use.item);
+            }
+            if let Some(shot) = replay.shot(self.clock.ticks()) {
+                if let Some(tank) = self.tanks.get_mut(self.current_tank) {
+                    tank.angle = shot.angle;
+                    tank.power = shot.power;
+                    tank.weapon = shot.weapon;
+                }
+                self.fire();
+            }
+        }
+    }
+
     //    #[inline]
     //    pub fn update_landscape_seed(&mut self) {
     //        self.landscape.set_seed(self.rng.gen());
@@ -90,20 +284,28 @@ impl Round {
     //        self.state = GameState::TanksThrowing;
     //    }
 
-    pub fn update(&mut self, world: &mut World) -> &GameState {
-        self.update_tanks(world);
-        self.update_missile(world);
-        self.update_explosions();
-        self.update_landscape();
+    /// Advances game clock by one tick and updates state of round.
+    pub fn update(&mut self, players: &mut [Player]) -> &GameState {
+        self.clock.tick();
+        let now = self.clock.now();
+        self.update_tanks(now, players);
+        self.update_missiles(now);
+        self.update_explosions(now);
+        self.update_landscape(now);
         &self.state
     }
 
-    fn update_tanks(&mut self, world: &mut World) {
+    /// Returns events happened since last call of this method.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = RoundEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    fn update_tanks(&mut self, now: f32, players: &mut [Player]) {
         if let GameState::TanksThrowing = self.state {
             let mut all_placed = true;
             let live_tanks = self.tanks.iter_mut().filter(|t| !t.dead);
             for tank in live_tanks {
-                let tank_state = tank.update(&mut self.landscape);
+                let tank_state = tank.update(now, &mut self.landscape);
                 if let TankState::Placed(path_len) = tank_state {
                     if self.number_of_iteration > 0 {
                         let damage_value: u8 =
@@ -116,52 +318,101 @@ impl Round {
             }
 
             if all_placed {
-                let explosions = self.remove_destroyed_tanks(world);
+                let explosions = self.remove_destroyed_tanks(now, players);
 
                 self.state = if !explosions.is_empty() {
-                    world.explosion_sound.play().unwrap();
+                    self.events.push(RoundEvent::Explosion);
                     GameState::Exploding(explosions)
                 } else if self.live_tanks_count() <= 1 {
+                    self.finish(players);
                     GameState::Finish
                 } else {
                     if self.number_of_iteration > 0 {
                         self.switch_current_tank();
                     }
-                    // self.change_wind();
                     self.number_of_iteration = self.number_of_iteration.saturating_add(1);
+                    self.rng = Self::turn_rng(self.seed, self.number_of_iteration);
+                    if self.number_of_iteration > 1 {
+                        self.change_wind();
+                    }
                     GameState::Aiming
                 };
             }
         }
     }
 
-    fn update_missile(&mut self, world: &mut World) {
-        if let GameState::FlyingOfMissile(ref mut missile) = self.state {
-            let landscape = &self.landscape;
-            let tanks = &self.tanks;
-            let size = landscape.size();
-            let borders = (size.0 as i32, size.1 as i32);
-            let hit_point = missile.update(borders, |x, y| {
-                landscape.is_not_empty(x, y)
-                    || tanks
-                        .iter()
-                        .filter(|t| !t.dead)
-                        .any(|t| t.has_collision((x as f32, y as f32)))
-            });
-            if let Some(pos) = hit_point {
-                world.explosion_sound.play().unwrap();
-                self.state = GameState::Exploding(vec![Explosion::new(pos, 50.0)]);
+    fn update_missiles(&mut self, now: f32) {
+        let acceleration = self.missile_acceleration();
+        let gusty = self.wind_params().mode == WindMode::Gusty;
+        if let GameState::FlyingOfMissiles(ref mut missiles) = self.state {
+            let mut warheads: Vec<Missile> = Vec::new();
+            for missile in missiles.iter_mut() {
+                if gusty {
+                    missile.set_acceleration(acceleration);
+                }
+                match missile.update(now, &self.landscape, &self.tanks) {
+                    Some(MissileEvent::Split(new_missiles)) => warheads.extend(new_missiles),
+                    Some(MissileEvent::Hit(pos)) => {
+                        let weapon = missile.weapon;
+                        if weapon.impact() == Impact::Tunnel {
+                            let radius = weapon.explosion_radius();
+                            let direction = missile.direction();
+                            self.landscape
+                                .dig_tunnel(pos, direction, TUNNEL_LENGTH, radius);
+                        }
+                        self.missile_hits.push((weapon, pos));
+                    }
+                    None => (),
+                }
+            }
+            missiles.retain(|m| !m.is_finished());
+            missiles.extend(warheads);
+
+            if missiles.is_empty() {
+                let explosions: Vec<Explosion> = self
+                    .missile_hits
+                    .drain(..)
+                    .filter(|(weapon, _)| weapon.impact() != Impact::Tunnel)
+                    .map(|(weapon, pos)| {
+                        Explosion::new(now, pos, weapon.explosion_radius()).damage(weapon.damage())
+                    })
+                    .collect();
+                if explosions.is_empty() {
+                    self.landscape.subsidence(now);
+                    self.state = GameState::Subsidence;
+                } else {
+                    self.events.push(RoundEvent::Explosion);
+                    self.state = GameState::Exploding(explosions);
+                }
             }
         }
     }
 
-    fn update_explosions(&mut self) {
+    /// Returns a point where a missile fired by current tank with given
+    /// angle and power of gun would hit something.
+    pub fn simulate_shot(&self, angle: f32, power: f32) -> Option<Point2> {
+        let mut tank = self.tanks.get(self.current_tank)?.clone();
+        tank.angle = angle;
+        tank.power = power;
+        let now = self.clock.now();
+        let mut missile = tank.shoot(now, self.missile_acceleration());
+        let end_time = now + MAX_SIMULATED_FLIGHT_TIME;
+        missile.fly(end_time, &self.landscape, &self.tanks)
+    }
+
+    #[inline]
+    fn missile_acceleration(&self) -> Vector2 {
+        Vector2::new(self.current_wind_power(), G)
+    }
+
+    fn update_explosions(&mut self, now: f32) {
         if let GameState::Exploding(ref mut explosions) = self.state {
+            self.landscape.update_debris(now);
             let landscape = &mut self.landscape;
             let count_not_finished_explosions = explosions
                 .iter_mut()
                 .filter_map(|e| {
-                    if e.update(landscape) {
+                    if e.update(now, landscape) {
                         return None;
                     };
                     Some(())
@@ -173,53 +424,118 @@ impl Round {
                 let live_tanks = self.tanks.iter_mut().filter(|t| !t.dead);
                 for tank in live_tanks {
                     for e in explosions.iter() {
-                        let percents = e.get_intersection_percents(tank.body_rect());
-                        tank.damage(percents);
+                        tank.take_hit(e.get_damage(tank.body_rect()));
                     }
                 }
-                self.landscape.subsidence();
+                self.landscape.subsidence(now);
                 self.state = GameState::Subsidence;
             }
         }
     }
 
-    fn update_landscape(&mut self) {
+    fn update_landscape(&mut self, now: f32) {
         if let GameState::Subsidence = self.state {
-            if self.landscape.update() {
+            if self.landscape.update(now) {
                 let live_tanks = self.tanks.iter_mut().filter(|t| !t.dead);
                 for tank in live_tanks {
-                    tank.throw_down(None);
+                    tank.throw_down(now, None);
                 }
                 self.state = GameState::TanksThrowing;
             }
         }
     }
 
+    /// Returns random numbers generator for given turn. It is created
+    /// at start of every turn to make a state of round easy to restore.
+    pub(crate) fn turn_rng(seed: u64, number_of_iteration: usize) -> Pcg32 {
+        Pcg32::new(seed, number_of_iteration as u64)
+    }
+
     fn change_wind(&mut self) {
-        self.wind_power = (self.rng.gen_range(-10.0_f32, 10.0_f32) * 10.0).round() / 10.0;
+        let params = self.wind_params();
+        self.wind_power = params.turn_power(self.wind_power, self.seed, self.number_of_iteration);
     }
 
     /// Mark all destroyed tanks as "dead", add some money to current player
-    /// and returns vector of tanks explosions.
-    fn remove_destroyed_tanks(&mut self, world: &mut World) -> Vec<Explosion> {
+    /// for every destroyed enemy and returns vector of tanks explosions.
+    fn remove_destroyed_tanks(&mut self, now: f32, players: &mut [Player]) -> Vec<Explosion> {
+        let current_player_number = self.player_number();
+        let mut kills = 0;
         let explosions: Vec<Explosion> = self
             .tanks
             .iter_mut()
             .filter(|t| t.health == 0 && !t.dead)
             .map(|t| {
                 t.dead = true;
-                Explosion::new(t.center(), 50.0)
+                if t.player_number != current_player_number {
+                    kills += 1;
+                }
+                Explosion::new(now, t.center(), EXPLOSION_RADIUS)
             })
             .collect();
 
-        let current_player_number = self.player_number() as usize;
-        let player = &mut world.players[current_player_number - 1];
-        let count_of_destroyed = explosions.len() as u32;
-        player.money = player.money.saturating_add(200 * count_of_destroyed);
+        let index = current_player_number as usize - 1;
+        let money = KILL_MONEY * kills;
+        if let Some(player) = players.get_mut(index) {
+            player.money = player.money.saturating_add(money);
+        }
+        if let Some(stats) = self.stats.get_mut(index) {
+            stats.kills += kills;
+            stats.money_earned += money;
+        }
 
         explosions
     }
 
+    /// Pays bonus to players whose tanks survived and adds scores of round
+    /// to total scores of players.
+    fn finish(&mut self, players: &mut [Player]) {
+        for tank in self.tanks.iter().filter(|t| !t.dead) {
+            let index = tank.player_number as usize - 1;
+            if let Some(player) = players.get_mut(index) {
+                player.money = player.money.saturating_add(SURVIVAL_MONEY);
+            }
+            if let Some(stats) = self.stats.get_mut(index) {
+                stats.money_earned += SURVIVAL_MONEY;
+            }
+        }
+        for result in self.results() {
+            if let Some(player) = players.get_mut(result.player_number as usize - 1) {
+                player.score = player.score.saturating_add(result.score);
+            }
+        }
+    }
+
+    /// Returns results of players in order of their numbers.
+    pub fn results(&self) -> Vec<RoundResult> {
+        self.stats
+            .iter()
+            .enumerate()
+            .map(|(i, stats)| {
+                let player_number = i as u8 + 1;
+                let survived = self.live_tanks().any(|t| t.player_number == player_number);
+                let survival_score = if survived { SURVIVAL_SCORE } else { 0 };
+                RoundResult {
+                    player_number,
+                    survived,
+                    kills: stats.kills,
+                    money_earned: stats.money_earned,
+                    score: stats.kills * KILL_SCORE + survival_score,
+                }
+            })
+            .collect()
+    }
+
+    /// Sets colors of tanks chosen by their players.
+    pub fn set_player_colors(&mut self, players: &[Player]) {
+        for tank in self.tanks.iter_mut() {
+            let player = players.get(tank.player_number as usize - 1);
+            if let Some(hue) = player.and_then(|p| p.hue) {
+                tank.hue_offset = HueOffset::new(hue);
+            }
+        }
+    }
+
     fn switch_current_tank(&mut self) {
         let mut current_tank = self.current_tank;
         for _ in 0..self.tanks.len() {
@@ -305,13 +621,212 @@ impl Round {
         }
     }
 
-    pub fn shoot(&mut self, world: &mut World) {
+    #[inline]
+    pub fn weapon(&self) -> Weapon {
+        self.tanks
+            .get(self.current_tank)
+            .map_or_else(Weapon::default, |tank| tank.weapon)
+    }
+
+    /// Returns player of current tank.
+    fn current_player<'a>(&self, players: &'a mut [Player]) -> Option<&'a mut Player> {
+        players.get_mut(self.player_number() as usize - 1)
+    }
+
+    /// Selects weapon of current tank if its player has it.
+    pub fn select_weapon(&mut self, weapon: Weapon, players: &[Player]) -> bool {
+        let index = self.player_number() as usize - 1;
+        let has_weapon = players.get(index).map_or(weapon.is_unlimited(), |p| {
+            p.inventory.has(Goods::Weapon(weapon))
+        });
+        if !has_weapon {
+            return false;
+        }
+        if let Some(tank) = self.tanks.get_mut(self.current_tank) {
+            tank.weapon = weapon;
+            return true;
+        }
+        false
+    }
+
+    /// Selects next weapon available for player of current tank.
+    pub fn select_next_weapon(&mut self, players: &[Player]) {
+        let current = self.weapon();
+        let start = Weapon::ALL.iter().position(|&w| w == current).unwrap_or(0);
+        for i in 1..=Weapon::ALL.len() {
+            let weapon = Weapon::ALL[(start + i) % Weapon::ALL.len()];
+            if self.select_weapon(weapon, players) {
+                return;
+            }
+        }
+    }
+
+    /// Uses item of player of current tank during aiming.
+    pub fn use_item(&mut self, item: Item, players: &mut [Player]) -> bool {
+        if let GameState::Aiming = self.state {
+            let used = match self.current_player(players) {
+                Some(player) => player.inventory.take(Goods::Item(item)),
+                None => false,
+            };
+            if used {
+                self.apply_item(item);
+            }
+            return used;
+        }
+        false
+    }
+
+    fn apply_item(&mut self, item: Item) {
+        if let Some(tank) = self.tanks.get_mut(self.current_tank) {
+            item.apply(tank);
+            self.replay.item_uses.push(ItemUse {
+                tick: self.clock.ticks(),
+                item,
+            });
+        }
+    }
+
+    /// Fires selected weapon of current tank and takes it
+    /// from inventory of its player.
+    pub fn shoot(&mut self, players: &mut [Player]) {
+        if let GameState::Aiming = self.state {
+            let weapon = self.weapon();
+            let mut remains = true;
+            if let Some(player) = self.current_player(players) {
+                let goods = Goods::Weapon(weapon);
+                if !player.inventory.take(goods) {
+                    return;
+                }
+                remains = player.inventory.has(goods);
+            }
+            self.fire();
+            if !remains {
+                if let Some(tank) = self.tanks.get_mut(self.current_tank) {
+                    tank.weapon = Weapon::default();
+                }
+            }
+        }
+    }
+
+    fn fire(&mut self) {
         if let GameState::Aiming = self.state {
             if let Some(tank) = self.tanks.get(self.current_tank) {
-                world.tank_fire_sound.play().unwrap();
-                let acceleration = Vector2::new(self.wind_power, G);
-                self.state = GameState::FlyingOfMissile(tank.shoot(acceleration));
+                self.events.push(RoundEvent::Shot);
+                self.replay.shots.push(Shot {
+                    tick: self.clock.ticks(),
+                    angle: tank.angle,
+                    power: tank.power,
+                    weapon: tank.weapon,
+                });
+                let missile = tank.shoot(self.clock.now(), self.missile_acceleration());
+                self.missile_hits.clear();
+                self.state = GameState::FlyingOfMissiles(vec![missile]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shoot() {
+        let mut players: Vec<Player> = (0..2).map(|_| Player::default()).collect();
+        let mut round =
+            Round::new(800, 600, 2, 1, Terrain::Hills, TerrainParams::default()).unwrap();
+        round.shoot(&mut players);
+        assert_eq!(round.drain_events().count(), 0);
+
+        round.state = GameState::Aiming;
+        round.shoot(&mut players);
+        match round.state {
+            GameState::FlyingOfMissiles(_) => (),
+            _ => panic!("Round state is not FlyingOfMissiles"),
+        }
+        let events: Vec<RoundEvent> = round.drain_events().collect();
+        assert_eq!(events, vec![RoundEvent::Shot]);
+        assert_eq!(round.drain_events().count(), 0);
+    }
+
+    #[test]
+    fn test_weapon_is_taken_from_inventory() {
+        let mut players: Vec<Player> = (0..2).map(|_| Player::default()).collect();
+        let mut round =
+            Round::new(800, 600, 2, 1, Terrain::Hills, TerrainParams::default()).unwrap();
+        round.state = GameState::Aiming;
+        let big_missile = Goods::Weapon(Weapon::BigMissile);
+
+        round.select_next_weapon(&players);
+        assert_eq!(round.weapon(), Weapon::BabyMissile);
+
+        let index = round.player_number() as usize - 1;
+        players[index].inventory.add(big_missile, 1);
+        round.select_next_weapon(&players);
+        assert_eq!(round.weapon(), Weapon::BigMissile);
+
+        round.shoot(&mut players);
+        assert_eq!(players[index].inventory.count(big_missile), 0);
+        assert_eq!(round.weapon(), Weapon::BabyMissile);
+        assert_eq!(round.replay().shots[0].weapon, Weapon::BigMissile);
+    }
+
+    #[test]
+    fn test_same_seed() {
+        let round1 =
+            Round::new(800, 600, 4, 12345, Terrain::Hills, TerrainParams::default()).unwrap();
+        let round2 =
+            Round::new(800, 600, 4, 12345, Terrain::Hills, TerrainParams::default()).unwrap();
+        assert_eq!(round1.seed(), 12345);
+        assert_eq!(round1.landscape.seed(), round2.landscape.seed());
+        assert_eq!(round1.landscape.dx, round2.landscape.dx);
+        assert_eq!(round1.wind_power, round2.wind_power);
+        let players1: Vec<u8> = round1.tanks.iter().map(|t| t.player_number).collect();
+        let players2: Vec<u8> = round2.tanks.iter().map(|t| t.player_number).collect();
+        assert_eq!(players1, players2);
+
+        let round3 =
+            Round::new(800, 600, 4, 54321, Terrain::Hills, TerrainParams::default()).unwrap();
+        assert_ne!(round1.landscape.seed(), round3.landscape.seed());
+
+        for &count in [0, 1, MAX_PLAYERS_COUNT + 1].iter() {
+            assert!(
+                Round::new(800, 600, count, 1, Terrain::Hills, TerrainParams::default()).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_full_turn() {
+        let mut players: Vec<Player> = (0..2).map(|_| Player::default()).collect();
+        let mut round =
+            Round::new(800, 600, 2, 1, Terrain::Hills, TerrainParams::default()).unwrap();
+
+        let mut ticks = 0;
+        while let GameState::TanksThrowing = round.update(&mut players) {
+            ticks += 1;
+            assert!(ticks < 10_000, "Tanks are thrown too long");
+        }
+        match round.state {
+            GameState::Aiming => (),
+            _ => panic!("Round state is not Aiming"),
+        }
+        assert_eq!(round.clock.ticks(), ticks + 1);
+
+        let first_tank = round.current_tank;
+        round.inc_gun_angle(45.);
+        round.shoot(&mut players);
+        loop {
+            match round.update(&mut players) {
+                GameState::Aiming | GameState::Finish => break,
+                _ => {
+                    ticks += 1;
+                    assert!(ticks < 100_000, "Turn is too long");
+                }
             }
         }
+        if let GameState::Aiming = round.state {
+            assert_ne!(round.current_tank, first_tank);
+        }
     }
 }
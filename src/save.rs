@@ -0,0 +1,581 @@
+//! Saving of an unfinished match at start of a turn or between rounds
+//! and restoring it.
+//!
+//! Saved match is stored as a text file with one record per line,
+//! similar to the format of replay:
+//!
+//! ```text
+//! tank_war save 1
+//! match 8972350171023 2 3 random
+//! width 1022
+//! height 766
+//! seed 8972350171023
+//! terrain valleys
+//! terrain_params 4 2 2 0.5 0.5 0.5
+//! wind_params changing 10
+//! map canyon
+//! ticks 1520
+//! iteration 3
+//! wind -4.2
+//! current_tank 1
+//! state aiming
+//! player 200 human 3 name=Alice hue=144 big_missile*3
+//! player 0 hard 2
+//! stats 1 200
+//! stats 0 0
+//! tank 2 100 301 -45 87 64 0 baby_missile
+//! tank 1 881 250 12 40 100 0 big_missile deflector 35
+//! shot 248 -45 87 baby_missile
+//! item 1024 repair_kit
+//! landscape 0*62184 1*12 0*1010 1*14 ...
+//! ```
+//!
+//! A `match` record contains seed of match, number of current round,
+//! count of rounds and style of landscape chosen for match (`random`
+//! if omitted). A `terrain` record contains style of landscape
+//! of current round (`hills` if omitted). A `terrain_params` record
+//! is the same as in replay, parameters of all rounds of match
+//! are the same. A `wind_params` record is the same as in replay too.
+//! A `map` record contains name of custom map used
+//! instead of generated landscape in all rounds of match (there is
+//! no record if landscape is generated).
+//! A `player` record contains money and control of player
+//! (`human` if omitted), optional total score, optional name and hue
+//! of color as `name=value` and `hue=value`, and the inventory
+//! as `goods*count` pairs.
+//! A `stats` record contains kills and money earned by player
+//! in current round.
+//! A `tank` record contains number of player, position of top-left corner
+//! of tank, angle and power of gun, health, "dead" flag, selected weapon
+//! and optional shield with its strength.
+//! `shot` and `item` records are the same as in replay.
+//! A `landscape` record contains run-length encoded materials of pixels
+//! of landscape as a sequence of `value*count` pairs.
+//!
+//! Match saved between rounds has `state next_round` record, number
+//! of the next round in `match` record and only records of match
+//! and players.
+use std::io::{self, BufRead, Write};
+
+use crate::clock::Clock;
+use crate::game_match::Match;
+use crate::landscape::{Terrain, TerrainParams};
+use crate::parse::parse_value;
+use crate::player::{is_valid_name, Control, Player};
+use crate::replay::{ItemUse, Shot};
+use crate::round::{player_hue_offset, GameState, PlayerStats, Round};
+use crate::shield::Shield;
+use crate::shop::Goods;
+use crate::tank::Tank;
+use crate::weapon::Weapon;
+use crate::wind::WindParams;
+use crate::MAX_PLAYERS_COUNT;
+
+const HEADER: &str = "tank_war save 1";
+
+/// Writes state of match and its current round.
+/// Round can be saved only while a player is aiming.
+pub fn write<W: Write>(
+    mut writer: W,
+    game_match: &Match,
+    round: &Round,
+    players: &[Player],
+) -> io::Result<()> {
+    match round.state {
+        GameState::Aiming => (),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Round can be saved only at start of turn",
+            ))
+        }
+    }
+
+    let (width, height) = round.landscape.size();
+    write_match(&mut writer, game_match)?;
+    writeln!(writer, "width {}", width)?;
+    writeln!(writer, "height {}", height)?;
+    writeln!(writer, "seed {}", round.seed())?;
+    writeln!(writer, "terrain {}", round.terrain())?;
+    writeln!(writer, "terrain_params {}", round.terrain_params())?;
+    writeln!(writer, "wind_params {}", round.wind_params())?;
+    if let Some(map) = round.map() {
+        writeln!(writer, "map {}", map)?;
+    }
+    writeln!(writer, "ticks {}", round.clock.ticks())?;
+    writeln!(writer, "iteration {}", round.number_of_iteration)?;
+    writeln!(writer, "wind {}", round.wind_power)?;
+    writeln!(writer, "current_tank {}", round.current_tank)?;
+    writeln!(writer, "state aiming")?;
+    for player in players.iter() {
+        write_player(&mut writer, player)?;
+    }
+    for stats in round.stats.iter() {
+        writeln!(writer, "stats {} {}", stats.kills, stats.money_earned)?;
+    }
+    for tank in round.tanks.iter() {
+        write!(
+            writer,
+            "tank {} {} {} {} {} {} {} {}",
+            tank.player_number,
+            tank.rect.x,
+            tank.rect.y,
+            tank.angle,
+            tank.power,
+            tank.health,
+            tank.dead as u8,
+            tank.weapon
+        )?;
+        if let Some(shield) = tank.shield {
+            write!(writer, " {} {}", shield.kind, shield.strength)?;
+        }
+        writeln!(writer)?;
+    }
+    for shot in round.replay().shots.iter() {
+        writeln!(
+            writer,
+            "shot {} {} {} {}",
+            shot.tick, shot.angle, shot.power, shot.weapon
+        )?;
+    }
+    for item_use in round.replay().item_uses.iter() {
+        writeln!(writer, "item {} {}", item_use.tick, item_use.item)?;
+    }
+
+    write!(writer, "landscape")?;
+    let buffer = round.landscape.buffer();
+    let mut start = 0;
+    while start < buffer.len() {
+        let value = buffer[start];
+        let count = buffer[start..].iter().take_while(|&&v| v == value).count();
+        write!(writer, " {}*{}", value, count)?;
+        start += count;
+    }
+    writeln!(writer)
+}
+
+/// Writes state of match whose next round isn't started yet.
+pub fn write_next_round<W: Write>(
+    mut writer: W,
+    game_match: &Match,
+    players: &[Player],
+) -> io::Result<()> {
+    write_match(&mut writer, game_match)?;
+    writeln!(writer, "terrain_params {}", game_match.terrain_params)?;
+    writeln!(writer, "wind_params {}", game_match.wind_params)?;
+    if let Some(map) = &game_match.map {
+        writeln!(writer, "map {}", map)?;
+    }
+    writeln!(writer, "state next_round")?;
+    for player in players.iter() {
+        write_player(&mut writer, player)?;
+    }
+    Ok(())
+}
+
+/// Writes header and `match` record.
+fn write_match<W: Write>(writer: &mut W, game_match: &Match) -> io::Result<()> {
+    writeln!(writer, "{}", HEADER)?;
+    write!(
+        writer,
+        "match {} {} {}",
+        game_match.seed(),
+        game_match.round_number,
+        game_match.rounds_count
+    )?;
+    match game_match.terrain {
+        Some(terrain) => writeln!(writer, " {}", terrain),
+        None => writeln!(writer, " random"),
+    }
+}
+
+/// Reads saved match, players and current round. There is no round
+/// if match is saved between rounds.
+pub fn read<R: BufRead>(reader: R) -> Result<(Match, Option<Round>, Vec<Player>), String> {
+    let mut lines = reader.lines();
+    match lines.next() {
+        Some(Ok(ref line)) if line.trim() == HEADER => (),
+        _ => return Err("Unknown format of saved match".into()),
+    }
+
+    let mut game_match = Match::default();
+    let mut width: u16 = 0;
+    let mut height: u16 = 0;
+    let mut seed: u64 = 0;
+    let mut terrain = Terrain::default();
+    let mut terrain_params = TerrainParams::default();
+    let mut wind_params = WindParams::default();
+    let mut map: Option<String> = None;
+    let mut ticks: u64 = 0;
+    let mut number_of_iteration: usize = 0;
+    let mut wind_power: f32 = 0.0;
+    let mut current_tank: usize = 0;
+    let mut players: Vec<Player> = Vec::new();
+    let mut stats: Vec<PlayerStats> = Vec::new();
+    let mut tanks: Vec<Tank> = Vec::new();
+    let mut shots: Vec<Shot> = Vec::new();
+    let mut item_uses: Vec<ItemUse> = Vec::new();
+    let mut landscape: Vec<u8> = Vec::new();
+    let mut next_round = false;
+
+    for line in lines {
+        let line = line.map_err(|e| e.to_string())?;
+        let mut values = line.split_whitespace();
+        let key = match values.next() {
+            Some(key) => key,
+            None => continue,
+        };
+        match key {
+            "match" => {
+                let seed = parse_value(&mut values, key)?;
+                let round_number = parse_value(&mut values, key)?;
+                let rounds_count = parse_value(&mut values, key)?;
+                game_match = Match::new(seed, rounds_count);
+                game_match.round_number = round_number;
+                game_match.terrain = match values.next() {
+                    Some("random") | None => None,
+                    Some(terrain) => Some(terrain.parse()?),
+                };
+            }
+            "width" => width = parse_value(&mut values, key)?,
+            "height" => height = parse_value(&mut values, key)?,
+            "seed" => seed = parse_value(&mut values, key)?,
+            "terrain" => terrain = parse_value(&mut values, key)?,
+            "terrain_params" => terrain_params = TerrainParams::parse(&mut values)?,
+            "wind_params" => wind_params = WindParams::parse(&mut values)?,
+            "map" => map = Some(parse_value(&mut values, key)?),
+            "ticks" => ticks = parse_value(&mut values, key)?,
+            "iteration" => number_of_iteration = parse_value(&mut values, key)?,
+            "wind" => wind_power = parse_value(&mut values, key)?,
+            "current_tank" => current_tank = parse_value(&mut values, key)?,
+            "state" => {
+                let state: String = parse_value(&mut values, key)?;
+                match state.as_str() {
+                    "aiming" => next_round = false,
+                    "next_round" => next_round = true,
+                    _ => return Err(format!("Unsupported state of round: {}", state)),
+                }
+            }
+            "player" => players.push(parse_player(values)?),
+            "stats" => stats.push(PlayerStats {
+                kills: parse_value(&mut values, key)?,
+                money_earned: parse_value(&mut values, key)?,
+            }),
+            "tank" => {
+                let player_number: u8 = parse_value(&mut values, key)?;
+                let x: f32 = parse_value(&mut values, key)?;
+                let y: f32 = parse_value(&mut values, key)?;
+                let mut tank = Tank::new(player_number, [x, y], player_hue_offset(player_number));
+                tank.angle = parse_value(&mut values, key)?;
+                tank.power = parse_value(&mut values, key)?;
+                tank.health = parse_value(&mut values, key)?;
+                tank.dead = parse_value::<u8, _>(&mut values, key)? != 0;
+                if let Some(weapon) = values.next() {
+                    tank.weapon = weapon.parse()?;
+                }
+                if let Some(kind) = values.next() {
+                    let mut shield = Shield::new(kind.parse()?);
+                    shield.strength = parse_value(&mut values, key)?;
+                    tank.shield = Some(shield);
+                }
+                tanks.push(tank);
+            }
+            "shot" => shots.push(Shot {
+                tick: parse_value(&mut values, key)?,
+                angle: parse_value(&mut values, key)?,
+                power: parse_value(&mut values, key)?,
+                weapon: match values.next() {
+                    Some(weapon) => weapon.parse()?,
+                    None => Weapon::default(),
+                },
+            }),
+            "item" => item_uses.push(ItemUse {
+                tick: parse_value(&mut values, key)?,
+                item: parse_value(&mut values, key)?,
+            }),
+            "landscape" => {
+                // Size of landscape is written before it
+                let size = usize::from(width) * usize::from(height);
+                for run in values {
+                    let mut parts = run.split('*');
+                    let value: u8 = parse_value(&mut parts, key)?;
+                    let count: usize = parse_value(&mut parts, key)?;
+                    let len = landscape
+                        .len()
+                        .checked_add(count)
+                        .filter(|&len| len <= size)
+                        .ok_or("Landscape of saved match is bigger than its size")?;
+                    landscape.resize(len, value);
+                }
+            }
+            _ => return Err(format!("Unknown record '{}' in saved match", key)),
+        }
+    }
+
+    game_match.terrain_params = terrain_params;
+    game_match.wind_params = wind_params;
+    game_match.map = map.clone();
+    if next_round {
+        if !(2..=MAX_PLAYERS_COUNT as usize).contains(&players.len()) {
+            return Err("Invalid count of players in saved match".into());
+        }
+        return Ok((game_match, None, players));
+    }
+
+    if !(2..=MAX_PLAYERS_COUNT as usize).contains(&tanks.len())
+        || players.len() != tanks.len()
+        || current_tank >= tanks.len()
+    {
+        return Err("Invalid count of tanks in saved match".into());
+    }
+
+    let mut round = Round::new(
+        width,
+        height,
+        tanks.len() as u8,
+        seed,
+        terrain,
+        terrain_params,
+    )?;
+    round.set_wind_params(wind_params);
+    // Landscape is restored from saved pixels, so map itself isn't needed
+    round.replay.map = map;
+    round.landscape.set_buffer(landscape)?;
+    round.clock = Clock::from_ticks(ticks);
+    round.rng = Round::turn_rng(seed, number_of_iteration);
+    round.number_of_iteration = number_of_iteration;
+    round.wind_power = wind_power;
+    round.tanks = tanks;
+    round.current_tank = current_tank;
+    round.state = GameState::Aiming;
+    round.replay.shots = shots;
+    round.replay.item_uses = item_uses;
+    round.set_player_colors(&players);
+    if stats.len() == round.tanks.len() {
+        round.stats = stats;
+    }
+
+    Ok((game_match, Some(round), players))
+}
+
+/// Writes `player` record.
+pub(crate) fn write_player<W: Write>(writer: &mut W, player: &Player) -> io::Result<()> {
+    write!(
+        writer,
+        "player {} {} {}",
+        player.money,
+        player.control.name(),
+        player.score
+    )?;
+    if !player.name.is_empty() {
+        write!(writer, " name={}", player.name)?;
+    }
+    if let Some(hue) = player.hue {
+        write!(writer, " hue={}", hue)?;
+    }
+    for (goods, count) in player.inventory.iter() {
+        write!(writer, " {}*{}", goods, count)?;
+    }
+    writeln!(writer)
+}
+
+/// Parses values of `player` record.
+pub(crate) fn parse_player<'a, I>(values: I) -> Result<Player, String>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let key = "player";
+    let mut values = values.peekable();
+    let mut player = Player::new(Control::Human);
+    player.money = parse_value(&mut values, key)?;
+    if let Some(control) = values.next() {
+        player.control = control.parse()?;
+    }
+    if let Some(score) = values.peek().and_then(|v| v.parse().ok()) {
+        player.score = score;
+        values.next();
+    }
+    for value in values {
+        let mut parts = value.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some("name"), Some(name)) => {
+                if !is_valid_name(name) {
+                    return Err(format!("Invalid name of player: {}", name));
+                }
+                player.name = name.to_string();
+            }
+            (Some("hue"), Some(hue)) => {
+                player.hue = Some(parse_value(&mut Some(hue).into_iter(), key)?)
+            }
+            _ => {
+                let mut parts = value.split('*');
+                let goods: Goods = parse_value(&mut parts, key)?;
+                let count: u32 = parse_value(&mut parts, key)?;
+                player.inventory.add(goods, count);
+            }
+        }
+    }
+    Ok(player)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::Item;
+    use crate::shield::ShieldKind;
+    use crate::wind::WindMode;
+
+    /// Updates round until start of next turn.
+    fn play_turn(round: &mut Round, players: &mut [Player]) {
+        let turn = round.number_of_iteration;
+        while round.number_of_iteration == turn {
+            if let GameState::Finish = round.update(players) {
+                return;
+            }
+        }
+    }
+
+    #[test]
+    fn test_save_and_restore() {
+        let mut players: Vec<Player> = (0..3).map(|_| Player::default()).collect();
+        let params = TerrainParams {
+            octaves: 5,
+            ..TerrainParams::default()
+        };
+        let wind_params = WindParams {
+            mode: WindMode::Changing,
+            max_power: 12.,
+        };
+        let mut round = Round::new(800, 600, 3, 7, Terrain::Plains, params).unwrap();
+        round.set_wind_params(wind_params);
+        play_turn(&mut round, &mut players);
+        round.inc_gun_angle(30.);
+        round.shoot(&mut players);
+        play_turn(&mut round, &mut players);
+        players[1].money = 200;
+        let index = round.player_number() as usize - 1;
+        players[index]
+            .inventory
+            .add(Goods::Weapon(Weapon::BigMissile), 2);
+        players[index]
+            .inventory
+            .add(Goods::Item(Item::RepairKit), 1);
+        assert!(round.use_item(Item::RepairKit, &mut players));
+        let deflector = Item::Shield(ShieldKind::Deflector);
+        players[index].inventory.add(Goods::Item(deflector), 1);
+        assert!(round.use_item(deflector, &mut players));
+        assert!(round.select_weapon(Weapon::BigMissile, &players));
+
+        players[2].score = 3;
+        players[0].name = "Alice".into();
+        players[0].hue = Some(144.);
+        round.stats[0].kills = 1;
+        let mut game_match = Match::new(7, 3);
+        game_match.terrain = Some(Terrain::Plains);
+        game_match.terrain_params = params;
+        game_match.wind_params = wind_params;
+        game_match.next_round();
+
+        let mut buf: Vec<u8> = Vec::new();
+        write(&mut buf, &game_match, &round, &players).unwrap();
+        let (restored_match, restored_round, mut restored_players) = read(&buf[..]).unwrap();
+        let mut restored_round = restored_round.unwrap();
+
+        assert_eq!(restored_match, game_match);
+        assert_eq!(restored_players[1].money, 200);
+        assert_eq!(restored_players[2].score, 3);
+        assert_eq!(restored_players[0].name, "Alice");
+        assert_eq!(restored_players[0].hue, Some(144.));
+        assert_eq!(restored_round.stats, round.stats);
+        assert_eq!(restored_players[index].inventory, players[index].inventory);
+        assert_eq!(restored_round.weapon(), Weapon::BigMissile);
+        let shield = restored_round.tanks[restored_round.current_tank].shield;
+        assert_eq!(shield.map(|s| s.kind), Some(ShieldKind::Deflector));
+        assert_eq!(restored_round.landscape.buffer(), round.landscape.buffer());
+        assert_eq!(restored_round.replay(), round.replay());
+        assert_eq!(restored_round.clock, round.clock);
+        assert_eq!(restored_round.wind_power, round.wind_power);
+
+        // Restored round continues exactly as original one.
+        for (r, p) in [
+            (&mut round, &mut players),
+            (&mut restored_round, &mut restored_players),
+        ]
+        .iter_mut()
+        {
+            r.inc_gun_angle(-50.);
+            r.inc_gun_power(20.);
+            r.shoot(p);
+            play_turn(r, p);
+        }
+        assert_eq!(restored_players[index].inventory, players[index].inventory);
+        assert_eq!(restored_round.landscape.buffer(), round.landscape.buffer());
+        assert_eq!(restored_round.current_tank, round.current_tank);
+        assert_eq!(restored_round.wind_power, round.wind_power);
+        for (tank, restored_tank) in round.tanks.iter().zip(restored_round.tanks.iter()) {
+            assert_eq!(tank.rect, restored_tank.rect);
+            assert_eq!(tank.angle, restored_tank.angle);
+            assert_eq!(tank.health, restored_tank.health);
+        }
+    }
+
+    #[test]
+    fn test_save_between_rounds() {
+        let mut players: Vec<Player> = (0..2).map(|_| Player::default()).collect();
+        players[0].money = 300;
+        players[1].name = "Bob".into();
+        players[1]
+            .inventory
+            .add(Goods::Weapon(Weapon::BigMissile), 2);
+        let mut game_match = Match::new(7, 3);
+        game_match.map = Some("canyon".into());
+        game_match.wind_params.mode = WindMode::Calm;
+        game_match.next_round();
+
+        let mut buf: Vec<u8> = Vec::new();
+        write_next_round(&mut buf, &game_match, &players).unwrap();
+        let (restored_match, round, restored_players) = read(&buf[..]).unwrap();
+        assert_eq!(restored_match, game_match);
+        assert!(round.is_none());
+        assert_eq!(restored_players.len(), 2);
+        assert_eq!(restored_players[0].money, 300);
+        assert_eq!(restored_players[1].name, "Bob");
+        assert_eq!(restored_players[1].inventory, players[1].inventory);
+
+        let mut buf: Vec<u8> = Vec::new();
+        write_next_round(&mut buf, &game_match, &players[..1]).unwrap();
+        assert!(read(&buf[..]).is_err());
+    }
+
+    #[test]
+    fn test_save_only_at_start_of_turn() {
+        let round = Round::new(800, 600, 2, 7, Terrain::Hills, TerrainParams::default()).unwrap();
+        let players: Vec<Player> = (0..2).map(|_| Player::default()).collect();
+        assert!(write(Vec::new(), &Match::default(), &round, &players).is_err());
+    }
+
+    #[test]
+    fn test_invalid_name_of_player() {
+        let mut values = "100 human name=Alice".split_whitespace();
+        assert_eq!(parse_player(&mut values).unwrap().name, "Alice");
+        let mut values = "100 human name=Al*ce".split_whitespace();
+        assert!(parse_player(&mut values).is_err());
+    }
+
+    #[test]
+    fn test_landscape_bigger_than_its_size() {
+        for runs in ["0*101", "0*50 1*18446744073709551615"].iter() {
+            let text = format!("{}\nwidth 10\nheight 10\nlandscape {}\n", HEADER, runs);
+            assert!(read(text.as_bytes()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_too_many_tanks() {
+        let mut text = format!("{}\nwidth 10\nheight 10\n", HEADER);
+        for _ in 0..256 {
+            text.push_str("player 100 human\ntank 1 5 5 0 50 100 0\n");
+        }
+        assert!(read(text.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::cmp::min;
+
+use ggez;
+use ggez::event::KeyCode;
+use ggez::graphics::{self, Color};
+use ggez_goodies::scene;
+
+use crate::input::{self, Button};
+use crate::types::Point2;
+use crate::world::World;
+use crate::{scenes, MAX_PLAYERS_COUNT};
+
+/// Shows keys of buttons and allows to bind other keys to them.
+pub struct ControlsScene {
+    // Set of keys which is edited: common keys or own keys of player.
+    player_number: Option<u8>,
+    current_button: usize,
+    // Scene waits for a key which will be bound to current button.
+    waiting_for_key: bool,
+    // Last pressed key wasn't bound, because a menu button uses it.
+    key_refused: bool,
+}
+
+impl ControlsScene {
+    pub fn new(_ctx: &mut ggez::Context, _world: &mut World) -> Self {
+        ControlsScene {
+            player_number: None,
+            current_button: 0,
+            waiting_for_key: false,
+            key_refused: false,
+        }
+    }
+
+    fn change_set(&mut self, forward: bool) {
+        let index = self.player_number.unwrap_or(0);
+        let count = MAX_PLAYERS_COUNT + 1;
+        let index = if forward {
+            (index + 1) % count
+        } else {
+            (index + count - 1) % count
+        };
+        self.player_number = if index == 0 { None } else { Some(index) };
+    }
+}
+
+impl scene::Scene<World, input::Event> for ControlsScene {
+    fn update(&mut self, world: &mut World, ctx: &mut ggez::Context) -> scenes::Switch {
+        let button = Button::ALL[self.current_button];
+        if self.waiting_for_key {
+            if let Some(key) = world.last_key {
+                if key != KeyCode::Escape {
+                    self.key_refused = !world.controls.set_key(self.player_number, button, key);
+                    if !self.key_refused {
+                        world.save_controls(ctx);
+                    }
+                }
+                self.waiting_for_key = false;
+            }
+            return scene::SceneSwitch::None;
+        }
+
+        if world.input.get_button_pressed(input::Button::Quit) {
+            return scene::SceneSwitch::Pop;
+        }
+
+        if world.input.get_button_pressed(input::Button::Select) {
+            self.waiting_for_key = true;
+            self.key_refused = false;
+        } else if world.last_key == Some(KeyCode::Delete) {
+            world.controls.reset_keys(self.player_number);
+            world.save_controls(ctx);
+        } else if let (Some(KeyCode::Back), Some(number)) = (world.last_key, self.player_number) {
+            world.controls.reset_key(number, button);
+            world.save_controls(ctx);
+        }
+
+        scene::SceneSwitch::None
+    }
+
+    fn draw(&mut self, world: &mut World, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.3, 0.3, 1.0].into());
+
+        let title = match self.player_number {
+            Some(number) => format!("Controls - own keys of player {}", number),
+            None => "Controls - common keys".to_string(),
+        };
+        let text = graphics::Text::new((title, world.font, 40.0));
+        graphics::draw(ctx, &text, (Point2::new(200.0, 100.0),))?;
+
+        for (index, &button) in Button::ALL.iter().enumerate() {
+            let key = match world.controls.key(self.player_number, button) {
+                Some(key) => input::key_name(key),
+                None => {
+                    // Own key of player is not set, common one is used
+                    let common_key = world.controls.key(None, button);
+                    let name = common_key.map_or_else(|| "-".to_string(), input::key_name);
+                    format!("{} (common)", name)
+                }
+            };
+            let line = if index == self.current_button && self.waiting_for_key {
+                format!("{}: press a key...", button.title())
+            } else {
+                format!("{}: {}", button.title(), key)
+            };
+            let text_color = if index == self.current_button {
+                Color::new(1., 0., 0., 1.)
+            } else {
+                Color::new(1., 1., 1., 1.)
+            };
+            let text_fragment = graphics::TextFragment::new(line)
+                .font(world.font)
+                .scale(graphics::Scale::uniform(30.0))
+                .color(text_color);
+            let text = graphics::Text::new(text_fragment);
+            let y = index as f32 * 40.0 + 180.0;
+            graphics::draw(ctx, &text, (Point2::new(200.0, y),))?;
+        }
+
+        let hint = if self.key_refused {
+            "The key is used by another button of menus"
+        } else if self.player_number.is_some() {
+            "Enter - change key, Backspace - use common key, Delete - use all common keys, \
+             Left/Right - other player"
+        } else {
+            "Enter - change key, Delete - restore default keys, Left/Right - own keys of players"
+        };
+        let text = graphics::Text::new((hint, world.font, 20.0));
+        let y = Button::ALL.len() as f32 * 40.0 + 220.0;
+        graphics::draw(ctx, &text, (Point2::new(200.0, y),))?;
+
+        Ok(())
+    }
+
+    fn input(&mut self, _world: &mut World, ev: input::Event, started: bool) {
+        if !started || self.waiting_for_key {
+            return;
+        }
+        if let input::Event::Button(button) = ev {
+            match button {
+                Button::Up => self.current_button = self.current_button.saturating_sub(1),
+                Button::Down => {
+                    self.current_button = min(self.current_button + 1, Button::ALL.len() - 1)
+                }
+                Button::Left => self.change_set(false),
+                Button::Right => self.change_set(true),
+                _ => (),
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "ControlsScene"
+    }
+}
@@ -1,62 +1,668 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cgmath::MetricSpace;
 use ggez;
-use ggez::{graphics, timer, GameError};
+use ggez::audio::SoundSource;
+use ggez::event::MouseButton;
+use ggez::graphics::Drawable;
+use ggez::input::mouse;
+use ggez::{filesystem, graphics, timer, GameError};
 use ggez_goodies::scene;
 
-use crate::round::{GameState, Round};
+use crate::ai::Bot;
+use crate::game_match::{self, Match};
+use crate::item::Item;
+use crate::map;
+use crate::net::{self, Command};
+use crate::player::Control;
+use crate::replay::Replay;
+use crate::round::{GameState, Round, RoundEvent};
+use crate::save;
 use crate::shaders::GlowParams;
-use crate::types::Point2;
+use crate::shield::ShieldKind;
+use crate::shop::Goods;
+use crate::tank::Tank;
+use crate::texture::Textures;
+use crate::types::{Point2, Vector2};
+use crate::wind::WindParams;
 use crate::world::World;
-use crate::{input, scenes, utils};
+use crate::{clock, input, scenes, utils};
+
+/// Distance of dragging by mouse in pixels which changes power of gun by 1.
+const DRAG_DISTANCE_PER_POWER: f32 = 3.;
+/// Step of changing of angle and power of gun by keys.
+const AIM_STEP: f32 = 1.;
+/// Step of changing of angle and power while fine aim modifier is held.
+const FINE_AIM_STEP: f32 = 0.1;
+/// Number of ticks of holding of a key before continuous aiming starts.
+const HOLD_DELAY_TICKS: u32 = 15;
+/// Speed of continuous aiming in steps per second.
+const MIN_AIM_SPEED: f32 = 10.;
+const MAX_AIM_SPEED: f32 = 60.;
+/// Increase of speed of continuous aiming per second of holding a key.
+const AIM_ACCELERATION: f32 = 40.;
+/// Count of specks of dust which drift with wind.
+const WIND_PARTICLES_COUNT: usize = 60;
+/// Distance in pixels which speck of dust drifts per tick
+/// per unit of power of wind.
+const WIND_PARTICLE_SPEED: f32 = 0.4;
+/// Length of wind arrow in status line for max power of wind.
+const WIND_ARROW_LENGTH: f32 = 80.;
+
+/// Returns path to file with automatically saved unfinished match.
+pub fn saved_match_path(ctx: &ggez::Context) -> PathBuf {
+    filesystem::user_data_dir(ctx).join("saved_match")
+}
+
+/// Writes saved match with given function. It is written to temporary
+/// file at first to keep previous save if something goes wrong.
+fn write_saved_match<F>(ctx: &ggez::Context, write: F)
+where
+    F: FnOnce(io::BufWriter<fs::File>) -> io::Result<()>,
+{
+    let path = saved_match_path(ctx);
+    let tmp_path = path.with_extension("tmp");
+    let result = fs::create_dir_all(filesystem::user_data_dir(ctx))
+        .and_then(|_| fs::File::create(&tmp_path))
+        .and_then(|file| write(io::BufWriter::new(file)))
+        .and_then(|_| fs::rename(&tmp_path, &path));
+    if let Err(error) = result {
+        eprintln!("Can't save match into {}: {}", path.display(), error);
+    }
+}
+
+const PLAYBACK_SPEEDS: [f32; 6] = [0.25, 0.5, 1., 2., 4., 8.];
+const NORMAL_PLAYBACK_SPEED: usize = 2;
+
+/// Continuous aiming while direction keys are held.
+#[derive(Default)]
+struct HeldAim {
+    ticks: u32,
+    // Changes of angle and power which are less than a step and
+    // were not applied yet.
+    angle: f32,
+    power: f32,
+}
+
+/// Speck of dust which shows direction and strength of wind.
+struct WindParticle {
+    pos: Point2,
+    // Multiplier of speed which makes specks look nearer or farther.
+    speed: f32,
+}
+
+/// State of playing of a replay.
+struct Playback {
+    replay: Replay,
+    paused: bool,
+    speed: usize,
+    // Fractional part of ticks which were not played yet.
+    ticks_remainder: f32,
+    pause_on_next_turn: bool,
+}
+
+impl Playback {
+    /// Returns count of game clock ticks which should be played during one update.
+    fn ticks_to_play(&mut self) -> usize {
+        if self.paused {
+            return 0;
+        }
+        let ticks = self.ticks_remainder + PLAYBACK_SPEEDS[self.speed];
+        self.ticks_remainder = ticks.fract();
+        ticks as usize
+    }
+
+    fn input(&mut self, button: input::Button) {
+        match button {
+            input::Button::Fire => self.paused = !self.paused,
+            input::Button::Up => self.speed = (self.speed + 1).min(PLAYBACK_SPEEDS.len() - 1),
+            input::Button::Down => self.speed = self.speed.saturating_sub(1),
+            input::Button::Right => {
+                self.paused = false;
+                self.pause_on_next_turn = true;
+            }
+            _ => (),
+        }
+    }
+
+    fn status(&self) -> String {
+        if self.paused {
+            "Replay: paused".into()
+        } else {
+            format!("Replay: x{}", PLAYBACK_SPEEDS[self.speed])
+        }
+    }
+}
 
 pub struct GamePlayScene {
     game_round: Round,
-    landscape_image: Option<graphics::Image>,
+    playback: Option<Playback>,
+    session: Option<net::Session>,
+    bot: Bot,
+    selected_item: Item,
+    held_aim: HeldAim,
+    // Image of landscape, only changed regions of it are redrawn.
+    landscape_canvas: Option<graphics::Canvas>,
     glow_params: GlowParams,
     glow_canvas: graphics::Canvas,
+    wind_particles: Vec<WindParticle>,
 }
 
 impl GamePlayScene {
-    pub fn new(ctx: &mut ggez::Context, world: &mut World) -> ggez::GameResult<Self> {
+    pub fn new(ctx: &mut ggez::Context, world: &mut World, seed: u64) -> ggez::GameResult<Self> {
         let (width, height) = utils::screen_size(ctx);
-        let game_round = Round::new(width as u16 - 2, height as u16 - 2, world.players_count())
+        let mut game_round = Round::new(
+            width as u16 - 2,
+            height as u16 - 2,
+            world.players_count(),
+            seed,
+            world.game_match.round_terrain(),
+            world.game_match.terrain_params,
+        )
+        .map_err(GameError::ResourceLoadError)?;
+        game_round.set_wind_params(world.game_match.wind_params);
+        let map = map::find(&world.maps, world.game_match.map.as_deref())
+            .map_err(GameError::ResourceLoadError)?;
+        if let Some(map) = map {
+            game_round.load_map(map);
+        }
+        game_round.set_player_colors(&world.players);
+
+        Self::with_round(ctx, game_round, None)
+    }
+
+    /// Creates scene which plays networked round.
+    pub fn from_session(
+        ctx: &mut ggez::Context,
+        world: &mut World,
+        session: net::Session,
+        setup: net::Setup,
+    ) -> ggez::GameResult<Self> {
+        let mut game_round = setup
+            .create_round(&world.maps)
             .map_err(GameError::ResourceLoadError)?;
+        game_round.set_player_colors(&setup.players);
+        world.players = setup.players;
+        world.game_match = Match::new(setup.seed, 1);
+        world.game_match.terrain = Some(setup.terrain);
+        world.game_match.terrain_params = setup.terrain_params;
+        world.game_match.wind_params = setup.wind_params;
+        world.game_match.map = setup.map;
 
+        let mut scene = Self::with_round(ctx, game_round, None)?;
+        scene.session = Some(session);
+        Ok(scene)
+    }
+
+    /// Creates scene which plays given replay.
+    pub fn from_replay(
+        ctx: &mut ggez::Context,
+        world: &mut World,
+        replay: Replay,
+    ) -> ggez::GameResult<Self> {
+        world.create_players_count(replay.players_count);
+        let game_round = replay
+            .create_round(&world.maps)
+            .map_err(GameError::ResourceLoadError)?;
+        let playback = Playback {
+            replay,
+            paused: false,
+            speed: NORMAL_PLAYBACK_SPEED,
+            ticks_remainder: 0.0,
+            pause_on_next_turn: false,
+        };
+
+        Self::with_round(ctx, game_round, Some(playback))
+    }
+
+    /// Creates scene which continues saved unfinished match: its round
+    /// or shop before its next round.
+    pub fn from_saved_match(
+        ctx: &mut ggez::Context,
+        world: &mut World,
+    ) -> ggez::GameResult<Box<dyn scene::Scene<World, input::Event>>> {
+        let file = fs::File::open(saved_match_path(ctx))?;
+        let (game_match, game_round, players) =
+            save::read(io::BufReader::new(file)).map_err(GameError::ResourceLoadError)?;
+        world.game_match = game_match;
+        world.players = players;
+
+        match game_round {
+            Some(game_round) => Ok(Box::new(Self::with_round(ctx, game_round, None)?)),
+            None => {
+                let seed = world.game_match.round_seed();
+                Ok(Box::new(scenes::ShopScene::new(ctx, world, seed)))
+            }
+        }
+    }
+
+    fn with_round(
+        ctx: &mut ggez::Context,
+        game_round: Round,
+        playback: Option<Playback>,
+    ) -> ggez::GameResult<Self> {
+        let (width, height) = (game_round.width, game_round.height);
+        let wind_particles = (0..WIND_PARTICLES_COUNT)
+            .map(|_| WindParticle {
+                pos: Point2::new(
+                    rand::random::<f32>() * width,
+                    rand::random::<f32>() * height,
+                ),
+                speed: 0.5 + rand::random::<f32>(),
+            })
+            .collect();
         let state = Self {
             game_round,
-            landscape_image: None,
+            playback,
+            session: None,
+            bot: Bot::new(),
+            selected_item: Item::RepairKit,
+            held_aim: HeldAim::default(),
+            landscape_canvas: None,
             glow_params: GlowParams {
                 glow_color: [1., 1., 1.],
                 glow_intensity: 1.0,
             },
             glow_canvas: graphics::Canvas::with_window_size(ctx)?,
+            wind_particles,
         };
 
         Ok(state)
     }
 
-    fn update_landscape_image(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
-        if self.landscape_image.is_none() || self.game_round.landscape.changed() {
-            self.landscape_image = Some(self.game_round.landscape.create_image(ctx)?);
+    /// Updates round and returns `true` if it has finished.
+    fn update_round(&mut self, world: &mut World) -> io::Result<bool> {
+        if let Some(ref mut session) = self.session {
+            session.update(&mut self.game_round, &mut world.players)?;
+            if let GameState::Finish = self.game_round.state {
+                return Ok(true);
+            }
+            return Ok(false);
+        }
+
+        let ticks = match self.playback {
+            Some(ref mut playback) => playback.ticks_to_play(),
+            None => 1,
+        };
+
+        for _ in 0..ticks {
+            let turn = self.game_round.number_of_iteration;
+            if let Some(ref playback) = self.playback {
+                self.game_round.play_replay(&playback.replay);
+            }
+            if let GameState::Finish = self.game_round.update(&mut world.players) {
+                return Ok(true);
+            }
+
+            let next_turn = self.game_round.number_of_iteration != turn;
+            if let Some(ref mut playback) = self.playback {
+                if next_turn && playback.pause_on_next_turn {
+                    playback.paused = true;
+                    playback.pause_on_next_turn = false;
+                    break;
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns control of player whose tank is current.
+    /// Tanks of remote players in networked round are controlled
+    /// by other instances of game.
+    fn current_control(&self, world: &World) -> Option<Control> {
+        if let Some(ref session) = self.session {
+            if !session.is_local_turn(&self.game_round) {
+                return None;
+            }
+        }
+        let index = self.game_round.player_number() as usize - 1;
+        let control = world
+            .players
+            .get(index)
+            .map_or(Control::Human, |player| player.control);
+        Some(control)
+    }
+
+    /// Applies command of local player to round or sends it to host
+    /// of networked round.
+    fn run_command(&mut self, world: &mut World, command: Command) {
+        match self.session {
+            Some(ref mut session) => {
+                if let Err(error) = session.send_command(command) {
+                    eprintln!("Can't send command to host: {}", error);
+                }
+            }
+            None => command.apply(&mut self.game_round, &mut world.players),
+        }
+    }
+
+    /// Aims gun of current tank by mouse. Angle of gun follows cursor,
+    /// power is set by distance of cursor dragged from tank or by wheel.
+    fn aim_by_mouse(&mut self, world: &mut World, ctx: &mut ggez::Context) {
+        match self.game_round.state {
+            GameState::Aiming => (),
+            _ => return,
+        }
+        let tank = match self.game_round.tanks.get(self.game_round.current_tank) {
+            Some(tank) => tank,
+            None => return,
+        };
+        // Playable space is drawn with offset of 1 pixel
+        let tank_center = tank.center() + Vector2::new(1., 1.);
+
+        if let (Some(position), true) = (world.mouse.position, world.mouse.moved) {
+            let offset = position - tank_center;
+            let angle = offset.x.atan2(-offset.y).to_degrees().round();
+            let angle = angle.max(-90.).min(90.);
+            if (angle - self.game_round.gun_angle()).abs() > f32::EPSILON {
+                self.run_command(world, Command::SetAngle(angle));
+            }
+            if mouse::button_pressed(ctx, MouseButton::Left) {
+                let power = (position.distance(tank_center) / DRAG_DISTANCE_PER_POWER).round();
+                let power = power.min(100.);
+                if (power - self.game_round.gun_power()).abs() > f32::EPSILON {
+                    self.run_command(world, Command::SetPower(power));
+                }
+            }
+        }
+
+        if world.mouse.wheel != 0. {
+            self.run_command(world, Command::Power(world.mouse.wheel));
+        }
+    }
+
+    fn aim_step(world: &World) -> f32 {
+        if world.input.get_button_down(input::Button::FineAim) {
+            FINE_AIM_STEP
+        } else {
+            AIM_STEP
+        }
+    }
+
+    /// Changes angle and power of gun while direction keys or gamepad
+    /// stick are held. Speed of changing grows the longer they are held
+    /// and depends on deflection of stick.
+    fn aim_by_axes(&mut self, world: &mut World) {
+        let held = world.input.get_axis_raw(input::Axis::Horizontal).abs() > f32::EPSILON
+            || world.input.get_axis_raw(input::Axis::Vertical).abs() > f32::EPSILON;
+        match self.game_round.state {
+            GameState::Aiming if held => (),
+            _ => {
+                self.held_aim = HeldAim::default();
+                return;
+            }
+        }
+        self.held_aim.ticks += 1;
+        if self.held_aim.ticks <= HOLD_DELAY_TICKS {
+            // Single press is handled as one step
+            return;
+        }
+
+        let ticks_per_second = clock::TICKS_PER_SECOND as f32;
+        let held_time = (self.held_aim.ticks - HOLD_DELAY_TICKS) as f32 / ticks_per_second;
+        let speed = (MIN_AIM_SPEED + AIM_ACCELERATION * held_time).min(MAX_AIM_SPEED);
+        let step = Self::aim_step(world);
+        let change = speed * step / ticks_per_second;
+        let axis_change = |axis| world.input.get_axis(axis) * world.stick.speed(axis) * change;
+        self.held_aim.angle += axis_change(input::Axis::Horizontal);
+        self.held_aim.power += axis_change(input::Axis::Vertical);
+
+        let angle_delta = (self.held_aim.angle / step).trunc() * step;
+        if angle_delta.abs() > f32::EPSILON {
+            self.held_aim.angle -= angle_delta;
+            self.run_command(world, Command::Angle(angle_delta));
+        }
+        let power_delta = (self.held_aim.power / step).trunc() * step;
+        if power_delta.abs() > f32::EPSILON {
+            self.held_aim.power -= power_delta;
+            self.run_command(world, Command::Power(power_delta));
+        }
+    }
+
+    /// Saves unfinished match if current player is aiming.
+    fn save_match(&self, world: &World, ctx: &mut ggez::Context) {
+        if self.playback.is_some() || self.session.is_some() {
+            return;
+        }
+        if let GameState::Aiming = self.game_round.state {
+            write_saved_match(ctx, |file| {
+                save::write(file, &world.game_match, &self.game_round, &world.players)
+            });
+        }
+    }
+
+    /// Saves match whose round is finished, so it continues from
+    /// the next round.
+    fn save_next_round(&self, world: &World, ctx: &mut ggez::Context) {
+        if self.playback.is_some() || self.session.is_some() {
+            return;
+        }
+        let mut game_match = world.game_match.clone();
+        game_match.next_round();
+        write_saved_match(ctx, |file| {
+            save::write_next_round(file, &game_match, &world.players)
+        });
+    }
+
+    fn remove_saved_match(&self, ctx: &mut ggez::Context) {
+        if self.playback.is_some() || self.session.is_some() {
+            return;
+        }
+        let path = saved_match_path(ctx);
+        if path.exists() {
+            if let Err(error) = fs::remove_file(&path) {
+                eprintln!("Can't remove saved match {}: {}", path.display(), error);
+            }
+        }
+    }
+
+    /// Saves replay of played round into user's data directory.
+    fn save_replay(&self, ctx: &mut ggez::Context) {
+        if self.playback.is_some() {
+            return;
+        }
+        let dir_path = filesystem::user_data_dir(ctx).join("replays");
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let path = dir_path.join(format!("{}.replay", timestamp));
+        let result = fs::create_dir_all(&dir_path)
+            .and_then(|_| fs::File::create(&path))
+            .and_then(|file| self.game_round.replay().write(io::BufWriter::new(file)));
+        if let Err(error) = result {
+            eprintln!("Can't save replay into {}: {}", path.display(), error);
+        }
+    }
+
+    fn update_landscape_image(
+        &mut self,
+        ctx: &mut ggez::Context,
+        textures: &Textures,
+    ) -> ggez::GameResult {
+        let landscape = &mut self.game_round.landscape;
+        if self.landscape_canvas.is_none() {
+            let canvas = graphics::Canvas::with_window_size(ctx)?;
+            graphics::set_canvas(ctx, Some(&canvas));
+            graphics::clear(ctx, [0.0, 0.0, 0.0, 0.0].into());
+            graphics::set_canvas(ctx, None);
+            self.landscape_canvas = Some(canvas);
+            landscape.set_changed();
+        }
+        if !landscape.changed() {
+            return Ok(());
+        }
+
+        let rects = landscape.update_rgba_buffer(textures);
+        graphics::set_canvas(ctx, self.landscape_canvas.as_ref());
+        let result = rects.iter().try_for_each(|rect| {
+            let rgba = landscape.rgba_pixels(rect);
+            let (width, height) = (rect.width() as u16, rect.height() as u16);
+            let mut image = graphics::Image::from_rgba8(ctx, width, height, &rgba)?;
+            // Empty pixels must erase old pixels of canvas
+            image.set_blend_mode(Some(graphics::BlendMode::Replace));
+            let dest = [rect.left as f32, rect.top as f32];
+            graphics::draw(ctx, &image, (dest,))
+        });
+        graphics::set_canvas(ctx, None);
+        result
+    }
+
+    /// Moves specks of dust by current wind.
+    fn update_wind_particles(&mut self) {
+        let wind = self.game_round.current_wind_power();
+        let (width, height) = (self.game_round.width, self.game_round.height);
+        for particle in self.wind_particles.iter_mut() {
+            let pos = &mut particle.pos;
+            pos.x += wind * WIND_PARTICLE_SPEED * particle.speed;
+            // Specks sway up and down while they drift
+            pos.y += (pos.x / 40.).sin() * wind.abs() * 0.05 * particle.speed;
+            pos.x = pos.x.rem_euclid(width);
+            pos.y = pos.y.rem_euclid(height);
         }
-        Ok(())
     }
+
+    fn play_sounds(&mut self, world: &mut World) {
+        for event in self.game_round.drain_events() {
+            match event {
+                RoundEvent::Shot => world.tank_fire_sound.play().unwrap(),
+                RoundEvent::Explosion => world.explosion_sound.play().unwrap(),
+            }
+        }
+    }
+}
+
+fn draw_shield(ctx: &mut ggez::Context, world: &World, tank: &Tank) -> ggez::GameResult {
+    if let Some(shield) = tank.shield {
+        let (r, g, b) = match shield.kind {
+            ShieldKind::Light => (120, 200, 255),
+            ShieldKind::Heavy => (60, 100, 255),
+            ShieldKind::Deflector => (255, 220, 60),
+        };
+        let strength = f32::from(shield.strength) / f32::from(shield.kind.max_strength());
+        let alpha = (80. + 175. * strength) as u8;
+        let params = graphics::DrawParam::new()
+            .dest(tank.center())
+            .color(graphics::Color::from_rgba(r, g, b, alpha));
+        graphics::draw(ctx, &world.shield_mesh, params)?;
+    }
+    Ok(())
+}
+
+/// Draws arrow which points in direction of wind, its length
+/// shows strength of wind.
+fn draw_wind_arrow(ctx: &mut ggez::Context, wind: f32, center: Point2) -> ggez::GameResult {
+    let half_length = (wind / WindParams::MAX_POWER).max(-1.).min(1.) * WIND_ARROW_LENGTH / 2.;
+    let color = graphics::WHITE;
+    let mut builder = graphics::MeshBuilder::new();
+    if half_length.abs() < 1. {
+        builder.circle(graphics::DrawMode::fill(), center, 2., 0.5, color);
+    } else {
+        let direction = half_length.signum();
+        let tail = Point2::new(center.x - half_length, center.y);
+        let head = Point2::new(center.x + half_length, center.y);
+        builder.line(&[tail, head], 2., color)?;
+        builder.polygon(
+            graphics::DrawMode::fill(),
+            &[
+                Point2::new(head.x + direction * 3., head.y),
+                Point2::new(head.x - direction * 6., head.y - 5.),
+                Point2::new(head.x - direction * 6., head.y + 5.),
+            ],
+            color,
+        )?;
+    }
+    let mesh = builder.build(ctx)?;
+    graphics::draw(ctx, &mesh, graphics::DrawParam::default())
+}
+
+fn draw_tank(ctx: &mut ggez::Context, world: &World, tank: &Tank) -> ggez::GameResult {
+    let _lock = graphics::use_shader(ctx, &world.hue_shader);
+    world.hue_shader.send(ctx, tank.hue_offset.into())?;
+
+    let pos = tank.top_left();
+    let gun_params = graphics::DrawParam::new()
+        .dest(pos + Vector2::new(20.5, 20.5))
+        .offset(Point2::new(0.5, 0.5))
+        .rotation(std::f32::consts::PI * tank.angle / 180.0);
+    graphics::draw(ctx, &world.gun_image, gun_params)?;
+    let tank_params = graphics::DrawParam::new().dest(pos);
+    graphics::draw(ctx, &world.tank_image, tank_params)?;
+    Ok(())
 }
 
 impl scene::Scene<World, input::Event> for GamePlayScene {
     fn update(&mut self, world: &mut World, ctx: &mut ggez::Context) -> scenes::Switch {
         if world.input.get_button_pressed(input::Button::Quit) {
+            self.save_match(world, ctx);
+            self.save_replay(ctx);
             return scene::SceneSwitch::Pop;
         }
 
-        if world.input.get_button_pressed(input::Button::Fire) {
-            self.game_round.shoot(world)
+        if self.playback.is_none() {
+            // Human player uses own keys during their turn.
+            if let Some(Control::Human) = self.current_control(world) {
+                world.active_player = Some(self.game_round.player_number());
+            }
+            match self.current_control(world) {
+                Some(Control::Human) if world.input.get_button_pressed(input::Button::Fire) => {
+                    self.run_command(world, Command::Shoot);
+                }
+                Some(Control::Human) => {
+                    self.aim_by_axes(world);
+                    self.aim_by_mouse(world, ctx);
+                }
+                Some(Control::Computer(difficulty)) => {
+                    let mut rng = rand::thread_rng();
+                    self.bot.update(
+                        &mut self.game_round,
+                        &mut world.players,
+                        difficulty,
+                        &mut rng,
+                    );
+                }
+                None => (),
+            }
         }
 
-        if let GameState::Finish = self.game_round.update(world) {
-            return scene::SceneSwitch::Pop;
+        let turn = self.game_round.number_of_iteration;
+        let finished = match self.update_round(world) {
+            Ok(finished) => finished,
+            Err(error) => {
+                eprintln!("Network game is interrupted: {}", error);
+                return scene::SceneSwitch::Pop;
+            }
+        };
+        if finished {
+            if self.playback.is_some() {
+                return scene::SceneSwitch::Pop;
+            }
+            if world.game_match.is_last_round() {
+                self.remove_saved_match(ctx);
+            } else {
+                self.save_next_round(world, ctx);
+            }
+            self.save_replay(ctx);
+            let results = self.game_round.results();
+            world.profiles.record_round(&world.players, &results);
+            if world.game_match.is_last_round() {
+                let winners = game_match::winners(&world.players);
+                world.profiles.record_match(&world.players, &winners);
+            }
+            world.save_profiles(ctx);
+            let results_scene = scenes::ResultsScene::new(results);
+            return scene::SceneSwitch::Replace(Box::new(results_scene));
         }
+        if self.game_round.number_of_iteration != turn {
+            // Autosave at start of every turn
+            self.save_match(world, ctx);
+        }
+        self.play_sounds(world);
+        self.update_wind_particles();
 
-        self.update_landscape_image(ctx)
+        self.update_landscape_image(ctx, &world.landscape_textures)
             .expect("Can't update landscape image");
         self.glow_params.glow_intensity =
             (0.5 + (((timer::ticks(ctx) as f32) / 20.0).cos() / 2.0)) * 0.8;
@@ -74,8 +680,37 @@ impl scene::Scene<World, input::Event> for GamePlayScene {
             graphics::apply_transformations(ctx)?;
 
             // Landscape
-            if let Some(image) = &self.landscape_image {
-                graphics::draw(ctx, image, ([0.0, 0.0],))?;
+            if let Some(canvas) = &self.landscape_canvas {
+                graphics::draw(ctx, canvas, ([0.0, 0.0],))?;
+            }
+
+            // Specks of dust drifting with wind, they are drawn as
+            // streaks which are longer for stronger wind
+            let wind = self.game_round.current_wind_power();
+            let streak = (wind * WIND_PARTICLE_SPEED * 2.).abs().max(1.);
+            let mut builder = graphics::MeshBuilder::new();
+            let mut specks_count = 0;
+            for particle in self.wind_particles.iter() {
+                let pos = particle.pos;
+                if self
+                    .game_round
+                    .landscape
+                    .is_not_empty(pos.x as i32, pos.y as i32)
+                {
+                    continue;
+                }
+                let length = streak * particle.speed;
+                let x = if wind > 0. { pos.x - length } else { pos.x };
+                builder.rectangle(
+                    graphics::DrawMode::fill(),
+                    graphics::Rect::new(x, pos.y, length, 1.0),
+                    graphics::Color::new(1., 1., 1., 0.35),
+                );
+                specks_count += 1;
+            }
+            if specks_count > 0 {
+                let mesh = builder.build(ctx)?;
+                graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
             }
 
             // Current tank with glowing effect
@@ -83,7 +718,7 @@ impl scene::Scene<World, input::Event> for GamePlayScene {
             if !cur_tank.dead {
                 graphics::set_canvas(ctx, Some(&self.glow_canvas));
                 graphics::clear(ctx, [0.0, 0.0, 0.0, 0.0].into());
-                cur_tank.draw(ctx, world)?;
+                draw_tank(ctx, world, cur_tank)?;
                 graphics::set_canvas(ctx, None);
                 {
                     let _lock = graphics::use_shader(ctx, &world.glow_shader);
@@ -95,13 +730,37 @@ impl scene::Scene<World, input::Event> for GamePlayScene {
             // Other tanks
             for (i, tank) in self.game_round.tanks.iter().enumerate() {
                 if !tank.dead && i != self.game_round.current_tank {
-                    tank.draw(ctx, world)?;
+                    draw_tank(ctx, world, tank)?;
                 }
             }
 
+            // Shields
+            for tank in self.game_round.live_tanks() {
+                draw_shield(ctx, world, tank)?;
+            }
+
             // Missile
-            if let GameState::FlyingOfMissile(ref missile) = self.game_round.state {
-                graphics::draw(ctx, &world.missile_mesh, (missile.cur_pos(),))?;
+            if let GameState::FlyingOfMissiles(ref missiles) = self.game_round.state {
+                for missile in missiles.iter() {
+                    graphics::draw(ctx, &world.missile_mesh, (missile.cur_pos(),))?;
+                }
+            }
+
+            // Debris of landscape
+            let debris = self.game_round.landscape.debris();
+            if !debris.is_empty() {
+                let mut builder = graphics::MeshBuilder::new();
+                for d in debris {
+                    let pos = d.cur_pos();
+                    let [r, g, b] = d.material.color();
+                    builder.rectangle(
+                        graphics::DrawMode::fill(),
+                        graphics::Rect::new(pos.x.floor(), pos.y.floor(), 1.0, 1.0),
+                        graphics::Color::from_rgb(r, g, b),
+                    );
+                }
+                let mesh = builder.build(ctx)?;
+                graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
             }
 
             // Explosions
@@ -127,25 +786,28 @@ impl scene::Scene<World, input::Event> for GamePlayScene {
         // Status line
         {
             let angle = self.game_round.gun_angle();
-            let text = graphics::Text::new((format!("Angle: {}", angle), world.font, 20.0));
+            let text = graphics::Text::new((format!("Angle: {:.1}", angle), world.font, 20.0));
             let dest_point = Point2::new(10.0, 10.0);
             graphics::draw(ctx, &text, (dest_point,))?;
 
             let power = self.game_round.gun_power();
-            let text = graphics::Text::new((format!("Power: {}", power), world.font, 20.0));
+            let text = graphics::Text::new((format!("Power: {:.1}", power), world.font, 20.0));
             let dest_point = Point2::new(110.0, 10.0);
             graphics::draw(ctx, &text, (dest_point,))?;
 
-            let text = graphics::Text::new((
-                format!("Wind: {}", self.game_round.wind_power * 10.0),
-                world.font,
-                20.0,
-            ));
+            let wind = self.game_round.current_wind_power();
+            let text =
+                graphics::Text::new((format!("Wind: {:.0}", wind.abs() * 10.0), world.font, 20.0));
             let dest_point = Point2::new(220.0, 10.0);
             graphics::draw(ctx, &text, (dest_point,))?;
+            draw_wind_arrow(ctx, wind, Point2::new(360.0, 20.0))?;
 
             let player = self.game_round.player_number();
-            let text = graphics::Text::new((format!("Player: {}", player), world.font, 20.0));
+            let player_title = world
+                .players
+                .get(player as usize - 1)
+                .map_or_else(|| format!("Player {}", player), |p| p.title(player));
+            let text = graphics::Text::new((player_title, world.font, 20.0));
             let dest_point = Point2::new(440.0, 10.0);
             graphics::draw(ctx, &text, (dest_point,))?;
 
@@ -153,20 +815,94 @@ impl scene::Scene<World, input::Event> for GamePlayScene {
             let text = graphics::Text::new((format!("Health: {}", health), world.font, 20.0));
             let dest_point = Point2::new(540.0, 10.0);
             graphics::draw(ctx, &text, (dest_point,))?;
+
+            let seed = self.game_round.seed();
+            let text = graphics::Text::new((format!("Seed: {}", seed), world.font, 20.0));
+            let dest_point = Point2::new(680.0, 10.0);
+            graphics::draw(ctx, &text, (dest_point,))?;
+
+            let weapon = self.game_round.weapon();
+            let weapon_text = if weapon.is_unlimited() || self.playback.is_some() {
+                format!("Weapon: {}", weapon.title())
+            } else {
+                let index = player as usize - 1;
+                let count = world
+                    .players
+                    .get(index)
+                    .map_or(0, |p| p.inventory.count(Goods::Weapon(weapon)));
+                format!("Weapon: {} ({})", weapon.title(), count)
+            };
+            let text = graphics::Text::new((weapon_text, world.font, 20.0));
+            let dest_point = Point2::new(10.0, 40.0);
+            graphics::draw(ctx, &text, (dest_point,))?;
+
+            if self.playback.is_none() {
+                let index = player as usize - 1;
+                let items_count = world
+                    .players
+                    .get(index)
+                    .map_or(0, |p| p.inventory.count(Goods::Item(self.selected_item)));
+                let text = graphics::Text::new((
+                    format!("Item: {} ({})", self.selected_item.title(), items_count),
+                    world.font,
+                    20.0,
+                ));
+                let dest_point = Point2::new(260.0, 40.0);
+                graphics::draw(ctx, &text, (dest_point,))?;
+
+                let game_match = &world.game_match;
+                let text = graphics::Text::new((
+                    format!(
+                        "Round: {}/{}",
+                        game_match.round_number, game_match.rounds_count
+                    ),
+                    world.font,
+                    20.0,
+                ));
+                let dest_point = Point2::new(540.0, 40.0);
+                graphics::draw(ctx, &text, (dest_point,))?;
+            }
+
+            if let Some(ref playback) = self.playback {
+                let text = graphics::Text::new((playback.status(), world.font, 20.0));
+                let dest_point = Point2::new(10.0, 70.0);
+                graphics::draw(ctx, &text, (dest_point,))?;
+            }
         }
 
         //        graphics::present(ctx)?;
         Ok(())
     }
 
-    fn input(&mut self, _world: &mut World, ev: input::Event, started: bool) {
+    fn input(&mut self, world: &mut World, ev: input::Event, started: bool) {
         if started {
             if let input::Event::Button(button) = ev {
+                if let Some(ref mut playback) = self.playback {
+                    playback.input(button);
+                    return;
+                }
+                if self.current_control(world) != Some(Control::Human) {
+                    return;
+                }
+                // Repeated presses of held keys are replaced by continuous aiming
+                if input::button_axis(button).is_some() && self.held_aim.ticks > 0 {
+                    return;
+                }
+                let step = Self::aim_step(world);
                 match button {
-                    input::Button::Left => self.game_round.inc_gun_angle(-1.0),
-                    input::Button::Right => self.game_round.inc_gun_angle(1.0),
-                    input::Button::Down => self.game_round.inc_gun_power(-1.0),
-                    input::Button::Up => self.game_round.inc_gun_power(1.0),
+                    input::Button::Left => self.run_command(world, Command::Angle(-step)),
+                    input::Button::Right => self.run_command(world, Command::Angle(step)),
+                    input::Button::Down => self.run_command(world, Command::Power(-step)),
+                    input::Button::Up => self.run_command(world, Command::Power(step)),
+                    input::Button::NextWeapon => self.run_command(world, Command::NextWeapon),
+                    input::Button::NextItem => {
+                        let index = Item::ALL.iter().position(|&i| i == self.selected_item);
+                        let next_index = index.map_or(0, |i| (i + 1) % Item::ALL.len());
+                        self.selected_item = Item::ALL[next_index];
+                    }
+                    input::Button::UseItem => {
+                        self.run_command(world, Command::UseItem(self.selected_item));
+                    }
                     _ => (),
                 }
             }
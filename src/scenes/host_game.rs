@@ -0,0 +1,145 @@
+use std::cmp::{max, min};
+
+use ggez;
+use ggez::graphics;
+use ggez_goodies::scene;
+
+use crate::game_match::Match;
+use crate::map::{self, Map};
+use crate::net::{self, Lobby};
+use crate::player::{Control, Player};
+use crate::types::Point2;
+use crate::world::World;
+use crate::{input, scenes, utils, MAX_PLAYERS_COUNT};
+
+/// Hosts networked round and waits until other players join it.
+pub struct HostGameScene {
+    lobby: Option<Lobby>,
+    error: Option<String>,
+}
+
+impl HostGameScene {
+    pub fn new(_ctx: &mut ggez::Context, world: &mut World) -> Self {
+        match Lobby::bind(("0.0.0.0", net::DEFAULT_PORT), world.players_count()) {
+            Ok(lobby) => HostGameScene {
+                lobby: Some(lobby),
+                error: None,
+            },
+            Err(error) => HostGameScene {
+                lobby: None,
+                error: Some(format!("Can't host game: {}", error)),
+            },
+        }
+    }
+
+    fn start(&mut self, ctx: &mut ggez::Context, world: &mut World) -> scenes::Switch {
+        let lobby = match self.lobby.take() {
+            Some(lobby) => lobby,
+            None => return scene::SceneSwitch::None,
+        };
+        let (width, height) = utils::screen_size(ctx);
+        // Round uses style of landscape or map and wind selected for last match
+        let mut game_match = Match::new(rand::random(), 1);
+        game_match.terrain = world.game_match.terrain;
+        let setup = net::Setup {
+            seed: game_match.seed(),
+            width: width as u16 - 2,
+            height: height as u16 - 2,
+            terrain: game_match.round_terrain(),
+            terrain_params: world.game_match.terrain_params,
+            wind_params: world.game_match.wind_params,
+            map: world.game_match.map.clone(),
+            map_checksum: map::find(&world.maps, world.game_match.map.as_deref())
+                .ok()
+                .flatten()
+                .map_or(0, Map::checksum),
+            players: (0..lobby.players_count())
+                .map(|_| Player::new(Control::Human))
+                .collect(),
+        };
+        let result = lobby
+            .start(&setup)
+            .map_err(ggez::GameError::from)
+            .and_then(|session| scenes::GamePlayScene::from_session(ctx, world, session, setup));
+        match result {
+            Ok(game_play_scene) => scene::SceneSwitch::Replace(Box::new(game_play_scene)),
+            Err(error) => {
+                self.error = Some(format!("Can't start game: {}", error));
+                scene::SceneSwitch::None
+            }
+        }
+    }
+}
+
+impl scene::Scene<World, input::Event> for HostGameScene {
+    fn update(&mut self, world: &mut World, ctx: &mut ggez::Context) -> scenes::Switch {
+        if world.input.get_button_pressed(input::Button::Quit) {
+            return scene::SceneSwitch::Pop;
+        }
+
+        if let Some(ref mut lobby) = self.lobby {
+            if let Err(error) = lobby.update() {
+                self.error = Some(format!("Can't accept players: {}", error));
+                self.lobby = None;
+            }
+        }
+
+        let is_full = self.lobby.iter().any(Lobby::is_full);
+        if is_full && world.input.get_button_pressed(input::Button::Select) {
+            return self.start(ctx, world);
+        }
+
+        scene::SceneSwitch::None
+    }
+
+    fn draw(&mut self, world: &mut World, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.3, 0.3, 1.0].into());
+
+        let text = graphics::Text::new(("Host Game", world.font, 40.0));
+        graphics::draw(ctx, &text, (Point2::new(200.0, 100.0),))?;
+
+        let mut lines: Vec<String> = Vec::new();
+        if let Some(ref lobby) = self.lobby {
+            if let Ok(addr) = lobby.local_addr() {
+                lines.push(format!("Port: {}", addr.port()));
+            }
+            lines.push(format!("Count of players: {}", lobby.players_count()));
+            lines.push(format!("Connected: {}", lobby.connected_count()));
+            if lobby.is_full() {
+                lines.push("Enter - start game".into());
+            } else {
+                lines.push("Waiting for players...".into());
+            }
+        }
+        if let Some(ref error) = self.error {
+            lines.push(error.clone());
+        }
+
+        for (index, line) in lines.iter().enumerate() {
+            let text = graphics::Text::new((line.as_str(), world.font, 30.0));
+            let y = index as f32 * 40.0 + 180.0;
+            graphics::draw(ctx, &text, (Point2::new(200.0, y),))?;
+        }
+
+        Ok(())
+    }
+
+    fn input(&mut self, _world: &mut World, ev: input::Event, started: bool) {
+        if started {
+            if let (input::Event::Button(button), Some(lobby)) = (ev, self.lobby.as_mut()) {
+                let count = lobby.players_count();
+                match button {
+                    input::Button::Left => lobby.set_players_count(max(count - 1, 2)),
+                    input::Button::Right => {
+                        lobby.set_players_count(min(count + 1, MAX_PLAYERS_COUNT))
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "HostGameScene"
+    }
+}
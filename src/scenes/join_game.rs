@@ -0,0 +1,108 @@
+use ggez;
+use ggez::graphics;
+use ggez_goodies::scene;
+
+use crate::net::{self, Joining};
+use crate::types::Point2;
+use crate::world::World;
+use crate::{input, scenes};
+
+const BACKSPACE: char = '\u{8}';
+
+/// Connects to host of networked round and waits until it is started.
+pub struct JoinGameScene {
+    address: String,
+    joining: Option<Joining>,
+    status: String,
+}
+
+impl JoinGameScene {
+    pub fn new(_ctx: &mut ggez::Context, _world: &mut World) -> Self {
+        JoinGameScene {
+            address: format!("127.0.0.1:{}", net::DEFAULT_PORT),
+            joining: None,
+            status: "Enter - connect".into(),
+        }
+    }
+
+    fn type_address(&mut self, text: &str) {
+        for c in text.chars() {
+            if c == BACKSPACE {
+                self.address.pop();
+            } else if c.is_ascii_alphanumeric() || c == '.' || c == ':' || c == '-' {
+                self.address.push(c);
+            }
+        }
+    }
+}
+
+impl scene::Scene<World, input::Event> for JoinGameScene {
+    fn update(&mut self, world: &mut World, ctx: &mut ggez::Context) -> scenes::Switch {
+        if world.input.get_button_pressed(input::Button::Quit) {
+            return scene::SceneSwitch::Pop;
+        }
+
+        let joining = match self.joining {
+            Some(ref mut joining) => joining,
+            None => {
+                self.type_address(&world.text_input);
+                if world.input.get_button_pressed(input::Button::Select) {
+                    match Joining::connect(self.address.as_str()) {
+                        Ok(joining) => {
+                            self.joining = Some(joining);
+                            self.status = "Waiting for host to start game...".into();
+                        }
+                        Err(error) => self.status = format!("Can't connect: {}", error),
+                    }
+                }
+                return scene::SceneSwitch::None;
+            }
+        };
+
+        match joining.update() {
+            Ok(Some((session, setup))) => {
+                match scenes::GamePlayScene::from_session(ctx, world, session, setup) {
+                    Ok(game_play_scene) => {
+                        return scene::SceneSwitch::Replace(Box::new(game_play_scene))
+                    }
+                    Err(error) => self.status = format!("Can't start game: {}", error),
+                }
+                self.joining = None;
+            }
+            Ok(None) => (),
+            Err(error) => {
+                self.status = format!("Connection is lost: {}", error);
+                self.joining = None;
+            }
+        }
+
+        scene::SceneSwitch::None
+    }
+
+    fn draw(&mut self, world: &mut World, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.3, 0.3, 1.0].into());
+
+        let text = graphics::Text::new(("Join Game", world.font, 40.0));
+        graphics::draw(ctx, &text, (Point2::new(200.0, 100.0),))?;
+
+        let mut address_text = graphics::Text::new(("Host: ", world.font, 30.0));
+        address_text.add(
+            graphics::TextFragment::new(self.address.as_str())
+                .font(world.font)
+                .scale(graphics::Scale::uniform(30.0))
+                .color(graphics::Color::new(1., 0., 0., 1.)),
+        );
+        graphics::draw(ctx, &address_text, (Point2::new(200.0, 180.0),))?;
+
+        let text = graphics::Text::new((self.status.as_str(), world.font, 30.0));
+        graphics::draw(ctx, &text, (Point2::new(200.0, 230.0),))?;
+
+        Ok(())
+    }
+
+    fn input(&mut self, _world: &mut World, _ev: input::Event, _started: bool) {}
+
+    fn name(&self) -> &str {
+        "JoinGameScene"
+    }
+}
@@ -1,30 +1,56 @@
 use std::cmp::min;
 
 use ggez;
+use ggez::event::MouseButton;
 use ggez::graphics::Color;
 use ggez::{event, graphics};
 use ggez_goodies::scene;
 
-use crate::input;
-use crate::scenes;
+use crate::scenes::game_play;
 use crate::types::Point2;
 use crate::world::World;
+use crate::{input, scenes, utils};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum MenuItem {
     Play,
+    Continue,
+    HostGame,
+    JoinGame,
+    Controls,
+    Settings,
     Quit,
 }
 
-const MENU_ITEMS: [(MenuItem, &str); 2] = [(MenuItem::Play, "Play"), (MenuItem::Quit, "Quit")];
+const MENU_ITEMS: [(MenuItem, &str); 7] = [
+    (MenuItem::Play, "Play"),
+    (MenuItem::Continue, "Continue"),
+    (MenuItem::HostGame, "Host Game"),
+    (MenuItem::JoinGame, "Join Game"),
+    (MenuItem::Controls, "Controls"),
+    (MenuItem::Settings, "Settings"),
+    (MenuItem::Quit, "Quit"),
+];
 
 pub struct MainMenuScene {
     current_item: usize,
+    has_saved_match: bool,
+    // Saved match can appear or disappear while other scenes are
+    // above menu, so it is checked again when menu is active again.
+    check_saved_match: bool,
+    // Bounds of items on screen for mouse pointing, they are updated
+    // on every drawing.
+    item_rects: Vec<graphics::Rect>,
 }
 
 impl MainMenuScene {
-    pub fn new(_ctx: &mut ggez::Context, _world: &mut World) -> Self {
-        MainMenuScene { current_item: 0 }
+    pub fn new(ctx: &mut ggez::Context, _world: &mut World) -> Self {
+        MainMenuScene {
+            current_item: 0,
+            has_saved_match: game_play::saved_match_path(ctx).exists(),
+            check_saved_match: false,
+            item_rects: Vec::new(),
+        }
     }
 }
 
@@ -35,36 +61,88 @@ impl scene::Scene<World, input::Event> for MainMenuScene {
             return scene::SceneSwitch::None;
         }
 
+        if self.check_saved_match {
+            self.has_saved_match = game_play::saved_match_path(ctx).exists();
+            self.check_saved_match = false;
+        }
+
         if world.input.get_button_pressed(input::Button::Up) {
             self.current_item = self.current_item.saturating_sub(1);
         }
         if world.input.get_button_pressed(input::Button::Down) {
             self.current_item = min(self.current_item + 1, MENU_ITEMS.len() - 1)
         }
+        if world.mouse.moved {
+            if let Some(index) = utils::rect_at(&self.item_rects, world.mouse.position) {
+                self.current_item = index;
+            }
+        }
+        let click_position = world.mouse.click_position(MouseButton::Left);
+        let clicked_item = utils::rect_at(&self.item_rects, click_position);
+        if let Some(index) = clicked_item {
+            self.current_item = index;
+        }
 
-        if world.input.get_button_pressed(input::Button::Select) {
-            match MENU_ITEMS[self.current_item].0 {
-                MenuItem::Play => {
-                    let game_play_scene =
-                        Box::new(scenes::SelectCountOfPlayersScene::new(ctx, world));
-                    scene::SceneSwitch::Push(game_play_scene)
-                }
-                MenuItem::Quit => {
-                    event::quit(ctx);
-                    scene::SceneSwitch::None
+        let switch =
+            if clicked_item.is_some() || world.input.get_button_pressed(input::Button::Select) {
+                match MENU_ITEMS[self.current_item].0 {
+                    MenuItem::Play => {
+                        let game_play_scene =
+                            Box::new(scenes::SelectCountOfPlayersScene::new(ctx, world));
+                        scene::SceneSwitch::Push(game_play_scene)
+                    }
+                    MenuItem::Continue if self.has_saved_match => {
+                        match scenes::GamePlayScene::from_saved_match(ctx, world) {
+                            Ok(game_play_scene) => scene::SceneSwitch::Push(game_play_scene),
+                            Err(error) => {
+                                eprintln!("Can't continue saved match: {}", error);
+                                scene::SceneSwitch::None
+                            }
+                        }
+                    }
+                    MenuItem::Continue => scene::SceneSwitch::None,
+                    MenuItem::HostGame => {
+                        let host_game_scene = Box::new(scenes::HostGameScene::new(ctx, world));
+                        scene::SceneSwitch::Push(host_game_scene)
+                    }
+                    MenuItem::JoinGame => {
+                        let join_game_scene = Box::new(scenes::JoinGameScene::new(ctx, world));
+                        scene::SceneSwitch::Push(join_game_scene)
+                    }
+                    MenuItem::Controls => {
+                        let controls_scene = Box::new(scenes::ControlsScene::new(ctx, world));
+                        scene::SceneSwitch::Push(controls_scene)
+                    }
+                    MenuItem::Settings => {
+                        let settings_scene = Box::new(scenes::SettingsScene::new(ctx, world));
+                        scene::SceneSwitch::Push(settings_scene)
+                    }
+                    MenuItem::Quit => {
+                        event::quit(ctx);
+                        scene::SceneSwitch::None
+                    }
                 }
-            }
-        } else {
-            scene::SceneSwitch::None
+            } else {
+                scene::SceneSwitch::None
+            };
+        if let scene::SceneSwitch::Push(_) = switch {
+            self.check_saved_match = true;
         }
+        switch
     }
 
     fn draw(&mut self, world: &mut World, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
         graphics::clear(ctx, [0.0, 0.2, 0.4, 1.0].into());
 
-        for (index, &(_, item_text)) in MENU_ITEMS.iter().enumerate() {
+        // Menu is centered in window of any size
+        let (width, height) = utils::screen_size(ctx);
+        let top = ((height - MENU_ITEMS.len() as f32 * 50.0) / 2.0).round();
+        self.item_rects.clear();
+        for (index, &(item, item_text)) in MENU_ITEMS.iter().enumerate() {
             let text_color = if index == self.current_item {
                 Color::new(1., 0., 0., 1.)
+            } else if item == MenuItem::Continue && !self.has_saved_match {
+                Color::new(0.5, 0.5, 0.5, 1.)
             } else {
                 Color::new(1., 1., 1., 1.)
             };
@@ -74,9 +152,12 @@ impl scene::Scene<World, input::Event> for MainMenuScene {
                 .color(text_color);
 
             let text = graphics::Text::new(text_fragment);
-            let y = index as f32 * 50.0 + 330.0;
-            let dest_point = Point2::new(480.0, y);
+            let y = index as f32 * 50.0 + top;
+            let x = ((width - text.width(ctx) as f32) / 2.0).round();
+            let dest_point = Point2::new(x, y);
             graphics::draw(ctx, &text, (dest_point,))?;
+            self.item_rects
+                .push(utils::text_rect(ctx, &text, dest_point));
         }
 
         Ok(())
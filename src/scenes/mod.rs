@@ -1,15 +1,29 @@
 use ggez_goodies::scene;
 
+pub use controls::ControlsScene;
 pub use game_play::GamePlayScene;
+pub use host_game::HostGameScene;
+pub use join_game::JoinGameScene;
 pub use main_menu::MainMenuScene;
+pub use results::ResultsScene;
 pub use select_count_of_players::SelectCountOfPlayersScene;
+pub use settings::SettingsScene;
+pub use shop::ShopScene;
+pub use winner::WinnerScene;
 
 use crate::input;
 use crate::world::World;
 
+pub mod controls;
 pub mod game_play;
+pub mod host_game;
+pub mod join_game;
 pub mod main_menu;
+pub mod results;
 pub mod select_count_of_players;
+pub mod settings;
+pub mod shop;
+pub mod winner;
 
 // Shortcuts for our scene type.
 pub type Switch = scene::SceneSwitch<World, input::Event>;
@@ -0,0 +1,105 @@
+use ggez;
+use ggez::graphics;
+use ggez_goodies::scene;
+
+use crate::round::RoundResult;
+use crate::types::Point2;
+use crate::world::World;
+use crate::{input, scenes};
+
+/// Shows results of finished round and starts the next one.
+pub struct ResultsScene {
+    results: Vec<RoundResult>,
+}
+
+impl ResultsScene {
+    pub fn new(results: Vec<RoundResult>) -> Self {
+        ResultsScene { results }
+    }
+}
+
+/// Returns horizontal position of column of results table.
+fn column_x(column: usize) -> f32 {
+    // The first column contains names of players and is wider than others.
+    if column == 0 {
+        100.0
+    } else {
+        column as f32 * 140.0 + 240.0
+    }
+}
+
+impl scene::Scene<World, input::Event> for ResultsScene {
+    fn update(&mut self, world: &mut World, ctx: &mut ggez::Context) -> scenes::Switch {
+        if world.input.get_button_pressed(input::Button::Quit) {
+            return scene::SceneSwitch::Pop;
+        }
+
+        if world.input.get_button_pressed(input::Button::Select) {
+            if world.game_match.is_last_round() {
+                return scene::SceneSwitch::Replace(Box::new(scenes::WinnerScene));
+            }
+            world.game_match.next_round();
+            let seed = world.game_match.round_seed();
+            let shop_scene = Box::new(scenes::ShopScene::new(ctx, world, seed));
+            return scene::SceneSwitch::Replace(shop_scene);
+        }
+
+        scene::SceneSwitch::None
+    }
+
+    fn draw(&mut self, world: &mut World, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.3, 0.3, 1.0].into());
+
+        let title = format!(
+            "Round {} of {} finished",
+            world.game_match.round_number, world.game_match.rounds_count
+        );
+        let text = graphics::Text::new((title, world.font, 40.0));
+        graphics::draw(ctx, &text, (Point2::new(200.0, 100.0),))?;
+
+        let header = ["Player", "Survived", "Kills", "Money", "Score", "Total"];
+        for (column, &title) in header.iter().enumerate() {
+            let text = graphics::Text::new((title, world.font, 30.0));
+            graphics::draw(ctx, &text, (Point2::new(column_x(column), 180.0),))?;
+        }
+
+        for (index, result) in self.results.iter().enumerate() {
+            let player = world.players.get(result.player_number as usize - 1);
+            let total = player.map_or(0, |p| p.score);
+            let title = player.map_or_else(
+                || format!("Player {}", result.player_number),
+                |p| p.title(result.player_number),
+            );
+            let columns = [
+                title,
+                if result.survived { "yes" } else { "no" }.to_string(),
+                result.kills.to_string(),
+                format!("+${}", result.money_earned),
+                format!("+{}", result.score),
+                total.to_string(),
+            ];
+            let y = index as f32 * 40.0 + 230.0;
+            for (column, value) in columns.iter().enumerate() {
+                let text = graphics::Text::new((value.as_str(), world.font, 30.0));
+                graphics::draw(ctx, &text, (Point2::new(column_x(column), y),))?;
+            }
+        }
+
+        let hint = if world.game_match.is_last_round() {
+            "Enter - show winner"
+        } else {
+            "Enter - next round"
+        };
+        let text = graphics::Text::new((hint, world.font, 20.0));
+        let y = self.results.len() as f32 * 40.0 + 270.0;
+        graphics::draw(ctx, &text, (Point2::new(200.0, y),))?;
+
+        Ok(())
+    }
+
+    fn input(&mut self, _world: &mut World, _ev: input::Event, _started: bool) {}
+
+    fn name(&self) -> &str {
+        "ResultsScene"
+    }
+}
@@ -1,21 +1,445 @@
 use std::cmp::{max, min};
 
 use ggez;
+use ggez::event::MouseButton;
 use ggez::graphics::{self, Color};
+use ggez::GameError;
 use ggez_goodies::scene;
 
-use crate::types::Point2;
+use crate::ai::Difficulty;
+use crate::game_match::{Match, MAX_ROUNDS_COUNT};
+use crate::landscape::{Terrain, TerrainParams};
+use crate::map;
+use crate::player::{is_valid_name, Control};
+use crate::round::{self, player_hue_offset};
+use crate::types::{HueOffset, Point2};
+use crate::wind::{WindMode, WindParams};
 use crate::world::World;
 use crate::{input, scenes, utils, MAX_PLAYERS_COUNT};
 
+const BACKSPACE: char = '\u{8}';
+const MAX_NAME_LEN: usize = 16;
+/// Step of changing of hue of tank's color in degrees.
+const HUE_STEP: f32 = 30.;
+/// Step of changing of max power of wind.
+const WIND_POWER_STEP: f32 = 1.;
+
+const TERRAIN_PARAMS_COUNT: usize = 6;
+const TERRAIN_PARAM_TITLES: [&str; TERRAIN_PARAMS_COUNT] = [
+    "Octaves",
+    "Frequency",
+    "Lacunarity",
+    "Persistence",
+    "Amplitude",
+    "Base height",
+];
+
+const CONTROLS: [Control; 4] = [
+    Control::Human,
+    Control::Computer(Difficulty::Easy),
+    Control::Computer(Difficulty::Normal),
+    Control::Computer(Difficulty::Hard),
+];
+
+fn control_title(control: Control) -> &'static str {
+    match control {
+        Control::Human => "Human",
+        Control::Computer(Difficulty::Easy) => "CPU Easy",
+        Control::Computer(Difficulty::Normal) => "CPU Normal",
+        Control::Computer(Difficulty::Hard) => "CPU Hard",
+    }
+}
+
+/// Changes parameter of terrain with given index by one step.
+fn change_terrain_param(params: &mut TerrainParams, index: usize, forward: bool) {
+    let change = |value: f64, step: f64, min: f64, max: f64| {
+        let steps = (value / step).round() + if forward { 1. } else { -1. };
+        (steps * step).max(min).min(max)
+    };
+    match index {
+        0 if forward => params.octaves = (params.octaves + 1).min(TerrainParams::MAX_OCTAVES),
+        0 => params.octaves = params.octaves.saturating_sub(1).max(1),
+        1 => params.frequency = change(params.frequency, 0.5, 0.5, TerrainParams::MAX_FREQUENCY),
+        2 => params.lacunarity = change(params.lacunarity, 0.1, 1., TerrainParams::MAX_LACUNARITY),
+        3 => params.persistence = change(params.persistence, 0.05, 0.1, 1.),
+        4 => params.amplitude = change(params.amplitude, 0.05, 0., 1.),
+        5 => params.base_height = change(params.base_height, 0.05, 0., 1.),
+        _ => (),
+    }
+}
+
+/// Changes mode of wind or its max power by one step.
+fn change_wind_param(params: &mut WindParams, power: bool, forward: bool) {
+    if power {
+        let step = if forward {
+            WIND_POWER_STEP
+        } else {
+            -WIND_POWER_STEP
+        };
+        params.max_power = (params.max_power + step).max(0.).min(WindParams::MAX_POWER);
+    } else {
+        let count = WindMode::ALL.len();
+        let index = WindMode::ALL
+            .iter()
+            .position(|&m| m == params.mode)
+            .unwrap_or(0);
+        let index = if forward {
+            (index + 1) % count
+        } else {
+            (index + count - 1) % count
+        };
+        params.mode = WindMode::ALL[index];
+    }
+}
+
+fn terrain_param_value(params: &TerrainParams, index: usize) -> String {
+    match index {
+        0 => params.octaves.to_string(),
+        1 => format!("{:.1}", params.frequency),
+        2 => format!("{:.1}", params.lacunarity),
+        3 => format!("{:.2}", params.persistence),
+        4 => format!("{:.2}", params.amplitude),
+        _ => format!("{:.2}", params.base_height),
+    }
+}
+
+/// Source of landscape of rounds which can be selected for match.
+#[derive(Debug, Clone, PartialEq)]
+enum LandscapeChoice {
+    /// Random style of landscape in every round.
+    Random,
+    Generated(Terrain),
+    /// Custom map with given name.
+    Map(String),
+}
+
+impl LandscapeChoice {
+    fn title(&self) -> String {
+        match self {
+            LandscapeChoice::Random => "Random".to_string(),
+            LandscapeChoice::Generated(terrain) => terrain.title().to_string(),
+            LandscapeChoice::Map(name) => format!("Map {}", name),
+        }
+    }
+}
+
+/// Settings of one player in match.
+struct Seat {
+    control: Control,
+    name: String,
+    hue: f32,
+}
+
 pub struct SelectCountOfPlayersScene {
     count_of_players: u8,
+    rounds_count: u8,
+    seats: Vec<Seat>,
+    // Random landscape, styles of generated landscape and custom maps.
+    landscapes: Vec<LandscapeChoice>,
+    landscape_index: usize,
+    terrain_params: TerrainParams,
+    wind_params: WindParams,
+    // 0 - count of players, 1 - count of rounds,
+    // 2.. - seats of players in order of their numbers, then style
+    // of landscape, parameters of terrain, mode and max power of wind,
+    // seed and start of match.
+    selected_row: usize,
+    // Bounds of rows on screen for mouse pointing, they are updated
+    // on every drawing.
+    row_rects: Vec<(usize, graphics::Rect)>,
+    seed: String,
+    // Randomly generated seed will be replaced by first typed digit.
+    seed_typed: bool,
+    // Landscape of first round drawn behind of menu and seed, style
+    // and parameters of terrain it was generated for.
+    preview: Option<graphics::Image>,
+    preview_key: Option<(u64, Terrain, TerrainParams, Option<String>)>,
 }
 
 impl SelectCountOfPlayersScene {
     pub fn new(_ctx: &mut ggez::Context, world: &mut World) -> Self {
+        let seats = (0..MAX_PLAYERS_COUNT)
+            .map(|i| {
+                let default_hue = f32::from(player_hue_offset(i + 1));
+                match world.players.get(i as usize) {
+                    Some(player) => Seat {
+                        control: player.control,
+                        name: player.name.clone(),
+                        hue: player.hue.unwrap_or(default_hue),
+                    },
+                    None => Seat {
+                        control: Control::Human,
+                        name: String::new(),
+                        hue: default_hue,
+                    },
+                }
+            })
+            .collect();
+
+        let mut landscapes = vec![LandscapeChoice::Random];
+        landscapes.extend(Terrain::ALL.iter().map(|&t| LandscapeChoice::Generated(t)));
+        landscapes.extend(
+            world
+                .maps
+                .iter()
+                .map(|map| LandscapeChoice::Map(map.name().to_string())),
+        );
+        let current = match (&world.game_match.map, world.game_match.terrain) {
+            (Some(name), _) => LandscapeChoice::Map(name.clone()),
+            (None, Some(terrain)) => LandscapeChoice::Generated(terrain),
+            (None, None) => LandscapeChoice::Random,
+        };
+        let landscape_index = landscapes.iter().position(|l| *l == current).unwrap_or(0);
+
         Self {
-            count_of_players: world.players_count(),
+            count_of_players: world.settings.players_count,
+            rounds_count: world.game_match.rounds_count,
+            seats,
+            landscapes,
+            landscape_index,
+            terrain_params: world.game_match.terrain_params,
+            wind_params: world.game_match.wind_params,
+            selected_row: 0,
+            row_rects: Vec::new(),
+            seed: rand::random::<u64>().to_string(),
+            seed_typed: false,
+            preview: None,
+            preview_key: None,
+        }
+    }
+
+    fn terrain_row(&self) -> usize {
+        self.count_of_players as usize + 2
+    }
+
+    fn terrain_param_row(&self, index: usize) -> usize {
+        self.terrain_row() + 1 + index
+    }
+
+    fn wind_row(&self) -> usize {
+        self.terrain_param_row(TERRAIN_PARAMS_COUNT)
+    }
+
+    fn wind_power_row(&self) -> usize {
+        self.wind_row() + 1
+    }
+
+    fn seed_row(&self) -> usize {
+        self.wind_power_row() + 1
+    }
+
+    fn start_row(&self) -> usize {
+        self.seed_row() + 1
+    }
+
+    /// Returns row under given position of mouse.
+    fn row_at(&self, position: Option<Point2>) -> Option<usize> {
+        let position = position?;
+        self.row_rects
+            .iter()
+            .find(|(_, rect)| rect.contains(position))
+            .map(|&(row, _)| row)
+    }
+
+    /// Returns seat of selected row.
+    fn selected_seat(&mut self) -> Option<&mut Seat> {
+        let index = self.selected_seat_index()?;
+        self.seats.get_mut(index)
+    }
+
+    fn selected_seat_index(&self) -> Option<usize> {
+        if self.selected_row >= 2 && self.selected_row < self.terrain_row() {
+            Some(self.selected_row - 2)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if given name is used by another seat of match
+    /// than seat with given index.
+    fn is_name_taken(&self, name: &str, seat_index: usize) -> bool {
+        !name.is_empty()
+            && self.seats[..self.count_of_players as usize]
+                .iter()
+                .enumerate()
+                .any(|(i, seat)| i != seat_index && seat.name == name)
+    }
+
+    /// Returns `true` if several seats of match use the same name.
+    fn has_duplicate_names(&self) -> bool {
+        (0..self.count_of_players as usize)
+            .any(|index| self.is_name_taken(&self.seats[index].name, index))
+    }
+
+    fn type_text(&mut self, text: &str) {
+        if self.selected_row == self.seed_row() {
+            self.type_seed(text);
+        } else if let Some(index) = self.selected_seat_index() {
+            for c in text.chars() {
+                let mut name = self.seats[index].name.clone();
+                if c == BACKSPACE {
+                    name.pop();
+                } else if name.chars().count() < MAX_NAME_LEN {
+                    name.push(c);
+                    if !is_valid_name(&name) {
+                        continue;
+                    }
+                }
+                // Name of other seat is not accepted
+                if !self.is_name_taken(&name, index) {
+                    self.seats[index].name = name;
+                }
+            }
+        }
+    }
+
+    fn type_seed(&mut self, text: &str) {
+        for c in text.chars() {
+            if c == BACKSPACE {
+                self.seed.pop();
+                self.seed_typed = true;
+            } else if c.is_ascii_digit() {
+                if !self.seed_typed {
+                    self.seed.clear();
+                    self.seed_typed = true;
+                }
+                self.seed.push(c);
+                if self.seed.parse::<u64>().is_err() {
+                    // Too big number
+                    self.seed.pop();
+                }
+            }
+        }
+    }
+
+    fn change_selected_value(&mut self, forward: bool) {
+        match self.selected_row {
+            0 => {
+                self.count_of_players = if forward {
+                    min(self.count_of_players + 1, MAX_PLAYERS_COUNT)
+                } else {
+                    max(self.count_of_players - 1, 2)
+                };
+                return;
+            }
+            1 => {
+                self.rounds_count = if forward {
+                    min(self.rounds_count + 1, MAX_ROUNDS_COUNT)
+                } else {
+                    max(self.rounds_count - 1, 1)
+                };
+                return;
+            }
+            row if row == self.terrain_row() => {
+                let count = self.landscapes.len();
+                self.landscape_index = if forward {
+                    (self.landscape_index + 1) % count
+                } else {
+                    (self.landscape_index + count - 1) % count
+                };
+                return;
+            }
+            row if row > self.terrain_row() && row < self.wind_row() => {
+                let index = row - self.terrain_row() - 1;
+                change_terrain_param(&mut self.terrain_params, index, forward);
+                return;
+            }
+            row if row == self.wind_row() || row == self.wind_power_row() => {
+                let power = row == self.wind_power_row();
+                change_wind_param(&mut self.wind_params, power, forward);
+                return;
+            }
+            _ => (),
+        }
+
+        if let Some(seat) = self.selected_seat() {
+            let index = CONTROLS
+                .iter()
+                .position(|&c| c == seat.control)
+                .unwrap_or(0);
+            let index = if forward {
+                (index + 1) % CONTROLS.len()
+            } else {
+                (index + CONTROLS.len() - 1) % CONTROLS.len()
+            };
+            seat.control = CONTROLS[index];
+        }
+    }
+
+    /// Takes name and color of next saved profile for selected seat.
+    /// Profiles already taken by other seats are skipped.
+    fn select_next_profile(&mut self, world: &World) {
+        let index = match self.selected_seat_index() {
+            Some(index) => index,
+            None => return,
+        };
+        let profiles: Vec<_> = world
+            .profiles
+            .iter()
+            .filter(|p| !self.is_name_taken(&p.name, index))
+            .collect();
+        if profiles.is_empty() {
+            return;
+        }
+        let seat = &mut self.seats[index];
+        let next_index = profiles
+            .iter()
+            .position(|p| p.name == seat.name)
+            .map_or(0, |i| (i + 1) % profiles.len());
+        seat.name = profiles[next_index].name.clone();
+        seat.hue = profiles[next_index].hue;
+    }
+
+    /// Returns style of landscape and name of custom map selected
+    /// for match.
+    fn selected_landscape(&self) -> (Option<Terrain>, Option<String>) {
+        match &self.landscapes[self.landscape_index] {
+            LandscapeChoice::Random => (None, None),
+            LandscapeChoice::Generated(terrain) => (Some(*terrain), None),
+            LandscapeChoice::Map(name) => (None, Some(name.clone())),
+        }
+    }
+
+    /// Generates landscape of first round again if seed, style,
+    /// parameters of terrain or map are changed.
+    fn update_preview(&mut self, ctx: &mut ggez::Context, world: &World) -> ggez::GameResult {
+        let seed = match self.seed.parse() {
+            Ok(seed) => seed,
+            Err(_) => return Ok(()),
+        };
+        let (terrain, map_name) = self.selected_landscape();
+        let mut game_match = Match::new(seed, 1);
+        game_match.terrain = terrain;
+        let key = (
+            seed,
+            game_match.round_terrain(),
+            self.terrain_params,
+            map_name,
+        );
+        if self.preview_key.as_ref() == Some(&key) {
+            return Ok(());
+        }
+
+        let (width, height) = utils::screen_size(ctx);
+        let (width, height) = (width as u16 - 2, height as u16 - 2);
+        let mut landscape = round::preview_landscape(width, height, seed, key.1, key.2)
+            .map_err(GameError::ResourceLoadError)?;
+        let map = map::find(&world.maps, key.3.as_deref()).map_err(GameError::ResourceLoadError)?;
+        if let Some(map) = map {
+            landscape.load_map(map);
+        }
+        self.preview_key = Some(key);
+        let rgba = landscape.rgba_buffer(&world.landscape_textures);
+        let image = graphics::Image::from_rgba8(ctx, width, height, rgba)?;
+        self.preview = Some(image);
+        Ok(())
+    }
+
+    fn row_color(&self, row: usize) -> Color {
+        if row == self.selected_row {
+            Color::new(1., 0., 0., 1.)
+        } else {
+            Color::new(1., 1., 1., 1.)
         }
     }
 }
@@ -26,13 +450,57 @@ impl scene::Scene<World, input::Event> for SelectCountOfPlayersScene {
             return scene::SceneSwitch::Pop;
         }
 
-        if world.input.get_button_pressed(input::Button::Select) {
-            world.create_players_count(self.count_of_players);
+        self.type_text(&world.text_input);
+        if let Err(error) = self.update_preview(ctx, world) {
+            eprintln!("Can't generate preview of landscape: {}", error);
+        }
 
-            let game_play_scene = Box::new(
-                scenes::GamePlayScene::new(ctx, world).expect("Can't create GamePlayScene"),
-            );
-            return scene::SceneSwitch::Replace(game_play_scene);
+        if world.mouse.moved {
+            if let Some(row) = self.row_at(world.mouse.position) {
+                self.selected_row = row;
+            }
+        }
+        let mut start = world.input.get_button_pressed(input::Button::Select);
+        if let Some(button) = world.mouse.clicked {
+            if let Some(row) = self.row_at(world.mouse.position) {
+                self.selected_row = row;
+                match button {
+                    _ if row == self.start_row() => start = true,
+                    MouseButton::Left => self.change_selected_value(true),
+                    MouseButton::Right => self.change_selected_value(false),
+                    _ => (),
+                }
+            }
+        }
+
+        // Names can repeat if count of players is increased
+        if start && !self.has_duplicate_names() {
+            let seats = &self.seats[..self.count_of_players as usize];
+            let controls: Vec<Control> = seats.iter().map(|s| s.control).collect();
+            world.create_players(&controls);
+            for (player, seat) in world.players.iter_mut().zip(seats) {
+                if is_valid_name(&seat.name) {
+                    player.name = seat.name.clone();
+                }
+                player.hue = Some(seat.hue);
+            }
+            world.profiles.update_players(&world.players);
+            world.save_profiles(ctx);
+
+            let seed = self.seed.parse().unwrap_or_else(|_| rand::random());
+            world.game_match = Match::new(seed, self.rounds_count);
+            let (terrain, map) = self.selected_landscape();
+            world.game_match.terrain = terrain;
+            world.game_match.map = map;
+            world.game_match.terrain_params = self.terrain_params;
+            world.game_match.wind_params = self.wind_params;
+            world.settings.terrain_params = self.terrain_params;
+            world.settings.wind_params = self.wind_params;
+            world.save_settings(ctx);
+
+            let seed = world.game_match.round_seed();
+            let shop_scene = Box::new(scenes::ShopScene::new(ctx, world, seed));
+            return scene::SceneSwitch::Replace(shop_scene);
         }
 
         scene::SceneSwitch::None
@@ -41,6 +509,13 @@ impl scene::Scene<World, input::Event> for SelectCountOfPlayersScene {
     fn draw(&mut self, world: &mut World, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
         graphics::clear(ctx, [0.0, 0.3, 0.3, 1.0].into());
 
+        if let Some(image) = &self.preview {
+            let param = graphics::DrawParam::new()
+                .dest(Point2::new(1., 1.))
+                .color(Color::new(1., 1., 1., 0.4));
+            graphics::draw(ctx, image, param)?;
+        }
+
         let text_fragment = graphics::TextFragment::new("Select Count Of Players: ")
             .font(world.font)
             .scale(graphics::Scale::uniform(40.0))
@@ -51,29 +526,240 @@ impl scene::Scene<World, input::Event> for SelectCountOfPlayersScene {
             graphics::TextFragment::new(self.count_of_players.to_string())
                 .font(world.font)
                 .scale(graphics::Scale::uniform(40.0))
-                .color(Color::new(1., 0., 0., 1.)),
+                .color(self.row_color(0)),
         );
 
         let (width, height) = utils::screen_size(ctx);
         let text_width = (text.width(ctx) + 5) / 10;
         let x = (width - (text_width * 10) as f32) / 2.;
-        let y = (height - text.height(ctx) as f32) / 2.;
+        let y = ((height - text.height(ctx) as f32) / 2. - 250.).max(10.);
         let dest_point = Point2::new(x.round(), y.round());
         graphics::draw(ctx, &text, (dest_point,))?;
+        self.row_rects.clear();
+        self.row_rects
+            .push((0, utils::text_rect(ctx, &text, dest_point)));
+
+        let mut rounds_text = graphics::Text::new(
+            graphics::TextFragment::new("Rounds: ")
+                .font(world.font)
+                .scale(graphics::Scale::uniform(30.0))
+                .color(Color::new(1., 1., 1., 1.)),
+        );
+        rounds_text.add(
+            graphics::TextFragment::new(self.rounds_count.to_string())
+                .font(world.font)
+                .scale(graphics::Scale::uniform(30.0))
+                .color(self.row_color(1)),
+        );
+        let rounds_point = Point2::new(dest_point.x, dest_point.y + 60.);
+        graphics::draw(ctx, &rounds_text, (rounds_point,))?;
+        self.row_rects
+            .push((1, utils::text_rect(ctx, &rounds_text, rounds_point)));
+
+        let mut row_y = rounds_point.y + 50.;
+        for (i, seat) in self.seats[..self.count_of_players as usize]
+            .iter()
+            .enumerate()
+        {
+            // Tank in color chosen by player
+            {
+                let _lock = graphics::use_shader(ctx, &world.hue_shader);
+                world
+                    .hue_shader
+                    .send(ctx, HueOffset::new(seat.hue).into())?;
+                let tank_point = Point2::new(dest_point.x, row_y - 8.);
+                graphics::draw(ctx, &world.tank_image, (tank_point,))?;
+            }
+
+            let name = if seat.name.is_empty() {
+                format!("Player {}", i + 1)
+            } else {
+                seat.name.clone()
+            };
+            let mut player_text = graphics::Text::new(
+                graphics::TextFragment::new(format!("{}: ", name))
+                    .font(world.font)
+                    .scale(graphics::Scale::uniform(30.0))
+                    .color(self.row_color(i + 2)),
+            );
+            player_text.add(
+                graphics::TextFragment::new(control_title(seat.control))
+                    .font(world.font)
+                    .scale(graphics::Scale::uniform(30.0))
+                    .color(self.row_color(i + 2)),
+            );
+            let player_point = Point2::new(dest_point.x + 50., row_y);
+            graphics::draw(ctx, &player_text, (player_point,))?;
+            self.row_rects
+                .push((i + 2, utils::text_rect(ctx, &player_text, player_point)));
+            row_y += 45.;
+        }
+
+        let terrain_title = self.landscapes[self.landscape_index].title();
+        let mut terrain_text = graphics::Text::new(
+            graphics::TextFragment::new("Terrain: ")
+                .font(world.font)
+                .scale(graphics::Scale::uniform(30.0))
+                .color(Color::new(1., 1., 1., 1.)),
+        );
+        terrain_text.add(
+            graphics::TextFragment::new(terrain_title)
+                .font(world.font)
+                .scale(graphics::Scale::uniform(30.0))
+                .color(self.row_color(self.terrain_row())),
+        );
+        let terrain_x = (width - terrain_text.width(ctx) as f32) / 2.;
+        let terrain_point = Point2::new(terrain_x.round(), row_y + 20.);
+        graphics::draw(ctx, &terrain_text, (terrain_point,))?;
+        self.row_rects.push((
+            self.terrain_row(),
+            utils::text_rect(ctx, &terrain_text, terrain_point),
+        ));
+        row_y += 45.;
+
+        // Parameters of terrain are placed in two columns
+        let column_width = 260.;
+        for (index, title) in TERRAIN_PARAM_TITLES.iter().enumerate() {
+            let row = self.terrain_param_row(index);
+            let mut param_text = graphics::Text::new(
+                graphics::TextFragment::new(format!("{}: ", title))
+                    .font(world.font)
+                    .scale(graphics::Scale::uniform(24.0))
+                    .color(Color::new(1., 1., 1., 1.)),
+            );
+            param_text.add(
+                graphics::TextFragment::new(terrain_param_value(&self.terrain_params, index))
+                    .font(world.font)
+                    .scale(graphics::Scale::uniform(24.0))
+                    .color(self.row_color(row)),
+            );
+            let column = (index % 2) as f32;
+            let x = width / 2. - column_width + column * column_width;
+            let param_point = Point2::new(x.round(), row_y + 20. + (index / 2) as f32 * 32.);
+            graphics::draw(ctx, &param_text, (param_point,))?;
+            self.row_rects
+                .push((row, utils::text_rect(ctx, &param_text, param_point)));
+        }
+        row_y += (TERRAIN_PARAMS_COUNT / 2) as f32 * 32. + 10.;
+
+        // Mode and max power of wind are placed in the same columns
+        let wind_values = [
+            ("Wind", self.wind_params.mode.title().to_string()),
+            (
+                "Max wind",
+                format!("{:.0}", self.wind_params.max_power * 10.),
+            ),
+        ];
+        for (index, (title, value)) in wind_values.iter().enumerate() {
+            let row = self.wind_row() + index;
+            let mut wind_text = graphics::Text::new(
+                graphics::TextFragment::new(format!("{}: ", title))
+                    .font(world.font)
+                    .scale(graphics::Scale::uniform(24.0))
+                    .color(Color::new(1., 1., 1., 1.)),
+            );
+            wind_text.add(
+                graphics::TextFragment::new(value.as_str())
+                    .font(world.font)
+                    .scale(graphics::Scale::uniform(24.0))
+                    .color(self.row_color(row)),
+            );
+            let x = width / 2. - column_width + index as f32 * column_width;
+            let wind_point = Point2::new(x.round(), row_y + 20.);
+            graphics::draw(ctx, &wind_text, (wind_point,))?;
+            self.row_rects
+                .push((row, utils::text_rect(ctx, &wind_text, wind_point)));
+        }
+        row_y += 32.;
+
+        let seed_fragment = graphics::TextFragment::new("Seed: ")
+            .font(world.font)
+            .scale(graphics::Scale::uniform(30.0))
+            .color(Color::new(1., 1., 1., 1.));
+        let mut seed_text = graphics::Text::new(seed_fragment);
+        seed_text.add(
+            graphics::TextFragment::new(self.seed.as_str())
+                .font(world.font)
+                .scale(graphics::Scale::uniform(30.0))
+                .color(self.row_color(self.seed_row())),
+        );
+        let seed_x = (width - seed_text.width(ctx) as f32) / 2.;
+        let seed_point = Point2::new(seed_x.round(), row_y + 20.);
+        graphics::draw(ctx, &seed_text, (seed_point,))?;
+        self.row_rects.push((
+            self.seed_row(),
+            utils::text_rect(ctx, &seed_text, seed_point),
+        ));
+
+        let start_text = graphics::Text::new(
+            graphics::TextFragment::new("Start")
+                .font(world.font)
+                .scale(graphics::Scale::uniform(30.0))
+                .color(self.row_color(self.start_row())),
+        );
+        let start_x = (width - start_text.width(ctx) as f32) / 2.;
+        let start_point = Point2::new(start_x.round(), seed_point.y + 50.);
+        graphics::draw(ctx, &start_text, (start_point,))?;
+        self.row_rects.push((
+            self.start_row(),
+            utils::text_rect(ctx, &start_text, start_point),
+        ));
+
+        let selected_name = self.selected_seat().map(|seat| seat.name.clone());
+        let mut hints = Vec::new();
+        if self.has_duplicate_names() {
+            hints.push("Players can't have the same name".to_string());
+        }
+        if self.selected_row > self.terrain_row() && self.selected_row < self.wind_row() {
+            hints.push("Left/Right or click - change parameter of terrain".to_string());
+        }
+        if self.selected_row == self.wind_row() || self.selected_row == self.wind_power_row() {
+            hints.push("Left/Right or click - change wind".to_string());
+        }
+        if let Some(name) = selected_name {
+            hints.push(
+                "Type name, Left/Right or click - control, Space - color, Tab - profile"
+                    .to_string(),
+            );
+            if let Some(profile) = world.profiles.get(&name) {
+                hints.push(format!(
+                    "{}: matches {}, wins {}, rounds {}, kills {}, score {}",
+                    profile.name,
+                    profile.matches,
+                    profile.wins,
+                    profile.rounds,
+                    profile.kills,
+                    profile.score
+                ));
+            }
+        }
+        for (i, hint) in hints.iter().enumerate() {
+            let text = graphics::Text::new((hint.as_str(), world.font, 20.0));
+            let hint_point = Point2::new(dest_point.x, start_point.y + 60. + i as f32 * 30.);
+            graphics::draw(ctx, &text, (hint_point,))?;
+        }
 
         Ok(())
     }
 
-    fn input(&mut self, _world: &mut World, ev: input::Event, started: bool) {
+    fn input(&mut self, world: &mut World, ev: input::Event, started: bool) {
         if started {
             if let input::Event::Button(button) = ev {
                 match button {
+                    input::Button::Up => {
+                        self.selected_row = self.selected_row.saturating_sub(1);
+                    }
                     input::Button::Down => {
-                        self.count_of_players = max(self.count_of_players - 1, 2)
+                        self.selected_row = min(self.selected_row + 1, self.start_row());
                     }
-                    input::Button::Up => {
-                        self.count_of_players = min(self.count_of_players + 1, MAX_PLAYERS_COUNT)
+                    input::Button::Left => self.change_selected_value(false),
+                    input::Button::Right => self.change_selected_value(true),
+                    input::Button::Fire => {
+                        if let Some(seat) = self.selected_seat() {
+                            seat.hue = HueOffset::new(seat.hue + HUE_STEP).degrees();
+                        }
                     }
+                    input::Button::NextWeapon => self.select_next_profile(world),
                     _ => (),
                 }
             }
@@ -0,0 +1,183 @@
+use std::cmp::min;
+
+use ggez;
+use ggez::event::MouseButton;
+use ggez::graphics::{self, Color};
+use ggez_goodies::scene;
+
+use crate::settings::{FPS_LIMITS, MAX_VOLUME, WINDOW_SIZES};
+use crate::types::Point2;
+use crate::world::World;
+use crate::{input, scenes, utils, MAX_PLAYERS_COUNT};
+
+const VOLUME_STEP: u8 = 10;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Row {
+    WindowSize,
+    Fullscreen,
+    Volume,
+    Fps,
+    PlayersCount,
+}
+
+const ROWS: [(Row, &str); 5] = [
+    (Row::WindowSize, "Window size"),
+    (Row::Fullscreen, "Fullscreen"),
+    (Row::Volume, "Volume"),
+    (Row::Fps, "FPS limit"),
+    (Row::PlayersCount, "Default count of players"),
+];
+
+/// Returns item which is next or previous to current one in list.
+fn cycle<T: PartialEq + Copy>(items: &[T], current: T, forward: bool) -> T {
+    let index = items.iter().position(|&i| i == current).unwrap_or(0);
+    let index = if forward {
+        (index + 1) % items.len()
+    } else {
+        (index + items.len() - 1) % items.len()
+    };
+    items[index]
+}
+
+/// Shows settings of game and allows to change them. Changes are
+/// applied and saved immediately.
+pub struct SettingsScene {
+    current_row: usize,
+    // Bounds of rows on screen for mouse pointing, they are updated
+    // on every drawing.
+    row_rects: Vec<graphics::Rect>,
+}
+
+impl SettingsScene {
+    pub fn new(_ctx: &mut ggez::Context, _world: &mut World) -> Self {
+        SettingsScene {
+            current_row: 0,
+            row_rects: Vec::new(),
+        }
+    }
+
+    fn change_value(&mut self, world: &mut World, ctx: &mut ggez::Context, forward: bool) {
+        let settings = &mut world.settings;
+        match ROWS[self.current_row].0 {
+            Row::WindowSize => {
+                let size = (settings.window_width, settings.window_height);
+                let size = cycle(&WINDOW_SIZES, size, forward);
+                settings.window_width = size.0;
+                settings.window_height = size.1;
+            }
+            Row::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            Row::Volume => {
+                settings.volume = if forward {
+                    min(settings.volume + VOLUME_STEP, MAX_VOLUME)
+                } else {
+                    settings.volume.saturating_sub(VOLUME_STEP)
+                };
+            }
+            Row::Fps => settings.fps = cycle(&FPS_LIMITS, settings.fps, forward),
+            Row::PlayersCount => {
+                let counts: Vec<u8> = (2..=MAX_PLAYERS_COUNT).collect();
+                // Default count is applied when setup of match is opened
+                settings.players_count = cycle(&counts, settings.players_count, forward);
+            }
+        }
+
+        if let Err(error) = world.apply_settings(ctx) {
+            eprintln!("Can't apply settings: {}", error);
+        }
+        world.save_settings(ctx);
+    }
+
+    fn value_title(&self, world: &World, row: Row) -> String {
+        let settings = &world.settings;
+        match row {
+            Row::WindowSize => format!("{}x{}", settings.window_width, settings.window_height),
+            Row::Fullscreen if settings.fullscreen => "On".to_string(),
+            Row::Fullscreen => "Off".to_string(),
+            Row::Volume => format!("{}%", settings.volume),
+            Row::Fps if settings.fps == 0 => "Unlimited".to_string(),
+            Row::Fps => settings.fps.to_string(),
+            Row::PlayersCount => settings.players_count.to_string(),
+        }
+    }
+}
+
+impl scene::Scene<World, input::Event> for SettingsScene {
+    fn update(&mut self, world: &mut World, ctx: &mut ggez::Context) -> scenes::Switch {
+        if world.input.get_button_pressed(input::Button::Quit) {
+            return scene::SceneSwitch::Pop;
+        }
+
+        if world.input.get_button_pressed(input::Button::Up) {
+            self.current_row = self.current_row.saturating_sub(1);
+        }
+        if world.input.get_button_pressed(input::Button::Down) {
+            self.current_row = min(self.current_row + 1, ROWS.len() - 1);
+        }
+        if world.input.get_button_pressed(input::Button::Left) {
+            self.change_value(world, ctx, false);
+        }
+        if world.input.get_button_pressed(input::Button::Right)
+            || world.input.get_button_pressed(input::Button::Select)
+        {
+            self.change_value(world, ctx, true);
+        }
+
+        if world.mouse.moved {
+            if let Some(index) = utils::rect_at(&self.row_rects, world.mouse.position) {
+                self.current_row = index;
+            }
+        }
+        if let Some(button) = world.mouse.clicked {
+            if let Some(index) = utils::rect_at(&self.row_rects, world.mouse.position) {
+                self.current_row = index;
+                match button {
+                    MouseButton::Left => self.change_value(world, ctx, true),
+                    MouseButton::Right => self.change_value(world, ctx, false),
+                    _ => (),
+                }
+            }
+        }
+
+        scene::SceneSwitch::None
+    }
+
+    fn draw(&mut self, world: &mut World, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.2, 0.4, 1.0].into());
+
+        let text = graphics::Text::new(("Settings", world.font, 40.0));
+        graphics::draw(ctx, &text, (Point2::new(200.0, 100.0),))?;
+
+        self.row_rects.clear();
+        for (index, &(row, title)) in ROWS.iter().enumerate() {
+            let text_color = if index == self.current_row {
+                Color::new(1., 0., 0., 1.)
+            } else {
+                Color::new(1., 1., 1., 1.)
+            };
+            let line = format!("{}: {}", title, self.value_title(world, row));
+            let text_fragment = graphics::TextFragment::new(line)
+                .font(world.font)
+                .scale(graphics::Scale::uniform(30.0))
+                .color(text_color);
+            let text = graphics::Text::new(text_fragment);
+            let dest_point = Point2::new(200.0, index as f32 * 45.0 + 200.0);
+            graphics::draw(ctx, &text, (dest_point,))?;
+            self.row_rects
+                .push(utils::text_rect(ctx, &text, dest_point));
+        }
+
+        let hint = "Left/Right or click - change value, Esc - back";
+        let text = graphics::Text::new((hint, world.font, 20.0));
+        let y = ROWS.len() as f32 * 45.0 + 240.0;
+        graphics::draw(ctx, &text, (Point2::new(200.0, y),))?;
+
+        Ok(())
+    }
+
+    fn input(&mut self, _world: &mut World, _ev: input::Event, _started: bool) {}
+
+    fn name(&self) -> &str {
+        "SettingsScene"
+    }
+}
@@ -0,0 +1,153 @@
+use std::cmp::min;
+
+use ggez;
+use ggez::graphics::{self, Color};
+use ggez_goodies::scene;
+
+use crate::player::Control;
+use crate::shop::{self, PRICE_LIST};
+use crate::types::Point2;
+use crate::world::World;
+use crate::{ai, input, scenes};
+
+/// Shop where human players in turn buy weapons and items before round.
+/// Computer players make their purchases at once.
+pub struct ShopScene {
+    seed: u64,
+    // Index of player who is shopping now.
+    player_index: usize,
+    current_offer: usize,
+}
+
+impl ShopScene {
+    pub fn new(_ctx: &mut ggez::Context, world: &mut World, seed: u64) -> Self {
+        let mut rng = rand::thread_rng();
+        for player in world.players.iter_mut() {
+            if let Control::Computer(_) = player.control {
+                ai::go_shopping(player, &mut rng);
+            }
+        }
+
+        let mut scene = ShopScene {
+            seed,
+            player_index: 0,
+            current_offer: 0,
+        };
+        scene.skip_computer_players(world);
+        scene
+    }
+
+    fn skip_computer_players(&mut self, world: &World) {
+        while let Some(player) = world.players.get(self.player_index) {
+            if player.control == Control::Human {
+                break;
+            }
+            self.player_index += 1;
+        }
+    }
+
+    fn next_player(&mut self, world: &World) {
+        self.player_index += 1;
+        self.current_offer = 0;
+        self.skip_computer_players(world);
+    }
+}
+
+impl scene::Scene<World, input::Event> for ShopScene {
+    fn update(&mut self, world: &mut World, ctx: &mut ggez::Context) -> scenes::Switch {
+        if world.input.get_button_pressed(input::Button::Quit) {
+            return scene::SceneSwitch::Pop;
+        }
+
+        if world.input.get_button_pressed(input::Button::Select) {
+            self.next_player(world);
+        }
+
+        if self.player_index >= world.players.len() {
+            let game_play_scene = Box::new(
+                scenes::GamePlayScene::new(ctx, world, self.seed)
+                    .expect("Can't create GamePlayScene"),
+            );
+            return scene::SceneSwitch::Replace(game_play_scene);
+        }
+
+        scene::SceneSwitch::None
+    }
+
+    fn draw(&mut self, world: &mut World, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.3, 0.3, 1.0].into());
+
+        let player = match world.players.get(self.player_index) {
+            Some(player) => player,
+            None => return Ok(()),
+        };
+
+        let title = format!("Shop - {}", player.title(self.player_index as u8 + 1));
+        let text = graphics::Text::new((title, world.font, 40.0));
+        graphics::draw(ctx, &text, (Point2::new(200.0, 100.0),))?;
+
+        let money = format!("Money: {}", player.money);
+        let text = graphics::Text::new((money, world.font, 30.0));
+        graphics::draw(ctx, &text, (Point2::new(200.0, 160.0),))?;
+
+        for (index, offer) in PRICE_LIST.iter().enumerate() {
+            let text_color = if index == self.current_offer {
+                Color::new(1., 0., 0., 1.)
+            } else if offer.price > player.money {
+                Color::new(0.5, 0.5, 0.5, 1.)
+            } else {
+                Color::new(1., 1., 1., 1.)
+            };
+            let line = format!(
+                "{} x{} - ${} (have {})",
+                offer.goods.title(),
+                offer.quantity,
+                offer.price,
+                player.inventory.count(offer.goods)
+            );
+            let text_fragment = graphics::TextFragment::new(line)
+                .font(world.font)
+                .scale(graphics::Scale::uniform(30.0))
+                .color(text_color);
+            let text = graphics::Text::new(text_fragment);
+            let y = index as f32 * 40.0 + 240.0;
+            graphics::draw(ctx, &text, (Point2::new(200.0, y),))?;
+        }
+
+        let hint = "Right - buy, Left - sell, Enter - done";
+        let text = graphics::Text::new((hint, world.font, 20.0));
+        let y = PRICE_LIST.len() as f32 * 40.0 + 280.0;
+        graphics::draw(ctx, &text, (Point2::new(200.0, y),))?;
+
+        Ok(())
+    }
+
+    fn input(&mut self, world: &mut World, ev: input::Event, started: bool) {
+        if started {
+            if let input::Event::Button(button) = ev {
+                let player = match world.players.get_mut(self.player_index) {
+                    Some(player) => player,
+                    None => return,
+                };
+                let offer = &PRICE_LIST[self.current_offer];
+                match button {
+                    input::Button::Up => self.current_offer = self.current_offer.saturating_sub(1),
+                    input::Button::Down => {
+                        self.current_offer = min(self.current_offer + 1, PRICE_LIST.len() - 1)
+                    }
+                    input::Button::Right | input::Button::Fire => {
+                        shop::buy(player, offer);
+                    }
+                    input::Button::Left => {
+                        shop::sell(player, offer);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "ShopScene"
+    }
+}
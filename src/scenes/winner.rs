@@ -0,0 +1,68 @@
+use std::cmp::Reverse;
+
+use ggez;
+use ggez::graphics;
+use ggez_goodies::scene;
+
+use crate::game_match;
+use crate::types::Point2;
+use crate::world::World;
+use crate::{input, scenes};
+
+/// Shows winner of match and final standings of players.
+pub struct WinnerScene;
+
+impl scene::Scene<World, input::Event> for WinnerScene {
+    fn update(&mut self, world: &mut World, _ctx: &mut ggez::Context) -> scenes::Switch {
+        if world.input.get_button_pressed(input::Button::Quit)
+            || world.input.get_button_pressed(input::Button::Select)
+        {
+            return scene::SceneSwitch::Pop;
+        }
+
+        scene::SceneSwitch::None
+    }
+
+    fn draw(&mut self, world: &mut World, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.3, 0.3, 1.0].into());
+
+        let player_title = |number: u8| {
+            world
+                .players
+                .get(number as usize - 1)
+                .map_or_else(|| format!("Player {}", number), |p| p.title(number))
+        };
+        let winners = game_match::winners(&world.players);
+        let title = match winners.len() {
+            1 => format!("{} wins the match!", player_title(winners[0])),
+            _ => {
+                let names: Vec<String> = winners.iter().map(|&n| player_title(n)).collect();
+                format!("Draw between {}", names.join(", "))
+            }
+        };
+        let text = graphics::Text::new((title, world.font, 40.0));
+        graphics::draw(ctx, &text, (Point2::new(200.0, 100.0),))?;
+
+        let mut standings: Vec<(u8, u32)> = world
+            .players
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i as u8 + 1, p.score))
+            .collect();
+        standings.sort_by_key(|&(_, score)| Reverse(score));
+        for (place, &(number, score)) in standings.iter().enumerate() {
+            let line = format!("{}. {} - {} points", place + 1, player_title(number), score);
+            let text = graphics::Text::new((line, world.font, 30.0));
+            let y = place as f32 * 40.0 + 180.0;
+            graphics::draw(ctx, &text, (Point2::new(200.0, y),))?;
+        }
+
+        Ok(())
+    }
+
+    fn input(&mut self, _world: &mut World, _ev: input::Event, _started: bool) {}
+
+    fn name(&self) -> &str {
+        "WinnerScene"
+    }
+}
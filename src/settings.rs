@@ -0,0 +1,170 @@
+//! Settings of game which can be changed by user.
+//!
+//! Settings are stored in user's config file, one setting per line:
+//!
+//! ```text
+//! tank_war settings 1
+//! window 1024 768
+//! fullscreen false
+//! volume 80
+//! fps 60
+//! players 2
+//! terrain_params 4 2 2 0.5 0.5 0.5
+//! wind_params constant 10
+//! ```
+//!
+//! Value `0` of `fps` means that count of drawn frames is not limited.
+//! A `terrain_params` record contains default parameters of terrain
+//! for new match in the same order as in replay, a `wind_params` record
+//! contains default mode and max power of wind in the same way.
+use std::io::{self, BufRead, Write};
+
+use crate::landscape::TerrainParams;
+use crate::parse::parse_value;
+use crate::wind::WindParams;
+use crate::MAX_PLAYERS_COUNT;
+
+const HEADER: &str = "tank_war settings 1";
+
+/// Sizes of window which can be selected by user.
+pub const WINDOW_SIZES: [(u16, u16); 6] = [
+    (800, 600),
+    (1024, 768),
+    (1280, 720),
+    (1280, 1024),
+    (1600, 900),
+    (1920, 1080),
+];
+
+/// Limits of count of drawn frames per second, `0` means unlimited.
+pub const FPS_LIMITS: [u32; 5] = [30, 60, 120, 144, 0];
+
+pub const MAX_VOLUME: u8 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settings {
+    pub window_width: u16,
+    pub window_height: u16,
+    pub fullscreen: bool,
+    /// Volume of sounds in percents.
+    pub volume: u8,
+    /// Max count of drawn frames per second, `0` means unlimited.
+    pub fps: u32,
+    /// Count of players selected by default for new match.
+    pub players_count: u8,
+    pub terrain_params: TerrainParams,
+    pub wind_params: WindParams,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            window_width: 1024,
+            window_height: 768,
+            fullscreen: false,
+            volume: 80,
+            fps: 60,
+            players_count: 2,
+            terrain_params: TerrainParams::default(),
+            wind_params: WindParams::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(
+            writer,
+            "window {} {}",
+            self.window_width, self.window_height
+        )?;
+        writeln!(writer, "fullscreen {}", self.fullscreen)?;
+        writeln!(writer, "volume {}", self.volume)?;
+        writeln!(writer, "fps {}", self.fps)?;
+        writeln!(writer, "players {}", self.players_count)?;
+        writeln!(writer, "terrain_params {}", self.terrain_params)?;
+        writeln!(writer, "wind_params {}", self.wind_params)?;
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(reader: R) -> Result<Settings, String> {
+        let mut lines = reader.lines();
+        match lines.next() {
+            Some(Ok(ref line)) if line.trim() == HEADER => (),
+            _ => return Err("Unknown format of settings".into()),
+        }
+
+        let mut settings = Settings::default();
+        for line in lines {
+            let line = line.map_err(|e| e.to_string())?;
+            let mut values = line.split_whitespace();
+            let key = match values.next() {
+                Some(key) => key,
+                None => continue,
+            };
+            match key {
+                "window" => {
+                    settings.window_width = parse_value(&mut values, key)?;
+                    settings.window_height = parse_value(&mut values, key)?;
+                    if !WINDOW_SIZES.contains(&(settings.window_width, settings.window_height)) {
+                        return Err(format!("Unsupported size of window: {}", line));
+                    }
+                }
+                "fullscreen" => settings.fullscreen = parse_value(&mut values, key)?,
+                "volume" => {
+                    settings.volume = parse_value(&mut values, key)?;
+                    if settings.volume > MAX_VOLUME {
+                        return Err(format!("Invalid volume: {}", settings.volume));
+                    }
+                }
+                "fps" => settings.fps = parse_value(&mut values, key)?,
+                "players" => {
+                    settings.players_count = parse_value(&mut values, key)?;
+                    if !(2..=MAX_PLAYERS_COUNT).contains(&settings.players_count) {
+                        return Err(format!(
+                            "Invalid count of players: {}",
+                            settings.players_count
+                        ));
+                    }
+                }
+                "terrain_params" => settings.terrain_params = TerrainParams::parse(&mut values)?,
+                "wind_params" => settings.wind_params = WindParams::parse(&mut values)?,
+                _ => return Err(format!("Unknown setting '{}'", key)),
+            }
+        }
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wind::WindMode;
+
+    #[test]
+    fn test_write_and_read() {
+        let settings = Settings {
+            window_width: 1280,
+            window_height: 720,
+            fullscreen: true,
+            volume: 35,
+            fps: 0,
+            players_count: 4,
+            terrain_params: TerrainParams {
+                frequency: 4.5,
+                ..TerrainParams::default()
+            },
+            wind_params: WindParams {
+                mode: WindMode::Gusty,
+                max_power: 14.,
+            },
+        };
+        let mut buf: Vec<u8> = Vec::new();
+        settings.write(&mut buf).unwrap();
+        assert_eq!(Settings::read(&buf[..]).unwrap(), settings);
+
+        let text = format!("{}\nvolume 150\n", HEADER);
+        assert!(Settings::read(text.as_bytes()).is_err());
+    }
+}
@@ -2,6 +2,8 @@ use gfx::{self, *};
 use ggez;
 use ggez::graphics;
 
+use crate::types::HueOffset;
+
 gfx_defines! {
     constant GlowParams {
         glow_color: [f32; 3] = "glow_color",
@@ -46,26 +48,11 @@ pub fn load_hue_shader(ctx: &mut ggez::Context) -> ggez::GameResult<HueShader> {
     )
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct HueOffset(f32);
-
-impl HueOffset {
-    #[inline]
-    pub fn new<T: Into<f32>>(offset: T) -> Self {
-        let mut offset = offset.into() % 360.;
-        if offset < 0. {
-            // Offset is always positive
-            offset += 360.;
-        }
-        Self(offset)
-    }
-}
-
 impl Into<HueParams> for HueOffset {
     #[inline]
     fn into(self) -> HueParams {
         HueParams {
-            hue_offset: self.0 / 360.,
+            hue_offset: self.degrees() / 360.,
         }
     }
 }
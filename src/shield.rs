@@ -0,0 +1,101 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Distance from center of tank to the border of its shield.
+pub const SHIELD_RADIUS: f32 = 32.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShieldKind {
+    Light,
+    Heavy,
+    /// Bounces missiles back and absorbs a damage of explosions.
+    Deflector,
+}
+
+impl ShieldKind {
+    pub const ALL: [ShieldKind; 3] = [ShieldKind::Light, ShieldKind::Heavy, ShieldKind::Deflector];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ShieldKind::Light => "shield",
+            ShieldKind::Heavy => "heavy_shield",
+            ShieldKind::Deflector => "deflector",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            ShieldKind::Light => "Shield",
+            ShieldKind::Heavy => "Heavy Shield",
+            ShieldKind::Deflector => "Deflector",
+        }
+    }
+
+    /// Hit points of new shield.
+    pub fn max_strength(self) -> u8 {
+        match self {
+            ShieldKind::Light => 100,
+            ShieldKind::Heavy => 200,
+            ShieldKind::Deflector => 60,
+        }
+    }
+}
+
+impl fmt::Display for ShieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ShieldKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ShieldKind::ALL
+            .iter()
+            .find(|k| k.name() == s)
+            .cloned()
+            .ok_or_else(|| format!("Unknown shield: {}", s))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shield {
+    pub kind: ShieldKind,
+    pub strength: u8,
+}
+
+impl Shield {
+    pub fn new(kind: ShieldKind) -> Self {
+        Shield {
+            kind,
+            strength: kind.max_strength(),
+        }
+    }
+
+    #[inline]
+    pub fn is_deflector(self) -> bool {
+        self.kind == ShieldKind::Deflector
+    }
+
+    /// Decreases strength of shield and returns a damage which was not absorbed.
+    pub fn absorb(&mut self, damage: u8) -> u8 {
+        let absorbed = damage.min(self.strength);
+        self.strength -= absorbed;
+        damage - absorbed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_absorb() {
+        let mut shield = Shield::new(ShieldKind::Light);
+        assert_eq!(shield.absorb(30), 0);
+        assert_eq!(shield.strength, 70);
+        assert_eq!(shield.absorb(90), 20);
+        assert_eq!(shield.strength, 0);
+    }
+}
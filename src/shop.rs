@@ -0,0 +1,157 @@
+//! Buying of weapons and items between rounds.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::item::Item;
+use crate::player::Player;
+use crate::shield::ShieldKind;
+use crate::weapon::Weapon;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Goods {
+    Weapon(Weapon),
+    Item(Item),
+}
+
+impl Goods {
+    pub fn name(self) -> &'static str {
+        match self {
+            Goods::Weapon(weapon) => weapon.name(),
+            Goods::Item(item) => item.name(),
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Goods::Weapon(weapon) => weapon.title(),
+            Goods::Item(item) => item.title(),
+        }
+    }
+}
+
+impl fmt::Display for Goods {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Goods {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse()
+            .map(Goods::Weapon)
+            .or_else(|_| s.parse().map(Goods::Item))
+            .map_err(|_: String| format!("Unknown goods: {}", s))
+    }
+}
+
+/// A bundle of goods which can be bought in the shop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Offer {
+    pub goods: Goods,
+    pub quantity: u32,
+    pub price: u32,
+}
+
+pub const PRICE_LIST: [Offer; 10] = [
+    Offer {
+        goods: Goods::Weapon(Weapon::Missile),
+        quantity: 5,
+        price: 150,
+    },
+    Offer {
+        goods: Goods::Weapon(Weapon::Digger),
+        quantity: 3,
+        price: 150,
+    },
+    Offer {
+        goods: Goods::Weapon(Weapon::Roller),
+        quantity: 3,
+        price: 200,
+    },
+    Offer {
+        goods: Goods::Weapon(Weapon::BigMissile),
+        quantity: 3,
+        price: 200,
+    },
+    Offer {
+        goods: Goods::Weapon(Weapon::Mirv),
+        quantity: 2,
+        price: 350,
+    },
+    Offer {
+        goods: Goods::Weapon(Weapon::Nuke),
+        quantity: 1,
+        price: 400,
+    },
+    Offer {
+        goods: Goods::Item(Item::RepairKit),
+        quantity: 1,
+        price: 250,
+    },
+    Offer {
+        goods: Goods::Item(Item::Shield(ShieldKind::Light)),
+        quantity: 1,
+        price: 200,
+    },
+    Offer {
+        goods: Goods::Item(Item::Shield(ShieldKind::Heavy)),
+        quantity: 1,
+        price: 350,
+    },
+    Offer {
+        goods: Goods::Item(Item::Shield(ShieldKind::Deflector)),
+        quantity: 1,
+        price: 300,
+    },
+];
+
+/// Buys given offer if player has enough money.
+pub fn buy(player: &mut Player, offer: &Offer) -> bool {
+    if player.money < offer.price {
+        return false;
+    }
+    player.money -= offer.price;
+    player.inventory.add(offer.goods, offer.quantity);
+    true
+}
+
+/// Returns bundle of goods back to the shop and refunds its price.
+pub fn sell(player: &mut Player, offer: &Offer) -> bool {
+    if player.inventory.count(offer.goods) < offer.quantity {
+        return false;
+    }
+    player.inventory.remove(offer.goods, offer.quantity);
+    player.money = player.money.saturating_add(offer.price);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buy_and_sell() {
+        let mut player = Player {
+            money: 450,
+            ..Player::default()
+        };
+        let offer = &PRICE_LIST[3];
+        assert_eq!(offer.goods, Goods::Weapon(Weapon::BigMissile));
+
+        assert!(buy(&mut player, offer));
+        assert!(buy(&mut player, offer));
+        assert!(!buy(&mut player, offer));
+        assert_eq!(player.money, 50);
+        assert_eq!(player.inventory.count(offer.goods), 2 * offer.quantity);
+
+        assert!(sell(&mut player, offer));
+        assert_eq!(player.money, 250);
+        assert_eq!(player.inventory.count(offer.goods), offer.quantity);
+
+        let goods: Goods = "repair_kit".parse().unwrap();
+        assert_eq!(goods, Goods::Item(Item::RepairKit));
+        assert!(!sell(&mut player, &PRICE_LIST[6]));
+    }
+}
@@ -1,15 +1,14 @@
 use std::f32::consts::PI;
 
-use cgmath::{Basis2, Deg, Rotation, Rotation2};
-use ggez::{self, graphics, GameResult};
+use cgmath::{Basis2, Deg, MetricSpace, Rotation, Rotation2};
 
 use crate::ballistics::Ballistics;
-use crate::geometry::Ellipse;
-use crate::landscape::Landscape;
+use crate::geometry::{Ellipse, Rect};
+use crate::landscape::{Landscape, Material};
 use crate::missile::Missile;
-use crate::shaders;
-use crate::types::{Point2, Vector2};
-use crate::world::World;
+use crate::shield::{Shield, SHIELD_RADIUS};
+use crate::types::{HueOffset, Point2, Vector2};
+use crate::weapon::Weapon;
 use crate::G;
 
 const TANK_SIZE: f32 = 41.;
@@ -26,13 +25,15 @@ struct TankThrowing {
 #[derive(Debug, Clone)]
 pub struct Tank {
     pub player_number: u8,
-    pub rect: graphics::Rect,
+    pub rect: Rect,
     body_bounds: Vec<Ellipse>,
     gun_bounds: Vec<Ellipse>,
-    hue_offset: shaders::HueOffset,
+    pub hue_offset: HueOffset,
     pub angle: f32,
     pub power: f32,
+    pub weapon: Weapon,
     pub health: u8,
+    pub shield: Option<Shield>,
     pub dead: bool,
     throwing: Option<TankThrowing>,
 }
@@ -57,7 +58,7 @@ impl Tank {
         H: Into<f32>,
     {
         let top_left: Point2 = top_left.into();
-        let rect = graphics::Rect::new(top_left.x, top_left.y, TANK_SIZE, TANK_SIZE);
+        let rect = Rect::new(top_left.x, top_left.y, TANK_SIZE, TANK_SIZE);
         let body_bounds = vec![
             Ellipse::new((20.5, 26.), 9.5, 9.),    // top bound
             Ellipse::new((11., 33.5), 10., 6.5),   // left bound
@@ -68,20 +69,20 @@ impl Tank {
             Ellipse::new((20.5, 6.5), 2.5, 5.),
             Ellipse::new((20.5, 15.5), 2., 8.),
         ];
-        let mut tank = Tank {
+        Tank {
             player_number,
             rect,
             body_bounds,
             gun_bounds,
-            hue_offset: shaders::HueOffset::new(hue_offset),
+            hue_offset: HueOffset::new(hue_offset),
             angle: 0.0,
             power: 40.0,
+            weapon: Weapon::default(),
             health: 100,
+            shield: None,
             dead: false,
             throwing: None,
-        };
-        tank.throw_down(None);
-        tank
+        }
     }
 
     //    #[inline]
@@ -94,6 +95,12 @@ impl Tank {
         [self.rect.x, self.rect.y].into()
     }
 
+    /// Moves tank so that middle of its bottom is at given point.
+    pub fn set_bottom_center(&mut self, point: Point2) {
+        self.rect.x = point.x - TANK_SIZE / 2.;
+        self.rect.y = point.y - TANK_SIZE;
+    }
+
     #[inline]
     pub fn center(&self) -> Point2 {
         Point2::new(
@@ -108,8 +115,10 @@ impl Tank {
         self.center() + gun_vec
     }
 
-    pub fn shoot(&self, acceleration: Vector2) -> Missile {
+    pub fn shoot(&self, now: f32, acceleration: Vector2) -> Missile {
         Missile::new(
+            self.weapon,
+            now,
             self.gun_barrel_pos(),
             self.angle,
             self.power * POWER_SCALE,
@@ -117,7 +126,7 @@ impl Tank {
         )
     }
 
-    pub fn update(&mut self, landscape: &mut Landscape) -> TankState {
+    pub fn update(&mut self, now: f32, landscape: &mut Landscape) -> TankState {
         let mut start_height = self.rect.bottom() - 1.;
 
         if let Some(throwing) = self.throwing.as_mut() {
@@ -127,7 +136,7 @@ impl Tank {
             let max_empty_count = (0.3 * tank_width).round() as usize;
             let mut offset: f32 = 0.0;
 
-            for (x, y) in throwing.ballistics.positions_iter(None, None) {
+            for (x, y) in throwing.ballistics.positions_iter(now, None) {
                 if y >= height {
                     self.throwing = None;
                     break;
@@ -135,12 +144,17 @@ impl Tank {
 
                 let pixels_under_tank = landscape.get_pixels_line_mut((x, y), tank_width as u16);
                 if let Some(pixels) = pixels_under_tank {
+                    if pixels.contains(&(Material::Bedrock as u8)) {
+                        // Bedrock can't be pushed through
+                        self.throwing = None;
+                        break;
+                    }
                     let empty_count = bytecount::count(pixels, 0);
                     if empty_count > max_empty_count {
                         if empty_count < tank_width as usize {
                             // Landscape under tank is not empty - clear it
                             pixels.iter_mut().for_each(|c| *c = 0);
-                            landscape.set_changed();
+                            landscape.set_rect_changed(x, y, tank_width as i32, 1);
                         }
                         // Get down tank
                         offset += 1.0;
@@ -165,7 +179,7 @@ impl Tank {
         }
     }
 
-    pub fn throw_down(&mut self, top: Option<f32>) {
+    pub fn throw_down(&mut self, now: f32, top: Option<f32>) {
         if let Some(top) = top {
             self.rect.y = top;
         }
@@ -173,34 +187,41 @@ impl Tank {
         let start_height = self.rect.bottom() - 1.;
         self.throwing = Some(TankThrowing {
             start_height,
-            ballistics: Ballistics::new([self.rect.x, start_height], [0., 0.], [0., G])
+            ballistics: Ballistics::new(now, [self.rect.x, start_height], [0., 0.], [0., G])
                 .time_scale(TIME_SCALE),
         });
     }
 
-    pub fn draw(&self, ctx: &mut ggez::Context, world: &World) -> GameResult {
-        let _lock = graphics::use_shader(ctx, &world.hue_shader);
-        world.hue_shader.send(ctx, self.hue_offset.into())?;
-
-        let pos = self.top_left();
-        let gun_params = graphics::DrawParam::new()
-            .dest(pos + Vector2::new(20.5, 20.5))
-            .offset(Point2::new(0.5, 0.5))
-            .rotation(std::f32::consts::PI * self.angle / 180.0);
-        graphics::draw(ctx, &world.gun_image, gun_params)?;
-        let tank_params = graphics::DrawParam::new().dest(pos);
-        graphics::draw(ctx, &world.tank_image, tank_params)?;
-        Ok(())
-    }
-
     #[inline]
     pub fn damage(&mut self, v: u8) {
         self.health = self.health.saturating_sub(v);
     }
 
+    /// Damages shield of tank at first and then the tank itself.
+    pub fn take_hit(&mut self, v: u8) {
+        let mut rest = v;
+        if let Some(ref mut shield) = self.shield {
+            rest = shield.absorb(v);
+            if shield.strength == 0 {
+                self.shield = None;
+            }
+        }
+        self.damage(rest);
+    }
+
+    /// Returns `true` if tank has a deflector and given point locates inside of it.
+    pub fn deflector_contains(&self, point: Point2) -> bool {
+        match self.shield {
+            Some(shield) if shield.is_deflector() => {
+                self.center().distance2(point) < SHIELD_RADIUS * SHIELD_RADIUS
+            }
+            _ => false,
+        }
+    }
+
     #[inline]
-    pub fn body_rect(&self) -> graphics::Rect {
-        graphics::Rect {
+    pub fn body_rect(&self) -> Rect {
+        Rect {
             x: self.rect.x,
             y: self.rect.y + 20.,
             w: self.rect.w,
@@ -0,0 +1,112 @@
+//! Textures of materials of landscape.
+//!
+//! Texture is a PNG image which is tiled over all pixels of one material.
+//! Textures are optional, landscape uses color of material if its
+//! texture isn't loaded.
+use std::io::Read;
+
+use crate::landscape::Material;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Texture {
+    width: u16,
+    height: u16,
+    // RGB pixels row by row.
+    rgb: Vec<u8>,
+}
+
+impl Texture {
+    /// Creates texture from RGBA pixels of image, alpha is ignored.
+    pub fn from_rgba(width: u16, height: u16, rgba: &[u8]) -> Result<Texture, String> {
+        if width == 0 || height == 0 {
+            return Err("Texture is empty".into());
+        }
+        if rgba.len() != width as usize * height as usize * 4 {
+            return Err("Size of pixels of texture is invalid".into());
+        }
+        let rgb = rgba
+            .chunks(4)
+            .flat_map(|pixel| pixel[..3].iter().cloned())
+            .collect();
+        Ok(Texture { width, height, rgb })
+    }
+
+    /// Reads texture from PNG image.
+    pub fn read_png<R: Read>(reader: R) -> Result<Texture, String> {
+        let (width, height, rgba) = decode_png(reader)?;
+        Texture::from_rgba(width, height, &rgba)
+    }
+
+    /// Returns color of texture tiled over landscape at given point
+    /// of landscape.
+    #[inline]
+    pub fn color(&self, x: usize, y: usize) -> [u8; 3] {
+        let x = x % self.width as usize;
+        let y = y % self.height as usize;
+        let index = (y * self.width as usize + x) * 3;
+        [self.rgb[index], self.rgb[index + 1], self.rgb[index + 2]]
+    }
+}
+
+/// Decodes PNG image, returns its width, height and RGBA pixels
+/// row by row.
+pub(crate) fn decode_png<R: Read>(mut reader: R) -> Result<(u16, u16, Vec<u8>), String> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).map_err(|e| e.to_string())?;
+    let image = image::load_from_memory_with_format(&data, image::ImageFormat::PNG)
+        .map_err(|e| format!("Can't decode image: {}", e))?
+        .to_rgba();
+    let (width, height) = image.dimensions();
+    if width > u32::from(u16::max_value()) || height > u32::from(u16::max_value()) {
+        return Err("Image is too big".into());
+    }
+    Ok((width as u16, height as u16, image.into_raw()))
+}
+
+/// Textures of materials of landscape.
+#[derive(Debug, Clone, Default)]
+pub struct Textures {
+    textures: Vec<(Material, Texture)>,
+}
+
+impl Textures {
+    pub fn set(&mut self, material: Material, texture: Texture) {
+        self.textures.retain(|(m, _)| *m != material);
+        self.textures.push((material, texture));
+    }
+
+    pub fn get(&self, material: Material) -> Option<&Texture> {
+        self.textures
+            .iter()
+            .find(|(m, _)| *m == material)
+            .map(|(_, texture)| texture)
+    }
+
+    /// Returns color of given material at given point of landscape.
+    #[inline]
+    pub fn color(&self, material: Material, x: usize, y: usize) -> [u8; 3] {
+        match self.get(material) {
+            Some(texture) => texture.color(x, y),
+            None => material.color(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiled_texture() {
+        let rgba = [10, 20, 30, 255, 40, 50, 60, 0];
+        let texture = Texture::from_rgba(2, 1, &rgba).unwrap();
+        assert_eq!(texture.color(0, 0), [10, 20, 30]);
+        assert_eq!(texture.color(3, 5), [40, 50, 60]);
+        assert!(Texture::from_rgba(2, 2, &rgba).is_err());
+
+        let mut textures = Textures::default();
+        textures.set(Material::Rock, texture);
+        assert_eq!(textures.color(Material::Rock, 2, 0), [10, 20, 30]);
+        assert_eq!(textures.color(Material::Dirt, 2, 0), Material::Dirt.color());
+    }
+}
@@ -4,3 +4,24 @@ use cgmath;
 pub type Point2 = cgmath::Point2<f32>;
 /// A 2 dimensional vector representing an offset of a location
 pub type Vector2 = cgmath::Vector2<f32>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct HueOffset(f32);
+
+impl HueOffset {
+    #[inline]
+    pub fn new<T: Into<f32>>(offset: T) -> Self {
+        let mut offset = offset.into() % 360.;
+        if offset < 0. {
+            // Offset is always positive
+            offset += 360.;
+        }
+        Self(offset)
+    }
+
+    /// Offset in degrees.
+    #[inline]
+    pub fn degrees(self) -> f32 {
+        self.0
+    }
+}
@@ -1,7 +1,25 @@
 use ggez::graphics;
 
+use crate::types::Point2;
+
 #[inline]
 pub(crate) fn screen_size(ctx: &mut ggez::Context) -> (f32, f32) {
     let screen_rect = graphics::screen_coordinates(ctx);
     (screen_rect.w, screen_rect.h)
 }
+
+/// Returns bounds of text drawn at given point.
+pub(crate) fn text_rect(
+    ctx: &mut ggez::Context,
+    text: &graphics::Text,
+    point: Point2,
+) -> graphics::Rect {
+    let (width, height) = text.dimensions(ctx);
+    graphics::Rect::new(point.x, point.y, width as f32, height as f32)
+}
+
+/// Returns index of rectangle which contains given point.
+pub(crate) fn rect_at(rects: &[graphics::Rect], point: Option<Point2>) -> Option<usize> {
+    let point = point?;
+    rects.iter().position(|rect| rect.contains(point))
+}
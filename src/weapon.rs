@@ -0,0 +1,133 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Weapon {
+    BabyMissile,
+    Missile,
+    Digger,
+    Roller,
+    BigMissile,
+    Mirv,
+    Nuke,
+}
+
+/// What a missile does when it hits landscape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Impact {
+    /// Explodes at once.
+    Explosion,
+    /// Rolls along the surface of landscape until it stops and then explodes.
+    Rolling,
+    /// Digs a tunnel in direction of flight without explosion.
+    Tunnel,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Weapon::BabyMissile
+    }
+}
+
+impl Weapon {
+    /// All weapons in order of their power.
+    pub const ALL: [Weapon; 7] = [
+        Weapon::BabyMissile,
+        Weapon::Missile,
+        Weapon::Digger,
+        Weapon::Roller,
+        Weapon::BigMissile,
+        Weapon::Mirv,
+        Weapon::Nuke,
+    ];
+
+    /// Unlimited weapon can be used without buying it.
+    #[inline]
+    pub fn is_unlimited(self) -> bool {
+        self == Weapon::BabyMissile
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Weapon::BabyMissile => "baby_missile",
+            Weapon::Missile => "missile",
+            Weapon::Digger => "digger",
+            Weapon::Roller => "roller",
+            Weapon::BigMissile => "big_missile",
+            Weapon::Mirv => "mirv",
+            Weapon::Nuke => "nuke",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Weapon::BabyMissile => "Baby Missile",
+            Weapon::Missile => "Missile",
+            Weapon::Digger => "Digger",
+            Weapon::Roller => "Roller",
+            Weapon::BigMissile => "Big Missile",
+            Weapon::Mirv => "MIRV",
+            Weapon::Nuke => "Nuke",
+        }
+    }
+
+    /// Radius of explosion or half of width of tunnel.
+    pub fn explosion_radius(self) -> f32 {
+        match self {
+            Weapon::BabyMissile => 50.0,
+            Weapon::Missile => 65.0,
+            Weapon::Digger => 8.0,
+            Weapon::Roller => 55.0,
+            Weapon::BigMissile => 80.0,
+            Weapon::Mirv => 45.0,
+            Weapon::Nuke => 150.0,
+        }
+    }
+
+    /// Damage of tank which is fully covered by explosion.
+    pub fn damage(self) -> f32 {
+        match self {
+            Weapon::BabyMissile => 100.0,
+            Weapon::Missile => 110.0,
+            Weapon::Digger => 0.0,
+            Weapon::Roller => 110.0,
+            Weapon::BigMissile => 130.0,
+            Weapon::Mirv => 80.0,
+            Weapon::Nuke => 200.0,
+        }
+    }
+
+    pub fn impact(self) -> Impact {
+        match self {
+            Weapon::Digger => Impact::Tunnel,
+            Weapon::Roller => Impact::Rolling,
+            _ => Impact::Explosion,
+        }
+    }
+
+    /// Count of warheads into which missile splits at the top of its trajectory.
+    pub fn warheads_count(self) -> usize {
+        match self {
+            Weapon::Mirv => 5,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for Weapon {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Weapon {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Weapon::ALL
+            .iter()
+            .find(|w| w.name() == s)
+            .cloned()
+            .ok_or_else(|| format!("Unknown weapon: {}", s))
+    }
+}
@@ -0,0 +1,240 @@
+//! Wind which pushes missiles horizontally.
+//!
+//! Changes of wind between turns and gusts during flight of missiles
+//! are calculated from seed of round and number of turn, so they are
+//! the same in every replay and in restored match.
+use std::f32::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use rand::Rng;
+use rand_pcg::Pcg32;
+
+use crate::parse::parse_value;
+
+/// Max change of wind between two turns relative to max power of wind.
+const MAX_TURN_CHANGE: f32 = 0.4;
+/// Max power of gusts relative to max power of wind.
+const GUST_STRENGTH: f32 = 0.5;
+/// Periods of two waves of gusts in seconds of game time and their
+/// parts of power of gusts.
+const GUST_WAVES: [(f32, f32); 2] = [(2.3, 0.6), (0.9, 0.4)];
+// Sequences of random numbers used by wind differ from ones used by round.
+const TURN_SALT: u64 = 0x5749_4e44;
+const GUST_SALT: u64 = 0x4755_5354;
+
+/// How wind behaves during round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindMode {
+    /// There is no wind at all.
+    Calm,
+    /// Wind is chosen at start of round and doesn't change.
+    Constant,
+    /// Wind changes at start of every turn.
+    Changing,
+    /// Wind changes at start of every turn and gusts during flight
+    /// of missiles.
+    Gusty,
+}
+
+impl Default for WindMode {
+    fn default() -> Self {
+        WindMode::Constant
+    }
+}
+
+impl WindMode {
+    pub const ALL: [WindMode; 4] = [
+        WindMode::Calm,
+        WindMode::Constant,
+        WindMode::Changing,
+        WindMode::Gusty,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WindMode::Calm => "calm",
+            WindMode::Constant => "constant",
+            WindMode::Changing => "changing",
+            WindMode::Gusty => "gusty",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            WindMode::Calm => "Calm",
+            WindMode::Constant => "Constant",
+            WindMode::Changing => "Changes every turn",
+            WindMode::Gusty => "Gusty",
+        }
+    }
+}
+
+impl fmt::Display for WindMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for WindMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WindMode::ALL
+            .iter()
+            .find(|m| m.name() == s)
+            .cloned()
+            .ok_or_else(|| format!("Unknown mode of wind: {}", s))
+    }
+}
+
+/// Mode of wind and limit of its power selected for match.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindParams {
+    pub mode: WindMode,
+    /// Max power of wind without gusts.
+    pub max_power: f32,
+}
+
+impl Default for WindParams {
+    fn default() -> Self {
+        WindParams {
+            mode: WindMode::default(),
+            max_power: Self::DEFAULT_MAX_POWER,
+        }
+    }
+}
+
+impl WindParams {
+    pub const DEFAULT_MAX_POWER: f32 = 10.;
+    pub const MAX_POWER: f32 = 20.;
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=Self::MAX_POWER).contains(&self.max_power) {
+            return Err(format!("Invalid max power of wind: {}", self.max_power));
+        }
+        Ok(())
+    }
+
+    /// Parses mode and max power of wind written by `Display`.
+    pub fn parse<'a, I>(values: &mut I) -> Result<Self, String>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let params = WindParams {
+            mode: parse_value(values, "mode of wind")?,
+            max_power: parse_value(values, "max power of wind")?,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Returns power of wind at start of round for given power drawn
+    /// for default limit of wind.
+    pub fn initial_power(&self, drawn_power: f32) -> f32 {
+        match self.mode {
+            WindMode::Calm => 0.,
+            _ => round_power(drawn_power * (self.max_power / Self::DEFAULT_MAX_POWER)),
+        }
+    }
+
+    /// Returns power of wind at start of given turn of round with
+    /// given seed if wind had given power in previous turn.
+    pub fn turn_power(&self, power: f32, seed: u64, turn: usize) -> f32 {
+        match self.mode {
+            WindMode::Calm | WindMode::Constant => power,
+            WindMode::Changing | WindMode::Gusty => {
+                let mut rng = Pcg32::new(seed ^ TURN_SALT, turn as u64);
+                let change = rng.gen_range(-1.0_f32, 1.0_f32) * MAX_TURN_CHANGE * self.max_power;
+                round_power((power + change).max(-self.max_power).min(self.max_power))
+            }
+        }
+    }
+
+    /// Returns power of gust at given game time during given turn
+    /// of round with given seed.
+    pub fn gust(&self, seed: u64, turn: usize, now: f32) -> f32 {
+        if self.mode != WindMode::Gusty {
+            return 0.;
+        }
+        let mut rng = Pcg32::new(seed ^ GUST_SALT, turn as u64);
+        let wave: f32 = GUST_WAVES
+            .iter()
+            .map(|&(period, part)| {
+                let phase = rng.gen_range(0., 2. * PI);
+                part * (2. * PI * now / period + phase).sin()
+            })
+            .sum();
+        wave * GUST_STRENGTH * self.max_power
+    }
+}
+
+/// Rounds power of wind to one decimal place.
+#[inline]
+fn round_power(power: f32) -> f32 {
+    (power * 10.0).round() / 10.0
+}
+
+impl fmt::Display for WindParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.mode, self.max_power)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wind_params() {
+        let params = WindParams {
+            mode: WindMode::Gusty,
+            max_power: 7.5,
+        };
+        let text = params.to_string();
+        assert_eq!(WindParams::parse(&mut text.split_whitespace()), Ok(params));
+        assert!(WindParams::parse(&mut "stormy 10".split_whitespace()).is_err());
+        assert!(WindParams::parse(&mut "gusty 25".split_whitespace()).is_err());
+    }
+
+    #[test]
+    fn test_wind_stays_within_limits() {
+        let calm = WindParams {
+            mode: WindMode::Calm,
+            max_power: 10.,
+        };
+        assert_eq!(calm.initial_power(-7.3), 0.);
+        assert_eq!(WindParams::default().initial_power(-7.3), -7.3);
+        assert_eq!(WindParams::default().turn_power(-7.3, 1, 2), -7.3);
+
+        let params = WindParams {
+            mode: WindMode::Changing,
+            max_power: 5.,
+        };
+        let mut power = params.initial_power(-10.);
+        assert_eq!(power, -5.);
+        let mut changed = false;
+        for turn in 1..100 {
+            let next_power = params.turn_power(power, 42, turn);
+            assert_eq!(next_power, params.turn_power(power, 42, turn));
+            assert!(next_power.abs() <= params.max_power);
+            assert!((next_power - power).abs() <= MAX_TURN_CHANGE * params.max_power + 0.05);
+            changed |= next_power != power;
+            power = next_power;
+        }
+        assert!(changed);
+        assert_eq!(params.gust(42, 1, 0.5), 0.);
+
+        let gusty = WindParams {
+            mode: WindMode::Gusty,
+            ..params
+        };
+        let gusts: Vec<f32> = (0..100)
+            .map(|t| gusty.gust(42, 1, t as f32 / 10.))
+            .collect();
+        assert!(gusts
+            .iter()
+            .all(|g| g.abs() <= GUST_STRENGTH * gusty.max_power));
+        assert!(gusts.iter().any(|&g| g != gusts[0]));
+    }
+}
@@ -1,13 +1,40 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
 use ggez;
-use ggez::{audio, graphics};
+use ggez::audio::SoundSource;
+use ggez::conf::{FullscreenType, WindowMode};
+use ggez::event::KeyCode;
+use ggez::{audio, filesystem, graphics};
+
+use crate::controls::Controls;
 
-use crate::player::Player;
+use crate::game_match::Match;
+use crate::landscape::Material;
+use crate::map::Map;
+use crate::player::{Control, Player};
+use crate::profile::Profiles;
+use crate::settings::{self, Settings};
+use crate::shield::SHIELD_RADIUS;
+use crate::texture::{Texture, Textures};
 use crate::types::Point2;
 use crate::{input, shaders, utils, MAX_PLAYERS_COUNT};
 use std::cmp::{max, min};
 
 pub struct World {
     pub input: input::State,
+    /// Characters typed by user since last update.
+    pub text_input: String,
+    /// Last key pressed by user since last update.
+    pub last_key: Option<KeyCode>,
+    pub controls: Controls,
+    /// Number of player whose own keys are used now.
+    /// It is set by scene during every update.
+    pub active_player: Option<u8>,
+    pub mouse: input::Mouse,
+    pub stick: input::Stick,
+    pub settings: Settings,
     pub tank_image: graphics::Image,
     pub gun_image: graphics::Image,
     pub font: graphics::Font,
@@ -16,28 +43,166 @@ pub struct World {
     pub borders_mesh: graphics::Mesh,
     pub missile_mesh: graphics::Mesh,
     pub explosion_mesh: graphics::Mesh,
+    pub shield_mesh: graphics::Mesh,
     pub glow_shader: shaders::GlowShader,
     pub hue_shader: shaders::HueShader,
     pub players: Vec<Player>,
+    pub game_match: Match,
+    pub profiles: Profiles,
+    /// Custom maps from assets sorted by their names.
+    pub maps: Vec<Map>,
+    pub landscape_textures: Textures,
+}
+
+/// Returns path to config file with keys of buttons.
+pub fn controls_path(ctx: &ggez::Context) -> PathBuf {
+    filesystem::user_config_dir(ctx).join("controls")
+}
+
+/// Reads file with given function. Returns `None` if file doesn't exist
+/// or it can't be read.
+fn load_file<T, F>(path: &Path, what: &str, read: F) -> Option<T>
+where
+    F: FnOnce(io::BufReader<fs::File>) -> Result<T, String>,
+{
+    if !path.exists() {
+        return None;
+    }
+    let result = fs::File::open(path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| read(io::BufReader::new(file)));
+    match result {
+        Ok(value) => Some(value),
+        Err(error) => {
+            eprintln!("Can't load {} from {}: {}", what, path.display(), error);
+            None
+        }
+    }
+}
+
+/// Writes file with given function, creates its directory if needed.
+fn save_file<F>(path: &Path, what: &str, write: F)
+where
+    F: FnOnce(io::BufWriter<fs::File>) -> io::Result<()>,
+{
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let result = fs::create_dir_all(dir)
+        .and_then(|_| fs::File::create(path))
+        .and_then(|file| write(io::BufWriter::new(file)));
+    if let Err(error) = result {
+        eprintln!("Can't save {} into {}: {}", what, path.display(), error);
+    }
+}
+
+/// Loads keys of buttons or writes default ones if config file doesn't exist.
+fn load_controls(ctx: &ggez::Context) -> Controls {
+    let path = controls_path(ctx);
+    if !path.exists() {
+        let controls = Controls::default();
+        save_controls(ctx, &controls);
+        return controls;
+    }
+    load_file(&path, "controls", Controls::read).unwrap_or_default()
+}
+
+fn save_controls(ctx: &ggez::Context, controls: &Controls) {
+    save_file(&controls_path(ctx), "controls", |file| controls.write(file));
+}
+
+/// Returns path to config file with settings of game.
+pub fn settings_path(ctx: &ggez::Context) -> PathBuf {
+    filesystem::user_config_dir(ctx).join("settings")
+}
+
+fn load_settings(ctx: &ggez::Context) -> Settings {
+    load_file(&settings_path(ctx), "settings", Settings::read).unwrap_or_default()
+}
+
+/// Loads custom maps from PNG images in `maps` directory of assets.
+fn load_maps(ctx: &mut ggez::Context) -> Vec<Map> {
+    let paths: Vec<PathBuf> = match filesystem::read_dir(ctx, "/maps") {
+        Ok(paths) => paths.collect(),
+        // There are no custom maps
+        Err(_) => return Vec::new(),
+    };
+    let mut maps = Vec::new();
+    for path in paths {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => (),
+            _ => continue,
+        }
+        let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+        let result = filesystem::open(ctx, &path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| Map::read_png(name, file));
+        match result {
+            Ok(map) => maps.push(map),
+            Err(error) => eprintln!("Can't load map from {}: {}", path.display(), error),
+        }
+    }
+    maps.sort_by(|a, b| a.name().cmp(b.name()));
+    maps
+}
+
+/// Loads textures of materials of landscape which exist in sprites
+/// of assets, e.g. `texture_rock.png`.
+fn load_landscape_textures(ctx: &mut ggez::Context) -> Textures {
+    let mut textures = Textures::default();
+    for &material in Material::ALL[1..].iter() {
+        let path = format!("/sprites/texture_{}.png", material.name());
+        if !filesystem::exists(ctx, &path) {
+            continue;
+        }
+        let result = filesystem::open(ctx, &path)
+            .map_err(|e| e.to_string())
+            .and_then(Texture::read_png);
+        match result {
+            Ok(texture) => textures.set(material, texture),
+            Err(error) => eprintln!("Can't load texture from {}: {}", path, error),
+        }
+    }
+    textures
+}
+
+fn create_borders_mesh(ctx: &mut ggez::Context) -> ggez::GameResult<graphics::Mesh> {
+    let (width, height) = utils::screen_size(ctx);
+    graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::stroke(1.0),
+        graphics::Rect::new(0.0, 0.0, width - 1.0, height - 1.0),
+        graphics::Color::from_rgb(255, 255, 255),
+    )
+}
+
+/// Returns path to file with profiles of players.
+pub fn profiles_path(ctx: &ggez::Context) -> PathBuf {
+    filesystem::user_data_dir(ctx).join("profiles")
+}
+
+fn load_profiles(ctx: &ggez::Context) -> Profiles {
+    load_file(&profiles_path(ctx), "profiles", Profiles::read).unwrap_or_default()
 }
 
 impl World {
     pub fn new(ctx: &mut ggez::Context) -> ggez::GameResult<Self> {
-        let (width, height) = utils::screen_size(ctx);
-
         let mut world = Self {
             input: input::State::new(),
+            text_input: String::new(),
+            last_key: None,
+            controls: load_controls(ctx),
+            active_player: None,
+            mouse: input::Mouse::default(),
+            stick: input::Stick::default(),
+            settings: load_settings(ctx),
             tank_image: graphics::Image::new(ctx, "/sprites/tank.png")?,
             gun_image: graphics::Image::new(ctx, "/sprites/gun.png")?,
             font: graphics::Font::new(ctx, "/fonts/DejaVuSerif.ttf")?,
             tank_fire_sound: audio::Source::new(ctx, "/sounds/cannon_fire.ogg")?,
             explosion_sound: audio::Source::new(ctx, "/sounds/explosion1.ogg")?,
-            borders_mesh: graphics::Mesh::new_rectangle(
-                ctx,
-                graphics::DrawMode::stroke(1.0),
-                graphics::Rect::new(0.0, 0.0, width - 1.0, height - 1.0),
-                graphics::Color::from_rgb(255, 255, 255),
-            )?,
+            borders_mesh: create_borders_mesh(ctx)?,
             missile_mesh: graphics::Mesh::new_circle(
                 ctx,
                 graphics::DrawMode::fill(),
@@ -54,11 +219,26 @@ impl World {
                 0.5,
                 graphics::WHITE,
             )?,
+            shield_mesh: graphics::Mesh::new_circle(
+                ctx,
+                graphics::DrawMode::stroke(2.0),
+                Point2::new(0.0, 0.0),
+                SHIELD_RADIUS,
+                0.5,
+                graphics::WHITE,
+            )?,
             glow_shader: shaders::load_glow_shader(ctx)?,
             hue_shader: shaders::load_hue_shader(ctx)?,
             players: Vec::with_capacity(MAX_PLAYERS_COUNT as usize),
+            game_match: Match::default(),
+            profiles: load_profiles(ctx),
+            maps: load_maps(ctx),
+            landscape_textures: load_landscape_textures(ctx),
         };
-        world.create_players_count(2);
+        world.create_players_count(world.settings.players_count);
+        world.game_match.terrain_params = world.settings.terrain_params;
+        world.game_match.wind_params = world.settings.wind_params;
+        world.apply_settings(ctx)?;
 
         Ok(world)
     }
@@ -67,11 +247,56 @@ impl World {
         let count = min(max(count, 2), MAX_PLAYERS_COUNT);
         self.players.clear();
         for _ in 0..count {
-            self.players.push(Player { money: 0 });
+            self.players.push(Player::default());
+        }
+    }
+
+    pub fn create_players(&mut self, controls: &[Control]) {
+        self.create_players_count(controls.len() as u8);
+        for (player, &control) in self.players.iter_mut().zip(controls) {
+            player.control = control;
         }
     }
 
     pub fn players_count(&self) -> u8 {
         self.players.len() as u8
     }
+
+    /// Applies settings of window and volume of sounds.
+    pub fn apply_settings(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
+        let width = f32::from(self.settings.window_width);
+        let height = f32::from(self.settings.window_height);
+        let fullscreen_type = if self.settings.fullscreen {
+            FullscreenType::True
+        } else {
+            FullscreenType::Windowed
+        };
+        let mode = WindowMode::default()
+            .dimensions(width, height)
+            .fullscreen_type(fullscreen_type);
+        graphics::set_mode(ctx, mode)?;
+        graphics::set_screen_coordinates(ctx, graphics::Rect::new(0.0, 0.0, width, height))?;
+        self.borders_mesh = create_borders_mesh(ctx)?;
+
+        let volume = f32::from(self.settings.volume) / f32::from(settings::MAX_VOLUME);
+        self.tank_fire_sound.set_volume(volume);
+        self.explosion_sound.set_volume(volume);
+        Ok(())
+    }
+
+    pub fn save_settings(&self, ctx: &ggez::Context) {
+        save_file(&settings_path(ctx), "settings", |file| {
+            self.settings.write(file)
+        });
+    }
+
+    pub fn save_controls(&self, ctx: &ggez::Context) {
+        save_controls(ctx, &self.controls);
+    }
+
+    pub fn save_profiles(&self, ctx: &ggez::Context) {
+        save_file(&profiles_path(ctx), "profiles", |file| {
+            self.profiles.write(file)
+        });
+    }
 }